@@ -0,0 +1,190 @@
+//! Parsing of the program's command-line arguments.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+
+/// The usage message shown for `--help` and invalid arguments.
+pub const USAGE: &str = "\
+Usage: folder_cleaner [OPTIONS]
+
+Options:
+  -c, --config <PATH>  Load the configuration from PATH instead of the default location
+  -p, --profile <NAME> Also run the routines of the profile NAME
+      --strict         Treat unknown keys in the configuration as errors
+      --lenient        Skip invalid routines instead of refusing to run any
+      --print-config   Print the configuration as the program sees it and exit
+      --undo <NAME>    Move back what the last run of the routine NAME moved and exit
+      --run-now <NAME> Ask the program that's already running to run the routine NAME within 5 seconds
+      --install        Start the program with these options whenever you log in and exit
+      --uninstall      Stop the program from starting when you log in and exit
+  -h, --help           Print this message";
+
+
+/// The options the program was started with.
+#[derive(Debug, Default)]
+pub struct Args {
+    /// Where to load the configuration from, if not the default location.
+    pub config: Option<PathBuf>,
+    /// The profile whose routines to run.
+    pub profile: Option<String>,
+    /// Whether unknown configuration keys are errors.
+    pub strict: bool,
+    /// Whether invalid routines are skipped.
+    pub lenient: bool,
+    /// Whether to print the resolved configuration instead of running it.
+    pub print_config: bool,
+    /// The routine whose last run to undo instead of running anything.
+    pub undo: Option<String>,
+    /// The routine to have the running instance run right away instead of
+    /// running anything.
+    pub run_now: Option<String>,
+    /// Whether to set the program up to start when the user logs in instead
+    /// of running anything.
+    pub install: bool,
+    /// Whether to stop the program from starting when the user logs in
+    /// instead of running anything.
+    pub uninstall: bool,
+    /// Whether the usage message was asked for.
+    pub help: bool
+}
+
+impl Args {
+    /// Parses the arguments the program was started with.
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(std::env::args_os().skip(1))
+    }
+
+    /// Parses arguments, not including the program name.
+    pub fn parse<I: IntoIterator<Item = OsString>>(args: I) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let arg = arg
+                .into_string()
+                .map_err(|arg| format!("invalid argument {arg:?}"))?;
+
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => {
+                    (name.to_string(), Some(OsString::from(value)))
+                }
+                _ => (arg, None)
+            };
+
+            match name.as_str() {
+                "-c" | "--config" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or_else(|| format!("{name} requires a path"))?;
+                    parsed.config = Some(PathBuf::from(value));
+                }
+                "-p" | "--profile" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or_else(|| format!("{name} requires a name"))?
+                        .into_string()
+                        .map_err(|value| format!("invalid profile {value:?}"))?;
+                    parsed.profile = Some(value);
+                }
+                "--undo" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or_else(|| format!("{name} requires a name"))?
+                        .into_string()
+                        .map_err(|value| format!("invalid routine {value:?}"))?;
+                    parsed.undo = Some(value);
+                }
+                "--run-now" => {
+                    let value = value
+                        .or_else(|| args.next())
+                        .ok_or_else(|| format!("{name} requires a name"))?
+                        .into_string()
+                        .map_err(|value| format!("invalid routine {value:?}"))?;
+                    parsed.run_now = Some(value);
+                }
+                "--strict" | "--lenient" | "--print-config" | "--install" | "--uninstall" | "--help"
+                if value.is_some()
+                => return Err(format!("{name} doesn't take a value")),
+                "--strict" => parsed.strict = true,
+                "--lenient" => parsed.lenient = true,
+                "--print-config" => parsed.print_config = true,
+                "--install" => parsed.install = true,
+                "--uninstall" => parsed.uninstall = true,
+                "-h" | "--help" => parsed.help = true,
+                _ => return Err(format!("unknown argument \"{name}\""))
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn nothing_is_set_without_arguments() {
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.config, None);
+        assert_eq!(args.profile, None);
+        assert!(!args.strict && !args.lenient && !args.print_config && !args.help);
+    }
+
+    #[test]
+    fn config_paths_can_be_given_in_every_form() {
+        for args in [["-c", "my config.toml"].as_slice(), &["--config", "my config.toml"], &["--config=my config.toml"]] {
+            let parsed = parse(args).unwrap();
+            assert_eq!(parsed.config, Some(PathBuf::from("my config.toml")), "{args:?}");
+        }
+        // only the first = separates the value
+        let args = parse(&["--config=a=b.toml"]).unwrap();
+        assert_eq!(args.config, Some(PathBuf::from("a=b.toml")));
+    }
+
+    #[test]
+    fn later_arguments_win() {
+        let args = parse(&["-c", "a.toml", "--config", "b.toml"]).unwrap();
+        assert_eq!(args.config, Some(PathBuf::from("b.toml")));
+    }
+
+    #[test]
+    fn names_are_given_after_their_option() {
+        let args = parse(&["-p", "work", "--undo", "downloads", "--run-now=screenshots"]).unwrap();
+        assert_eq!(args.profile.as_deref(), Some("work"));
+        assert_eq!(args.undo.as_deref(), Some("downloads"));
+        assert_eq!(args.run_now.as_deref(), Some("screenshots"));
+    }
+
+    #[test]
+    fn flags_are_set() {
+        let args = parse(&["--strict", "--lenient", "--print-config", "--install", "--uninstall", "-h"]).unwrap();
+        assert!(args.strict && args.lenient && args.print_config && args.install && args.uninstall && args.help);
+        assert!(parse(&["--help"]).unwrap().help);
+    }
+
+    #[test]
+    fn values_are_required() {
+        assert_eq!(parse(&["--strict", "-c"]).unwrap_err(), "-c requires a path");
+        assert_eq!(parse(&["--profile"]).unwrap_err(), "--profile requires a name");
+        assert_eq!(parse(&["--run-now"]).unwrap_err(), "--run-now requires a name");
+    }
+
+    #[test]
+    fn flags_take_no_values() {
+        assert_eq!(parse(&["--strict=yes"]).unwrap_err(), "--strict doesn't take a value");
+        assert_eq!(parse(&["--help=me"]).unwrap_err(), "--help doesn't take a value");
+    }
+
+    #[test]
+    fn unknown_arguments_are_rejected() {
+        assert_eq!(parse(&["--verbose"]).unwrap_err(), "unknown argument \"--verbose\"");
+        assert_eq!(parse(&["config.toml"]).unwrap_err(), "unknown argument \"config.toml\"");
+    }
+}
@@ -0,0 +1,200 @@
+//! Starting the program automatically when the user logs in.
+//!
+//! On Windows, the program is added to the programs that the `Run` key of
+//! the user's registry starts, and on Linux and other platforms following
+//! the XDG specifications, a desktop entry is left in the user's autostart
+//! directory, `$XDG_CONFIG_HOME/autostart` or `~/.config/autostart`.
+
+use std::ffi::OsString;
+use std::io;
+
+
+/// The name the program is registered under.
+const NAME: &str = "folder_cleaner";
+
+
+/// Makes the program start with `command` when the user logs in, which is
+/// the path of the program followed by its arguments, replacing how it
+/// started before if it was already set up.
+pub fn install(command: &[OsString]) -> io::Result<()> {
+    platform::install(command)
+}
+
+/// Stops the program from starting when the user logs in, returning whether
+/// it was set up to.
+pub fn uninstall() -> io::Result<bool> {
+    platform::uninstall()
+}
+
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::{OsStr, OsString};
+    use std::io;
+    use std::iter;
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+    use windows_sys::Win32::System::Registry::{RegDeleteKeyValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+
+    use super::NAME;
+
+    /// The key of the programs that start when the user logs in.
+    const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+    pub(super) fn install(command: &[OsString]) -> io::Result<()> {
+        let mut line = Vec::new();
+        for arg in command {
+            if !line.is_empty() {
+                line.push(u16::from(b' '));
+            }
+            quote(arg, &mut line);
+        }
+        line.push(0);
+        let bytes = u32::try_from(line.len() * 2)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "the command is too long"))?;
+
+        // SAFETY: the strings end with nulls and outlive the call, and the
+        // size of the value is that of the line
+        match unsafe {
+            RegSetKeyValueW(HKEY_CURRENT_USER, wide(RUN_KEY).as_ptr(), wide(NAME).as_ptr(), REG_SZ, line.as_ptr().cast(), bytes)
+        } {
+            0 => Ok(()),
+            e => Err(io::Error::from_raw_os_error(e as i32))
+        }
+    }
+
+    pub(super) fn uninstall() -> io::Result<bool> {
+        // SAFETY: the strings end with nulls and outlive the call
+        match unsafe { RegDeleteKeyValueW(HKEY_CURRENT_USER, wide(RUN_KEY).as_ptr(), wide(NAME).as_ptr()) } {
+            0 => Ok(true),
+            ERROR_FILE_NOT_FOUND => Ok(false),
+            e => Err(io::Error::from_raw_os_error(e as i32))
+        }
+    }
+
+    fn wide(s: &str) -> Vec<u16> {
+        OsStr::new(s).encode_wide().chain([0]).collect()
+    }
+
+    /// Adds `arg` to a command line in quotes, the way programs split their
+    /// command lines back into arguments.
+    fn quote(arg: &OsStr, line: &mut Vec<u16>) {
+        const QUOTE: u16 = b'"' as u16;
+        const BACKSLASH: u16 = b'\\' as u16;
+
+        line.push(QUOTE);
+        let mut backslashes = 0;
+        for c in arg.encode_wide() {
+            match c {
+                BACKSLASH => {
+                    backslashes += 1;
+                    continue;
+                }
+                // backslashes only escape quotes, including each other's
+                QUOTE => line.extend(iter::repeat_n(BACKSLASH, backslashes * 2 + 1)),
+                _ => line.extend(iter::repeat_n(BACKSLASH, backslashes))
+            }
+            backslashes = 0;
+            line.push(c);
+        }
+        // the closing quote mustn't be escaped by backslashes before it
+        line.extend(iter::repeat_n(BACKSLASH, backslashes * 2));
+        line.push(QUOTE);
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use std::env;
+    use std::ffi::OsString;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    use super::NAME;
+
+    pub(super) fn install(command: &[OsString]) -> io::Result<()> {
+        let mut exec = Vec::new();
+        for arg in command {
+            let arg = arg.to_str().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("{arg:?} isn't valid Unicode"))
+            })?;
+            exec.push(quote(arg));
+        }
+        let entry = format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name={NAME}\n\
+             Comment=Cleans folders in the background\n\
+             Exec={}\n\
+             Terminal=false\n",
+            exec.join(" ")
+        );
+
+        let path = entry_path()?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, entry)
+    }
+
+    pub(super) fn uninstall() -> io::Result<bool> {
+        match fs::remove_file(entry_path()?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Where the desktop entry that starts the program is left.
+    fn entry_path() -> io::Result<PathBuf> {
+        let config = match env::var_os("XDG_CONFIG_HOME") {
+            // relative paths are invalid according to the XDG specification
+            Some(dir) if PathBuf::from(&dir).is_absolute() => PathBuf::from(dir),
+            _ => match env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(".config"),
+                None => return Err(io::Error::new(io::ErrorKind::NotFound, "HOME isn't set"))
+            }
+        };
+        Ok(config.join("autostart").join(format!("{NAME}.desktop")))
+    }
+
+    /// Quotes `arg` for the `Exec` key of a desktop entry, whose own escapes
+    /// for backslashes apply on top of those of the quotes.
+    fn quote(arg: &str) -> String {
+        let mut quoted = String::from('"');
+        for c in arg.chars() {
+            match c {
+                '"' | '`' | '$' => quoted.push_str(r"\\"),
+                '\\' => quoted.push_str(r"\\\"),
+                '%' => quoted.push('%'),
+                '\n' => {
+                    quoted.push_str(r"\n");
+                    continue;
+                }
+                _ => {}
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        quoted
+    }
+}
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+mod platform {
+    use std::ffi::OsString;
+    use std::io;
+
+    pub(super) fn install(_command: &[OsString]) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub(super) fn uninstall() -> io::Result<bool> {
+        Err(unsupported())
+    }
+
+    fn unsupported() -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, "starting at logon can't be set up on this platform")
+    }
+}
@@ -0,0 +1,85 @@
+//! Tools for sharing settings between routines.
+//!
+//! A configuration can have a `defaults` table containing any settings a
+//! routine can have. Every routine inherits the settings it doesn't set
+//! itself from the defaults, so common settings like the interval don't have
+//! to be repeated in every routine.
+//!
+//! Defaults are also inherited by the files a configuration includes and by
+//! its fragments. Those files can have defaults of their own, which override
+//! the inherited ones for the routines in those files.
+//!
+//! # Examples
+//!
+//! ```toml
+//! [defaults]
+//! interval = "1h"
+//!
+//! [[routines]]
+//! name = "downloads"
+//! directory = "~/Downloads"
+//! pattern = "any"
+//!
+//! [[routines]]
+//! name = "desktop"
+//! directory = "~/Desktop"
+//! pattern = "any"
+//! interval = "1d"
+//! ```
+
+use serde::de::Error;
+use toml::value::{Table, Value};
+
+use crate::config::migrate;
+
+
+/// The name of the table that holds default settings.
+const DEFAULTS_KEY: &str = "defaults";
+
+/// Settings that have to be unique and therefore can't have defaults.
+const UNIQUE_KEYS: [&str; 1] = ["name"];
+
+
+/// Fills in the missing settings of every routine in `config` from its
+/// defaults, which in turn are filled in from the `inherited` defaults.
+///
+/// The defaults table is removed from `config`. Returns the defaults that
+/// were applied, so they can be passed on to other files. Any warnings about
+/// the defaults are pushed into `warnings`.
+///
+/// # Errors
+///
+/// This function returns an error if `config` has defaults that aren't a
+/// table.
+pub(crate) fn apply(
+    config: &mut Value,
+    inherited: &Table,
+    warnings: &mut Vec<String>
+) -> Result<Table, toml::de::Error> {
+    let Some(table) = config.as_table_mut() else {
+        return Ok(inherited.clone());
+    };
+
+    let mut defaults = inherited.clone();
+    match table.remove(DEFAULTS_KEY) {
+        Some(Value::Table(own)) => defaults.extend(own),
+        Some(_) => return Err(Error::custom("defaults must be a table")),
+        None => {}
+    }
+
+    for key in UNIQUE_KEYS {
+        if defaults.remove(key).is_some() {
+            warnings.push(format!("defaults.{key} is ignored, because it must be unique"));
+        }
+    }
+
+    for routine in migrate::routines(table) {
+        for (key, value) in &defaults {
+            if !routine.contains_key(key) {
+                routine.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    Ok(defaults)
+}
@@ -0,0 +1,154 @@
+//! Tools for writing durations as human-readable strings.
+//!
+//! Durations are written as a sequence of numbers followed by units, such as
+//! `"30m"`, `"1h30m"` or `"2d"`. The supported units are `ms`, `s`, `m`,
+//! `h`, `d`, `w` and `y`, for milliseconds, seconds, minutes, hours, days,
+//! weeks and years of 365 days respectively.
+//!
+//! The [`serialize`] and [`deserialize`] functions allow this module to be
+//! used with serde's `with` attribute.
+//!
+//! # Examples
+//!
+//! ```
+//! use folder_cleaner::config::duration;
+//! use std::time::Duration;
+//!
+//! let interval = duration::parse("1h30m").unwrap();
+//! assert_eq!(interval, Duration::from_secs(90 * 60));
+//! assert_eq!(duration::format(interval), "1h30m");
+//! ```
+
+use std::time::Duration;
+use serde::{Deserialize, Deserializer, Serializer};
+
+
+/// The supported units and their lengths in milliseconds, longest first.
+const UNITS: [(&str, u64); 7] = [
+    ("y", 365 * 24 * 60 * 60 * 1000),
+    ("w", 7 * 24 * 60 * 60 * 1000),
+    ("d", 24 * 60 * 60 * 1000),
+    ("h", 60 * 60 * 1000),
+    ("m", 60 * 1000),
+    ("s", 1000),
+    ("ms", 1)
+];
+
+
+/// Represents errors that occur when a string isn't a valid duration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDurationError {
+    input: String,
+    reason: &'static str
+}
+
+impl ParseDurationError {
+    fn new(input: &str, reason: &'static str) -> Self {
+        ParseDurationError { input: input.to_string(), reason }
+    }
+}
+
+impl std::fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid duration \"{}\": {} (expected something like \"1h30m\")",
+            self.input,
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for ParseDurationError {}
+
+
+/// Parses a duration from a string such as `"1h30m"`.
+///
+/// Whitespace between the parts of the duration is allowed, so `"1h 30m"`
+/// is the same as `"1h30m"`.
+///
+/// # Errors
+///
+/// This function returns an error if the string is empty, if a number is
+/// missing its unit or a unit is missing its number, if a unit isn't
+/// supported, or if the duration is too long to be represented.
+pub fn parse(s: &str) -> Result<Duration, ParseDurationError> {
+    let error = |reason| ParseDurationError::new(s, reason);
+
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(error("duration is empty"));
+    }
+
+    let mut total: u64 = 0;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(error("expected a number"));
+        }
+        let number: u64 = rest[..digits]
+            .parse()
+            .map_err(|_| error("number is too large"))?;
+        rest = &rest[digits..];
+
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        if letters == 0 {
+            return Err(error("number is missing a unit"));
+        }
+        let unit = &rest[..letters];
+        let (_, millis) = UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .ok_or_else(|| error("unknown unit"))?;
+        rest = rest[letters..].trim_start();
+
+        total = number
+            .checked_mul(*millis)
+            .and_then(|part| total.checked_add(part))
+            .ok_or_else(|| error("duration is too long"))?;
+    }
+
+    Ok(Duration::from_millis(total))
+}
+
+/// Formats a duration into a string that [`parse`] accepts.
+///
+/// The duration is written using the longest units possible, so 90 minutes
+/// becomes `"1h30m"`. Any part of the duration shorter than a millisecond
+/// is left out.
+pub fn format(duration: Duration) -> String {
+    let mut millis = duration.as_millis();
+    if millis == 0 {
+        return "0s".into();
+    }
+
+    let mut out = String::new();
+    for (name, length) in UNITS {
+        let length = u128::from(length);
+        let count = millis / length;
+        if count > 0 {
+            out.push_str(&format!("{count}{name}"));
+            millis %= length;
+        }
+    }
+    out
+}
+
+
+/// Serializes a duration as a human-readable string. See [`format`](fn@format).
+pub fn serialize<S: Serializer>(
+    duration: &Duration,
+    serializer: S
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(*duration))
+}
+
+/// Deserializes a duration from a human-readable string. See [`parse`].
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D
+) -> Result<Duration, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse(&s).map_err(serde::de::Error::custom)
+}
@@ -0,0 +1,79 @@
+//! Errors that can occur when loading or storing the configuration.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::expand::UndefinedVariable;
+use crate::config::format::FormatError;
+
+
+/// Represents errors that occur when loading or storing a configuration file.
+///
+/// Contains the path to the configuration file as well as the underlying
+/// error that prevented it from being used.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read or written.
+    Io(PathBuf, io::Error),
+    /// The file doesn't contain a valid configuration.
+    Parse(PathBuf, FormatError),
+    /// The configuration couldn't be converted into the file's format.
+    Serialize(PathBuf, FormatError),
+    /// A configured path refers to an undefined environment variable.
+    Expand(PathBuf, UndefinedVariable),
+    /// The file ends up including itself.
+    IncludeCycle(PathBuf),
+    /// The selected profile isn't defined in the configuration.
+    UnknownProfile(PathBuf, String)
+}
+
+impl ConfigError {
+    /// The path to the configuration file that couldn't be used.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Io(path, _)
+            | Self::Parse(path, _)
+            | Self::Serialize(path, _)
+            | Self::Expand(path, _)
+            | Self::IncludeCycle(path)
+            | Self::UnknownProfile(path, _) => path
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path().display();
+        match self {
+            Self::Io(_, e)
+            => write!(f, "failed to access \"{path}\": {e}"),
+
+            Self::Parse(_, e)
+            => write!(f, "invalid configuration in \"{path}\": {e}"),
+
+            Self::Serialize(_, e)
+            => write!(f, "failed to write configuration to \"{path}\": {e}"),
+
+            Self::Expand(_, e)
+            => write!(f, "invalid path in \"{path}\": {e}"),
+
+            Self::IncludeCycle(_)
+            => write!(f, "\"{path}\" is included in itself"),
+
+            Self::UnknownProfile(_, profile)
+            => write!(f, "profile \"{profile}\" isn't defined in \"{path}\"")
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, e) => Some(e),
+            Self::Parse(_, e) => Some(e.as_ref()),
+            Self::Serialize(_, e) => Some(e.as_ref()),
+            Self::Expand(_, e) => Some(e),
+            Self::IncludeCycle(_) | Self::UnknownProfile(..) => None
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Tools for expanding environment variables in configured paths.
+//!
+//! Both the Windows style `%NAME%` and the Unix style `$NAME` and `${NAME}`
+//! are supported, as is a leading `~` for the user's home directory. This
+//! allows the same configuration to be used across machines and user
+//! accounts. A `$` or `%` that's part of the path itself, like in
+//! `C:\$Recycle.Bin`, is written twice.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use folder_cleaner::config::expand::expand_path;
+//!
+//! let downloads = expand_path(r"%USERPROFILE%\Downloads").unwrap();
+//! ```
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+
+/// Represents an environment variable that a path refers to but that
+/// isn't defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndefinedVariable {
+    name: String
+}
+
+impl UndefinedVariable {
+    pub(crate) fn new(name: &str) -> Self {
+        UndefinedVariable { name: name.to_string() }
+    }
+
+    /// The name of the undefined variable.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl std::fmt::Display for UndefinedVariable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "environment variable \"{}\" is not defined", self.name)
+    }
+}
+
+impl std::error::Error for UndefinedVariable {}
+
+
+/// Expands environment variables and a leading `~` in a path.
+///
+/// A `%` or `$` that isn't followed by a variable name is kept as is, so
+/// paths that happen to contain those characters are mostly unaffected, and
+/// `$$` and `%%` stand for a single `$` or `%` where it would be taken for a
+/// variable otherwise. Paths that aren't valid Unicode are returned
+/// unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::config::expand::expand_path;
+/// use std::path::Path;
+///
+/// let bin = expand_path(r"C:\$$Recycle.Bin").unwrap();
+/// assert_eq!(bin, Path::new(r"C:\$Recycle.Bin"));
+/// ```
+///
+/// # Errors
+///
+/// This function returns an error if the path refers to an environment
+/// variable that isn't defined. An error is returned rather than leaving the
+/// reference in the path so that a routine never ends up running on some
+/// unintended directory.
+pub fn expand_path<P: AsRef<Path>>(path: P) -> Result<PathBuf, UndefinedVariable> {
+    let path = path.as_ref();
+    match path.to_str() {
+        Some(s) => expand(s).map(PathBuf::from),
+        None => Ok(path.to_path_buf())
+    }
+}
+
+/// Writes every `$` and `%` in a path twice, so that
+/// [expanding](expand_path) the result gives back the same path.
+pub(crate) fn escape_path(path: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(s) => PathBuf::from(s.replace('$', "$$").replace('%', "%%")),
+        None => path.to_path_buf()
+    }
+}
+
+
+fn expand(s: &str) -> Result<String, UndefinedVariable> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    if let Some(after) = rest.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            out.push_str(&home()?);
+            rest = after;
+        }
+    }
+
+    while let Some(i) = rest.find(['%', '$']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if rest[1..].starts_with(&rest[..1]) {
+            // an escaped `$` or `%`
+            out.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        match variable(rest) {
+            Some((name, len)) => {
+                out.push_str(&lookup(name)?);
+                rest = &rest[len..];
+            }
+            None => {
+                // not a variable reference, so keep the character
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Parses a variable reference at the start of `s`.
+///
+/// Returns the name of the variable and the length of the whole reference.
+fn variable(s: &str) -> Option<(&str, usize)> {
+    let (name, len, valid): (&str, usize, fn(char) -> bool) =
+        if let Some(inner) = s.strip_prefix("${") {
+            let end = inner.find('}')?;
+            (&inner[..end], end + 3, is_name_char)
+        }
+        else if let Some(inner) = s.strip_prefix('%') {
+            let end = inner.find('%')?;
+            // Windows has names like %ProgramFiles(x86)%
+            (&inner[..end], end + 2, |c| is_name_char(c) || c == '(' || c == ')')
+        }
+        else {
+            let inner = &s[1..];
+            let end = inner
+                .find(|c: char| !is_name_char(c))
+                .unwrap_or(inner.len());
+            (&inner[..end], end + 1, is_name_char)
+        };
+
+    match !name.is_empty() && name.chars().all(valid) {
+        true => Some((name, len)),
+        false => None
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn lookup(name: &str) -> Result<String, UndefinedVariable> {
+    env::var(name).map_err(|_| UndefinedVariable::new(name))
+}
+
+fn home() -> Result<String, UndefinedVariable> {
+    lookup("HOME").or_else(|_| lookup("USERPROFILE"))
+}
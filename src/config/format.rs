@@ -0,0 +1,64 @@
+//! Tools for reading and writing configurations in different file formats.
+//!
+//! Configurations are normally written in TOML, but JSON and YAML are
+//! supported as well for configurations generated by other tools. The format
+//! of a file is determined by its extension.
+
+use std::path::Path;
+use serde::Serialize;
+
+
+/// An error from parsing or serializing a configuration in some format.
+pub type FormatError = Box<dyn std::error::Error + Send + Sync>;
+
+
+/// The file formats a configuration can be written in.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+    Yaml
+}
+
+impl Format {
+    /// The extensions of every supported format.
+    pub const EXTENSIONS: [&'static str; 4] = ["toml", "json", "yaml", "yml"];
+
+    /// Determines the format of a file from its extension.
+    ///
+    /// Files with an unrecognised extension, or no extension at all, are
+    /// considered to be TOML.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let ext = path.as_ref().extension().unwrap_or_default();
+        match ext.to_str() {
+            Some("json") => Self::Json,
+            Some("yaml" | "yml") => Self::Yaml,
+            _ => Self::Toml
+        }
+    }
+
+    /// Parses text in this format into a TOML value.
+    ///
+    /// Every format is converted into TOML so that the rest of the loading
+    /// process only has to deal with one kind of value.
+    pub(crate) fn parse(self, text: &str) -> Result<toml::Value, FormatError> {
+        Ok(match self {
+            Self::Toml => toml::from_str(text)?,
+            Self::Json => serde_json::from_str(text)?,
+            Self::Yaml => serde_yaml::from_str(text)?
+        })
+    }
+
+    /// Serializes a value into text in this format.
+    pub(crate) fn serialize<T: Serialize>(self, value: &T) -> Result<String, FormatError> {
+        // going through a TOML value makes enums look the same in every
+        // format, so that whatever is written can also be parsed back
+        let value = toml::Value::try_from(value)?;
+        Ok(match self {
+            Self::Toml => toml::to_string_pretty(&value)?,
+            Self::Json => serde_json::to_string_pretty(&value)?,
+            Self::Yaml => serde_yaml::to_string(&value)?
+        })
+    }
+}
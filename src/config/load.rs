@@ -0,0 +1,807 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use toml::Table;
+
+use crate::config::{defaults, expand, migrate, profile, Config, ConfigError};
+use crate::config::format::{Format, FormatError};
+use crate::routine::Routine;
+
+
+/// The name of the directory that holds configuration fragments.
+const FRAGMENT_DIR: &str = "config.d";
+
+
+/// The commented example configuration that new configuration files start
+/// out as.
+const TEMPLATE: &str = include_str!("default.toml");
+
+
+/// Options that control how a configuration is loaded.
+///
+/// # Examples
+///
+/// Loads a configuration, refusing to accept any keys that the program
+/// doesn't recognise.
+///
+/// ```no_run
+/// use folder_cleaner::config::{Config, LoadOptions};
+///
+/// let options = LoadOptions { strict: true, ..Default::default() };
+/// let config = Config::load_with(r"C:\path\to\config.toml", &options);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Whether unknown keys are errors rather than warnings.
+    ///
+    /// A misspelled key is otherwise ignored, which can make a routine
+    /// silently fall back to some default, such as running on the wrong
+    /// schedule.
+    pub strict: bool,
+    /// The [profile] whose routines are used in addition to the routines
+    /// outside of any profile.
+    pub profile: Option<String>,
+    /// Whether routines that can't be loaded are skipped with a warning
+    /// rather than making the whole configuration fail to load.
+    ///
+    /// This keeps the rest of the routines running on machines that nobody
+    /// is watching. Unknown keys are still errors in
+    /// [strict](Self::strict) mode.
+    pub lenient: bool
+}
+
+
+impl Config {
+    /// Loads a configuration from the file at `path`.
+    ///
+    /// If the file doesn't exist, it's [created](init) from a commented
+    /// example configuration whose routines are disabled. Configurations
+    /// written for older versions of the program are
+    /// [upgraded](migrate::migrate) to the current format, and environment
+    /// variables in the configured paths are [expanded](expand::expand_path).
+    ///
+    /// The file can be written in any supported [format](Format), which is
+    /// determined by its extension.
+    ///
+    /// Every configuration file in the [fragment directory](fragment_dir)
+    /// next to the file is loaded as well, in alphabetical order, and the
+    /// routines they define are added after the routines of the main file.
+    ///
+    /// Any file can also list other files to include in its `include` key.
+    /// Relative paths are resolved against the directory of the including
+    /// file, and the routines of the included files are added after the
+    /// routines of the including file.
+    ///
+    /// Routines inherit any settings they don't have from the
+    /// [defaults] of the file they're in, and fragments and included files
+    /// inherit the defaults of the file that led to them being loaded.
+    ///
+    /// Keys that the program doesn't recognise are reported as
+    /// [warnings](Self::warnings()). Use [`load_with`](Self::load_with())
+    /// to treat them as errors instead.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if any of the files can't be read or
+    /// created, if their contents aren't a valid configuration, if a path
+    /// refers to an undefined environment variable, or if a file ends up
+    /// including itself.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Self::load_with(path, &LoadOptions::default())
+    }
+
+    /// Loads a configuration from the file at `path` using `options`.
+    ///
+    /// See [`load`](Self::load()) and [`LoadOptions`].
+    ///
+    /// # Errors
+    ///
+    /// See [`load`](Self::load()). In [strict](LoadOptions::strict) mode,
+    /// this function also returns an error if a file contains unknown keys.
+    /// An error is also returned if the selected [profile] isn't defined in
+    /// any of the files.
+    pub fn load_with<P: AsRef<Path>>(
+        path: P,
+        options: &LoadOptions
+    ) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let mut config = match Self::load_file(path, &Table::new(), options) {
+            // a missing include is an error rather than a reason to start over
+            Err(ConfigError::Io(p, e))
+                if p == path && e.kind() == io::ErrorKind::NotFound
+            => init(path)?,
+
+            other => other?
+        };
+
+        let fragments = fragment_paths(path)
+            .map_err(|e| ConfigError::Io(fragment_dir(path), e))?;
+        for fragment in fragments {
+            let fragment = Self::load_file(&fragment, &config.defaults, options)?;
+            config.merge(fragment);
+        }
+
+        if let Some(profile) = &options.profile {
+            if !config.profiles.contains(profile) {
+                return Err(ConfigError::UnknownProfile(
+                    path.to_path_buf(),
+                    profile.clone()
+                ));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Writes the configuration into the file at `path`.
+    ///
+    /// The configuration is written in the [format](Format::from_path) the
+    /// extension of `path` suggests. Any missing parent directories are
+    /// created as well.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the configuration can't be written
+    /// into the file.
+    pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let io_error = |e| ConfigError::Io(path.to_path_buf(), e);
+        let ser_error = |e| ConfigError::Serialize(path.to_path_buf(), e);
+
+        let text = Format::from_path(path).serialize(&self.escaped()).map_err(ser_error)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        fs::write(path, text).map_err(io_error)
+    }
+
+    /// Writes the configuration into `writer` in the given format.
+    ///
+    /// A loaded configuration has already been fully resolved, so the output
+    /// shows exactly which routines the program would run: environment
+    /// variables have been expanded, defaults have been applied, included
+    /// files and fragments have been merged in and the selected profile has
+    /// been applied. Any `$` or `%` left in a path is written twice, so that
+    /// loading the output gives back the same paths.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the configuration can't be
+    /// serialized or written into `writer`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use folder_cleaner::config::{Config, Format};
+    ///
+    /// let config = Config::load(r"C:\path\to\config.toml").unwrap();
+    /// config.to_writer(std::io::stdout(), Format::Toml).unwrap();
+    /// ```
+    pub fn to_writer<W: Write>(
+        &self,
+        mut writer: W,
+        format: Format
+    ) -> Result<(), FormatError> {
+        let text = format.serialize(&self.escaped())?;
+        writer.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Loads a single configuration file and the files it includes, without
+    /// looking for fragments.
+    fn load_file(
+        path: &Path,
+        defaults: &Table,
+        options: &LoadOptions
+    ) -> Result<Self, ConfigError> {
+        Self::load_including(path, defaults, options, &mut Vec::new())
+    }
+
+    /// Loads a configuration file and, recursively, the files it includes.
+    ///
+    /// `including` contains the files that are currently being loaded further
+    /// up the chain of includes, so that cycles can be detected.
+    fn load_including(
+        path: &Path,
+        defaults: &Table,
+        options: &LoadOptions,
+        including: &mut Vec<PathBuf>
+    ) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+
+        let format = Format::from_path(path);
+        let mut config = Self::parse(&text, format, defaults, options)
+            .map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+        config.expand_paths()
+            .map_err(|e| ConfigError::Expand(path.to_path_buf(), e))?;
+
+        for w in &mut config.warnings {
+            *w = format!("\"{}\": {w}", path.display());
+        }
+
+        // the same file can be reached through different paths,
+        // so compare canonical ones to catch every cycle
+        let id = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if including.contains(&id) {
+            return Err(ConfigError::IncludeCycle(path.to_path_buf()));
+        }
+
+        including.push(id);
+        let base = path.parent().unwrap_or(Path::new(""));
+        for include in std::mem::take(&mut config.include) {
+            // relative paths are relative to the including file
+            let included = Self::load_including(
+                &base.join(include),
+                &config.defaults,
+                options,
+                including
+            )?;
+            config.merge(included);
+        }
+        including.pop();
+
+        Ok(config)
+    }
+
+    /// Parses a configuration in some format, upgrading it if necessary and
+    /// applying its defaults on top of the inherited ones.
+    fn parse(
+        text: &str,
+        format: Format,
+        inherited: &Table,
+        options: &LoadOptions
+    ) -> Result<Self, FormatError> {
+        let mut value = format.parse(text)?;
+        // profiles go first, so that their routines get migrated as well
+        let profiles = profile::select(&mut value, options.profile.as_deref())?;
+        let mut warnings = migrate::migrate(&mut value)?;
+        let defaults = defaults::apply(&mut value, inherited, &mut warnings)?;
+        if options.lenient {
+            skip_invalid_routines(&mut value, &mut warnings);
+        }
+
+        let mut unknown = Vec::new();
+        let mut config: Self = serde_ignored::deserialize(value, |key| {
+            unknown.push(key.to_string());
+        })?;
+
+        if options.strict && !unknown.is_empty() {
+            return Err(format!("unknown keys: {}", unknown.join(", ")).into());
+        }
+        warnings.extend(unknown.into_iter().map(|key| format!("unknown key {key}")));
+
+        config.defaults = defaults;
+        config.profiles = profiles.into_iter().collect();
+        config.warnings = warnings;
+        Ok(config)
+    }
+
+    /// Expands environment variables in the paths of every routine and
+    /// include.
+    fn expand_paths(&mut self) -> Result<(), expand::UndefinedVariable> {
+        for routine in &mut self.routines {
+            routine.directory = expand::expand_path(&routine.directory)?;
+            // relative destinations are relative to the routine's directory
+            for path in routine.action.paths_mut() {
+                *path = routine.directory.join(expand::expand_path(&path)?);
+            }
+            if let Some(path) = routine.report.as_mut().and_then(|report| report.directory.as_mut()) {
+                *path = routine.directory.join(expand::expand_path(&path)?);
+            }
+        }
+        for include in &mut self.include {
+            *include = expand::expand_path(&include)?;
+        }
+        Ok(())
+    }
+
+    /// A copy of the configuration to be written out, with the paths
+    /// [escaped](expand::escape_path) so that expanding them again when the
+    /// copy is loaded doesn't change them.
+    fn escaped(&self) -> Self {
+        let mut routines = self.routines.clone();
+        for routine in &mut routines {
+            routine.directory = expand::escape_path(&routine.directory);
+            for path in routine.action.paths_mut() {
+                *path = expand::escape_path(path);
+            }
+            if let Some(path) = routine.report.as_mut().and_then(|report| report.directory.as_mut()) {
+                *path = expand::escape_path(path);
+            }
+        }
+        Self {
+            version: self.version,
+            include: self.include.iter().map(|include| expand::escape_path(include)).collect(),
+            routines,
+            defaults: self.defaults.clone(),
+            profiles: self.profiles.clone(),
+            warnings: self.warnings.clone()
+        }
+    }
+
+    /// Adds the routines, profiles and warnings of another configuration to
+    /// this one.
+    fn merge(&mut self, other: Self) {
+        self.routines.extend(other.routines);
+        self.profiles.extend(other.profiles);
+        self.warnings.extend(other.warnings);
+    }
+}
+
+
+/// Removes every routine that can't be deserialized from a configuration,
+/// adding a warning about each one.
+fn skip_invalid_routines(config: &mut toml::Value, warnings: &mut Vec<String>) {
+    let Some(toml::Value::Array(routines)) = config.get_mut("routines") else {
+        return;
+    };
+
+    let mut i = 0;
+    routines.retain(|routine| {
+        let result = Routine::deserialize(routine.clone());
+        if let Err(e) = &result {
+            let e = e.to_string();
+            warnings.push(format!("routines[{i}]: skipping routine: {}", e.trim_end()));
+        }
+        i += 1;
+        result.is_ok()
+    });
+}
+
+
+/// Creates a new configuration file at `path` from a commented example.
+///
+/// The example explains the available settings and contains a couple of
+/// sample routines, which are disabled so that nothing is removed until the
+/// user has had a chance to edit them. Any missing parent directories are
+/// created as well. Returns the configuration in the new file.
+///
+/// The comments are only included if the file is written in TOML. In other
+/// [formats](Format), the file contains just the sample routines.
+///
+/// # Errors
+///
+/// This function returns an error if the file already exists or can't be
+/// created.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::config;
+///
+/// let path = config::default_path().unwrap();
+/// config::init(&path).unwrap();
+/// ```
+pub fn init<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+    let path = path.as_ref();
+    let io_error = |e| ConfigError::Io(path.to_path_buf(), e);
+
+    let options = LoadOptions::default();
+    let text = match Format::from_path(path) {
+        Format::Toml => TEMPLATE.to_string(),
+        format => Config::parse(TEMPLATE, Format::Toml, &Table::new(), &options)
+            .and_then(|template| format.serialize(&template))
+            .map_err(|e| ConfigError::Serialize(path.to_path_buf(), e))?
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(io_error)?;
+
+    Config::load_file(path, &Table::new(), &options)
+}
+
+
+/// The directory that holds configuration fragments for the configuration
+/// file at `path`.
+///
+/// The directory is called `config.d` and is located next to the
+/// configuration file. Keeping routines in separate fragment files allows
+/// them to be managed independently, for example one file per cleaned
+/// directory.
+pub fn fragment_dir<P: AsRef<Path>>(path: P) -> PathBuf {
+    path.as_ref().with_file_name(FRAGMENT_DIR)
+}
+
+/// The paths of every fragment file for the configuration file at `path`,
+/// in alphabetical order.
+///
+/// A missing fragment directory simply means that there are no fragments.
+pub(crate) fn fragment_paths(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fragment_dir(path).read_dir() {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e)
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file()
+            && path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| Format::EXTENSIONS.contains(&ext))
+        {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+    Ok(paths)
+}
+
+/// The paths of every file that the configuration file at `path` includes,
+/// directly or through the files it includes.
+///
+/// Files that can't be read or parsed right now are left out of the search,
+/// but not the paths they're included by, so that it's noticed when they
+/// become readable again.
+pub(crate) fn include_paths(path: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    collect_includes(path, &mut paths);
+    paths
+}
+
+fn collect_includes(path: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(text) = fs::read_to_string(path) else {
+        return;
+    };
+    let Ok(value) = Format::from_path(path).parse(&text) else {
+        return;
+    };
+    let Some(includes) = value.get("include").and_then(toml::Value::as_array) else {
+        return;
+    };
+
+    let base = path.parent().unwrap_or(Path::new(""));
+    for include in includes.iter().filter_map(toml::Value::as_str) {
+        let Ok(include) = expand::expand_path(include) else {
+            continue;
+        };
+        // relative paths are relative to the including file, as when loading
+        let include = base.join(include);
+        // files included more than once, or in a cycle, are only searched once
+        if !paths.contains(&include) {
+            paths.push(include.clone());
+            collect_includes(&include, paths);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::fs_utils::Action;
+
+    /// A configuration that sets every routine setting to something other
+    /// than its default, since those are the ones serialization can drop.
+    const EVERYTHING: &str = r#"
+version = 4
+
+[[routines]]
+name = "downloads"
+directory = "/home/user/Downloads"
+interval = "45m..75m"
+pattern = "ext(iso, zip) and older(7d) and not name(keep)"
+enabled = false
+case_sensitive = true
+action = { move_to = { directory = "/home/user/Staging", keep_structure = true } }
+hooks = { on_start = ["echo", "{routine}"], on_error = ["notify-send", "{routine}: {error}"] }
+recursive = true
+max_depth = 2
+prune_empty_dirs = true
+report = { directory = "/home/user/reports", format = "json" }
+retry = { attempts = 3, backoff = "1s" }
+days = ["sat", "sun"]
+time_zone = "Europe/Berlin"
+jitter = 10
+initial_delay = "10m"
+once = true
+timing = "fixed"
+overlap = "skip"
+missed_runs = "each"
+trigger = { size_above = "20GB" }
+idle = "5m"
+quiet_hours = [
+    { from = "09:00", to = "18:00", days = ["mon", "tue", "wed", "thu", "fri"] },
+    { from = "22:00", to = "06:00" }
+]
+max_run_duration = "15m"
+
+[[routines]]
+name = "scratch"
+directory = "/tmp/scratch"
+pattern = "any"
+action = { fallback = ["trash", { archive = "/tmp/archives" }] }
+schedule = "0 3 * * sun"
+trigger = { free_space_below = "10%" }
+
+[[routines]]
+name = "screenshots"
+directory = "/home/user/Pictures"
+pattern = "glob(Screenshot*)"
+action = { quarantine = { grace = "7d" } }
+at = ["03:00", "15:30"]
+trigger = { watch = "10s" }
+"#;
+
+    fn parse(text: &str, format: Format) -> Config {
+        Config::parse(text, format, &Table::new(), &LoadOptions::default()).unwrap()
+    }
+
+    /// Writes `config` in `format` and reads it back.
+    fn round_trip(config: &Config, format: Format) -> Config {
+        let text = format.serialize(config).unwrap();
+        parse(&text, format)
+    }
+
+    /// The names of the routines of `config`, in order.
+    fn names(config: &Config) -> Vec<&str> {
+        config.routines().iter().map(|routine| routine.name.as_str()).collect()
+    }
+
+    /// A routine called `name` that uses the defaults for everything else.
+    fn routine(name: &str) -> String {
+        format!("[[routines]]\nname = \"{name}\"\ndirectory = '.'\n")
+    }
+
+    #[test]
+    fn round_trip_keeps_every_setting() {
+        let config = parse(EVERYTHING, Format::Toml);
+        assert!(config.warnings().is_empty(), "{:?}", config.warnings());
+        assert_eq!(config.routines().len(), 3);
+
+        for format in [Format::Toml, Format::Json, Format::Yaml] {
+            let again = round_trip(&config, format);
+            assert_eq!(again.version(), config.version(), "{format:?}");
+            assert_eq!(again.routines(), config.routines(), "{format:?}");
+            assert!(again.warnings().is_empty(), "{format:?}: {:?}", again.warnings());
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_settings_from_defaults() {
+        let config = parse(r#"
+version = 4
+
+[defaults]
+interval = "1h..2h"
+action = "trash"
+quiet_hours = [{ from = "22:00", to = "06:00" }]
+
+[[routines]]
+name = "desktop"
+directory = "/home/user/Desktop"
+pattern = "ext(lnk)"
+"#, Format::Toml);
+        let routine = &config.routines()[0];
+        assert_eq!(routine.action, Action::Trash);
+        assert_eq!(routine.quiet_hours.len(), 1);
+
+        let again = round_trip(&config, Format::Toml);
+        assert_eq!(again.routines(), config.routines());
+    }
+
+    #[test]
+    fn written_paths_load_unchanged() {
+        let mut config = parse(r#"
+version = 4
+
+[[routines]]
+name = "bin"
+directory = '/mnt/c/$$Recycle.Bin'
+pattern = "any"
+action = { archive = '100%%$$' }
+"#, Format::Toml);
+        config.expand_paths().unwrap();
+        assert_eq!(config.routines()[0].directory, Path::new("/mnt/c/$Recycle.Bin"));
+
+        let mut text = Vec::new();
+        config.to_writer(&mut text, Format::Toml).unwrap();
+        let mut again = parse(std::str::from_utf8(&text).unwrap(), Format::Toml);
+        again.expand_paths().unwrap();
+        assert_eq!(again.routines(), config.routines());
+    }
+
+    #[test]
+    fn round_trip_keeps_the_template() {
+        let config = parse(TEMPLATE, Format::Toml);
+        let again = round_trip(&config, Format::Toml);
+        assert_eq!(again.routines(), config.routines());
+    }
+
+    #[test]
+    fn fragments_are_loaded_in_alphabetical_order() {
+        let directory = crate::fs_utils::test_dir("load-fragments");
+        let path = directory.join("config.toml");
+        let defaults = "[defaults]\ninterval = \"1h\"\npattern = \"any\"\n";
+        fs::write(&path, format!("version = 4\n{defaults}{}", routine("main"))).unwrap();
+        fs::create_dir(fragment_dir(&path)).unwrap();
+        fs::write(fragment_dir(&path).join("b.toml"), format!("version = 4\n{}", routine("b"))).unwrap();
+        fs::write(
+            fragment_dir(&path).join("a.json"),
+            r#"{ "version": 4, "routines": [{ "name": "a", "directory": ".", "pattern": "ext(tmp)" }] }"#
+        ).unwrap();
+        fs::write(fragment_dir(&path).join("notes.txt"), "not a fragment").unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(names(&config), ["main", "a", "b"]);
+        // fragments inherit the defaults of the main file
+        assert!(config.routines().iter().all(|r| r.interval == Duration::from_secs(3600).into()));
+        assert_eq!(config.routines()[1].pattern.to_string(), "ext(tmp)");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn broken_fragments_fail_the_whole_configuration() {
+        let directory = crate::fs_utils::test_dir("load-broken-fragments");
+        let path = directory.join("config.toml");
+        fs::write(&path, "version = 4\n").unwrap();
+        fs::create_dir(fragment_dir(&path)).unwrap();
+        let fragment = fragment_dir(&path).join("broken.toml");
+        fs::write(&fragment, "version = ").unwrap();
+
+        let e = Config::load(&path).err().unwrap();
+        assert!(matches!(e, ConfigError::Parse(..)), "{e:?}");
+        assert_eq!(e.path(), fragment);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn includes_are_relative_to_the_including_file() {
+        let directory = crate::fs_utils::test_dir("load-includes");
+        fs::create_dir_all(directory.join("more/nested")).unwrap();
+        let path = directory.join("config.toml");
+        fs::write(&path, format!(
+            "version = 4\ninclude = [\"more/extra.toml\"]\n[defaults]\ninterval = \"1h\"\npattern = \"any\"\n{}",
+            routine("main")
+        )).unwrap();
+        fs::write(
+            directory.join("more/extra.toml"),
+            format!("version = 4\ninclude = [\"nested/deep.toml\"]\n{}", routine("extra"))
+        ).unwrap();
+        fs::write(directory.join("more/nested/deep.toml"), format!("version = 4\n{}", routine("deep"))).unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(names(&config), ["main", "extra", "deep"]);
+        // included files inherit the defaults of the files including them
+        assert!(config.routines().iter().all(|r| r.interval == Duration::from_secs(3600).into()));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn files_can_be_included_more_than_once() {
+        let directory = crate::fs_utils::test_dir("load-includes-twice");
+        let path = directory.join("config.toml");
+        fs::write(&path, "version = 4\ninclude = [\"a.toml\", \"b.toml\"]\n").unwrap();
+        fs::write(directory.join("a.toml"), "version = 4\ninclude = [\"shared.toml\"]\n").unwrap();
+        fs::write(directory.join("b.toml"), "version = 4\ninclude = [\"shared.toml\"]\n").unwrap();
+        fs::write(
+            directory.join("shared.toml"),
+            format!("version = 4\n{}interval = \"1h\"\npattern = \"any\"\n", routine("shared"))
+        ).unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(names(&config), ["shared", "shared"]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn include_cycles_are_detected() {
+        let directory = crate::fs_utils::test_dir("load-include-cycle");
+        let path = directory.join("config.toml");
+        fs::write(&path, "version = 4\ninclude = [\"a.toml\"]\n").unwrap();
+        fs::write(directory.join("a.toml"), "version = 4\ninclude = [\"b.toml\"]\n").unwrap();
+        // through a different path to the same file
+        fs::write(directory.join("b.toml"), "version = 4\ninclude = [\"./a.toml\"]\n").unwrap();
+
+        let e = Config::load(&path).err().unwrap();
+        assert!(matches!(&e, ConfigError::IncludeCycle(p) if p == &directory.join("./a.toml")), "{e:?}");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn missing_includes_are_not_created() {
+        let directory = crate::fs_utils::test_dir("load-missing-include");
+        let path = directory.join("config.toml");
+        fs::write(&path, "version = 4\ninclude = [\"missing.toml\"]\n").unwrap();
+
+        let e = Config::load(&path).err().unwrap();
+        assert!(matches!(&e, ConfigError::Io(p, _) if p == &directory.join("missing.toml")), "{e:?}");
+        assert!(!directory.join("missing.toml").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings_unless_strict() {
+        let text = format!("version = 4\ncolour = \"blue\"\n{}interval = \"1h\"\npattern = \"any\"\nintreval = \"2h\"\n", routine("a"));
+
+        let config = parse(&text, Format::Toml);
+        assert_eq!(config.warnings(), ["unknown key colour", "unknown key routines.0.intreval"]);
+
+        let strict = LoadOptions { strict: true, ..Default::default() };
+        let e = Config::parse(&text, Format::Toml, &Table::new(), &strict).err().unwrap();
+        assert_eq!(e.to_string(), "unknown keys: colour, routines.0.intreval");
+        let known = format!("version = 4\n{}pattern = \"any\"", routine("a"));
+        assert!(Config::parse(&known, Format::Toml, &Table::new(), &strict).is_ok());
+    }
+
+    #[test]
+    fn warnings_name_their_file() {
+        let directory = crate::fs_utils::test_dir("load-warnings");
+        let path = directory.join("config.toml");
+        fs::write(&path, "version = 4\ncolour = \"blue\"\n").unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.warnings(), [format!("\"{}\": unknown key colour", path.display())]);
+        let strict = LoadOptions { strict: true, ..Default::default() };
+        let e = Config::load_with(&path, &strict).err().unwrap();
+        assert!(matches!(&e, ConfigError::Parse(p, _) if p == &path), "{e:?}");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn selected_profiles_must_be_defined_somewhere() {
+        let directory = crate::fs_utils::test_dir("load-profiles");
+        let path = directory.join("config.toml");
+        fs::write(&path, format!("version = 4\n[defaults]\npattern = \"any\"\n{}", routine("main"))).unwrap();
+        fs::create_dir(fragment_dir(&path)).unwrap();
+        fs::write(
+            fragment_dir(&path).join("work.toml"),
+            "version = 4\n[[profile.work.routines]]\nname = \"scratch\"\ndirectory = '.'\n"
+        ).unwrap();
+
+        let work = LoadOptions { profile: Some("work".into()), ..Default::default() };
+        assert_eq!(names(&Config::load(&path).unwrap()), ["main"]);
+        assert_eq!(names(&Config::load_with(&path, &work).unwrap()), ["main", "scratch"]);
+
+        let home = LoadOptions { profile: Some("home".into()), ..Default::default() };
+        let e = Config::load_with(&path, &home).err().unwrap();
+        assert!(matches!(&e, ConfigError::UnknownProfile(p, name) if p == &path && name == "home"), "{e:?}");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn lenient_loading_skips_invalid_routines() {
+        let text = format!(
+            "version = 4\n[defaults]\npattern = \"any\"\n{}{}interval = \"soon\"\n{}",
+            routine("a"),
+            routine("b"),
+            routine("c")
+        );
+        assert!(Config::parse(&text, Format::Toml, &Table::new(), &LoadOptions::default()).is_err());
+
+        let lenient = LoadOptions { lenient: true, ..Default::default() };
+        let config = Config::parse(&text, Format::Toml, &Table::new(), &lenient).unwrap();
+        assert_eq!(names(&config), ["a", "c"]);
+        assert_eq!(config.warnings().len(), 1);
+        assert!(config.warnings()[0].starts_with("routines[1]: skipping routine: "), "{:?}", config.warnings());
+    }
+
+    #[test]
+    fn lenient_loading_still_rejects_unknown_keys_when_strict() {
+        let text = format!("version = 4\n{}pattern = \"any\"\nintreval = \"2h\"\n", routine("a"));
+        let options = LoadOptions { lenient: true, strict: true, ..Default::default() };
+        assert!(Config::parse(&text, Format::Toml, &Table::new(), &options).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+//! Tools for upgrading configurations written for older versions.
+//!
+//! Every configuration file has a `version` key that tells which format it
+//! was written in. When an older configuration is loaded, it's upgraded to
+//! the current format one version at a time, so that old configuration files
+//! keep working as the format changes. Keys that are no longer used produce
+//! warnings rather than errors.
+
+use std::time::Duration;
+use serde::de::Error;
+use toml::value::{Table, Value};
+
+use crate::config::duration;
+use crate::fs_utils::FilePattern;
+
+
+/// The version of the configuration format that this program writes.
+pub const CURRENT_VERSION: u32 = 4;
+
+/// A function that upgrades a configuration by one version.
+///
+/// Any warnings about the old configuration, such as deprecated keys, are
+/// pushed into the second argument.
+type Migration = fn(&mut Table, &mut Vec<String>);
+
+/// Migrations indexed by the version they upgrade from.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [
+    from_v0,
+    from_v1,
+    from_v2,
+    from_v3
+];
+
+
+/// Upgrades a configuration to the [current version](CURRENT_VERSION).
+///
+/// Configurations without a `version` key are considered to be version 0,
+/// which is the format used before the key was introduced. Returns any
+/// warnings produced while upgrading.
+///
+/// # Errors
+///
+/// This function returns an error if `config` isn't a table, if its version
+/// isn't a non-negative integer, or if it's newer than what this program
+/// supports.
+pub fn migrate(config: &mut Value) -> Result<Vec<String>, toml::de::Error> {
+    let table = config
+        .as_table_mut()
+        .ok_or_else(|| Error::custom("configuration must be a table"))?;
+
+    let version = match table.get("version") {
+        None => 0,
+        Some(Value::Integer(v)) => u32::try_from(*v)
+            .map_err(|_| Error::custom(format!("invalid version {v}")))?,
+        Some(_) => return Err(Error::custom("version must be an integer"))
+    };
+
+    if version > CURRENT_VERSION {
+        return Err(Error::custom(format!(
+            "version {version} is newer than the supported version {CURRENT_VERSION}"
+        )));
+    }
+
+    let mut warnings = Vec::new();
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(table, &mut warnings);
+    }
+    table.insert("version".into(), Value::Integer(CURRENT_VERSION.into()));
+
+    Ok(warnings)
+}
+
+
+/// Version 0 is identical to version 1 apart from not having a version.
+fn from_v0(_config: &mut Table, _warnings: &mut Vec<String>) {}
+
+/// Version 2 writes intervals as strings like `"1h"` instead of tables of
+/// seconds and nanoseconds.
+fn from_v1(config: &mut Table, warnings: &mut Vec<String>) {
+    for (i, routine) in routines(config).enumerate() {
+        let Some(Value::Table(interval)) = routine.get("interval") else {
+            continue;
+        };
+
+        let secs = interval.get("secs").and_then(Value::as_integer);
+        let nanos = interval.get("nanos").and_then(Value::as_integer);
+        let (Some(secs), Some(nanos)) = (secs, nanos) else {
+            // leave it for deserialization to complain about
+            continue;
+        };
+        let (Ok(secs), Ok(nanos)) = (u64::try_from(secs), u32::try_from(nanos)) else {
+            continue;
+        };
+
+        let text = duration::format(Duration::new(secs, nanos));
+        warnings.push(format!(
+            "routines[{i}].interval: intervals as tables of secs and nanos \
+            are deprecated, use a string like \"{text}\" instead"
+        ));
+        routine.insert("interval".into(), Value::String(text));
+    }
+}
+
+/// Version 3 requires every routine to have a name. Routines without one are
+/// named after their directory.
+fn from_v2(config: &mut Table, warnings: &mut Vec<String>) {
+    let mut taken: Vec<String> = routines(config)
+        .filter_map(|routine| routine.get("name")?.as_str().map(String::from))
+        .collect();
+
+    for (i, routine) in routines(config).enumerate() {
+        if routine.contains_key("name") {
+            continue;
+        }
+
+        let base = routine
+            .get("directory")
+            .and_then(Value::as_str)
+            .map(directory_name)
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "routine".into());
+
+        // routines cleaning different directories with the same name
+        // still need different names
+        let mut name = base.clone();
+        let mut n = 1;
+        while taken.contains(&name) {
+            n += 1;
+            name = format!("{base}-{n}");
+        }
+        taken.push(name.clone());
+
+        warnings.push(format!(
+            "routines[{i}]: routines should have a name, using \"{name}\""
+        ));
+        routine.insert("name".into(), Value::String(name));
+    }
+}
+
+/// Version 4 writes patterns as strings like `"ext(lnk)"` or tables with a
+/// `type` key, instead of the names of enum variants like `"Any"` or
+/// `{ Extension = "lnk" }`.
+fn from_v3(config: &mut Table, warnings: &mut Vec<String>) {
+    let mut convert = |location: String, table: &mut Table| {
+        let new = match table.get("pattern") {
+            Some(Value::String(s)) if s == "Any" => "any".to_string(),
+            Some(Value::Table(t)) if t.len() == 1 => match t.get("Extension") {
+                Some(Value::String(ext)) => FilePattern::Extension(ext.clone()).to_string(),
+                _ => return
+            },
+            _ => return
+        };
+
+        warnings.push(format!(
+            "{location}.pattern: this way of writing patterns is deprecated, \
+            use \"{new}\" instead"
+        ));
+        table.insert("pattern".into(), Value::String(new));
+    };
+
+    if let Some(Value::Table(defaults)) = config.get_mut("defaults") {
+        convert("defaults".into(), defaults);
+    }
+    for (i, routine) in routines(config).enumerate() {
+        convert(format!("routines[{i}]"), routine);
+    }
+}
+
+
+/// The last component of a directory path, regardless of which separators
+/// it uses.
+fn directory_name(directory: &str) -> String {
+    directory
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The routine tables of a configuration, skipping anything that isn't one.
+pub(crate) fn routines(config: &mut Table) -> impl Iterator<Item = &mut Table> {
+    config
+        .get_mut("routines")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_table_mut)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Upgrades the configuration in `toml`, returning it and the warnings.
+    fn migrated(toml: &str) -> (Value, Vec<String>) {
+        let mut config: Value = toml::from_str(toml).unwrap();
+        let warnings = migrate(&mut config).unwrap();
+        (config, warnings)
+    }
+
+    /// The value of `key` in the routine at `index` of `config`.
+    fn routine_key<'a>(config: &'a Value, index: usize, key: &str) -> &'a Value {
+        &config["routines"][index][key]
+    }
+
+    #[test]
+    fn configurations_without_a_version_are_upgraded() {
+        let (config, warnings) = migrated("");
+        assert_eq!(config["version"].as_integer(), Some(CURRENT_VERSION.into()));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn current_configurations_are_left_alone() {
+        let toml = "version = 4\n[[routines]]\nname = \"a\"\ninterval = { secs = 60, nanos = 0 }\npattern = \"Any\"";
+        let (config, warnings) = migrated(toml);
+        assert_eq!(config, toml::from_str::<Value>(toml).unwrap());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn interval_tables_become_strings() {
+        let (config, warnings) = migrated("version = 1\n[[routines]]\nname = \"a\"\ninterval = { secs = 5400, nanos = 0 }");
+        assert_eq!(routine_key(&config, 0, "interval").as_str(), Some("1h30m"));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("routines[0].interval: "), "{}", warnings[0]);
+    }
+
+    #[test]
+    fn routines_are_named_after_their_directory() {
+        let (config, warnings) = migrated(r#"
+version = 2
+
+[[routines]]
+directory = 'C:\Users\user\Downloads\'
+
+[[routines]]
+directory = "/home/user/Downloads"
+
+[[routines]]
+name = "Downloads-2"
+
+[[routines]]
+directory = "/"
+"#);
+        let names: Vec<_> = (0..4).map(|i| routine_key(&config, i, "name").as_str().unwrap()).collect();
+        assert_eq!(names, ["Downloads", "Downloads-3", "Downloads-2", "routine"]);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[1], "routines[1]: routines should have a name, using \"Downloads-3\"");
+    }
+
+    #[test]
+    fn enum_patterns_become_strings() {
+        let (config, warnings) = migrated(r#"
+version = 3
+
+[defaults]
+pattern = "Any"
+
+[[routines]]
+name = "a"
+pattern = { Extension = "lnk" }
+
+[[routines]]
+name = "b"
+pattern = "ext(tmp)"
+"#);
+        assert_eq!(config["defaults"]["pattern"].as_str(), Some("any"));
+        assert_eq!(routine_key(&config, 0, "pattern").as_str(), Some("ext(lnk)"));
+        assert_eq!(routine_key(&config, 1, "pattern").as_str(), Some("ext(tmp)"));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("defaults.pattern: "), "{}", warnings[0]);
+        assert!(warnings[1].starts_with("routines[0].pattern: "), "{}", warnings[1]);
+    }
+
+    #[test]
+    fn unsupported_versions_are_rejected() {
+        for toml in ["version = 5", "version = -1", "version = \"4\""] {
+            let mut config: Value = toml::from_str(toml).unwrap();
+            assert!(migrate(&mut config).is_err(), "{toml}");
+        }
+        assert!(migrate(&mut Value::Integer(4)).is_err());
+    }
+}
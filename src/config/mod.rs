@@ -0,0 +1,106 @@
+//! Tools for loading the program's configuration.
+//!
+//! The configuration is stored as a TOML, JSON or YAML file and describes the
+//! [routines](crate::routine) the program should run. Additional routines
+//! can be kept in separate files inside a [fragment directory](fragment_dir)
+//! next to the main configuration file, and settings shared by many routines
+//! can be given [defaults]. Routines that are only needed some of the time
+//! can be grouped into [profiles](profile). A loaded configuration
+//! can be checked for problems with [`Config::validate`], and the [`watch`]
+//! module can be used to pick up changes to the file while the program is
+//! running.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+
+use crate::routine::Routine;
+
+pub mod defaults;
+pub mod duration;
+pub mod error;
+pub mod expand;
+pub mod format;
+mod load;
+pub mod migrate;
+mod path;
+pub mod profile;
+pub mod size;
+pub mod validate;
+pub mod watch;
+
+#[doc(inline)]
+pub use error::ConfigError;
+#[doc(inline)]
+pub use format::Format;
+#[doc(inline)]
+pub use load::{fragment_dir, init, LoadOptions};
+#[doc(inline)]
+pub use path::{default_path, state_dir, PATH_VARIABLE};
+#[doc(inline)]
+pub use validate::ValidationError;
+
+
+#[derive(Serialize, Deserialize)]
+pub struct Config {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<PathBuf>,
+    #[serde(default)]
+    routines: std::vec::Vec<Routine>,
+
+    #[serde(skip)]
+    defaults: toml::Table,
+    #[serde(skip)]
+    profiles: BTreeSet<String>,
+    #[serde(skip)]
+    warnings: Vec<String>
+}
+
+impl Config {
+    /// The routines this configuration describes.
+    pub fn routines(&self) -> &[Routine] {
+        &self.routines
+    }
+
+    /// The version of the configuration format.
+    ///
+    /// A loaded configuration has always been upgraded to
+    /// [the current version](migrate::CURRENT_VERSION).
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Warnings about the configuration file, such as deprecated keys.
+    ///
+    /// Problems that warrant a warning don't prevent a configuration from
+    /// being loaded, but the user should still be told about them.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The names of every profile defined in the configuration files.
+    pub fn profiles(&self) -> impl Iterator<Item = &str> {
+        self.profiles.iter().map(String::as_str)
+    }
+
+    /// Consumes the configuration, returning its routines.
+    pub fn into_routines(self) -> Vec<Routine> {
+        self.routines
+    }
+}
+
+impl Default for Config {
+    /// An empty configuration with no routines.
+    fn default() -> Self {
+        Config {
+            version: migrate::CURRENT_VERSION,
+            include: Vec::new(),
+            routines: Vec::new(),
+            defaults: toml::Table::new(),
+            profiles: BTreeSet::new(),
+            warnings: Vec::new()
+        }
+    }
+}
+
@@ -0,0 +1,88 @@
+use std::env;
+use std::path::PathBuf;
+
+use crate::config::expand::UndefinedVariable;
+
+
+/// The name of the directory the configuration is stored in.
+const APP_NAME: &str = "folder_cleaner";
+
+/// The name of the main configuration file.
+const FILE_NAME: &str = "config.toml";
+
+/// The environment variable that overrides the configuration file path.
+pub const PATH_VARIABLE: &str = "FOLDER_CLEANER_CONFIG";
+
+
+/// The path the configuration file is stored at by default.
+///
+/// If the [`FOLDER_CLEANER_CONFIG`](PATH_VARIABLE) environment variable is
+/// set, its value is used as the path. Otherwise, the file is called
+/// `config.toml` and is located in a `folder_cleaner` directory inside the
+/// platform's configuration directory:
+///
+/// - `%APPDATA%` on Windows
+/// - `$XDG_CONFIG_HOME` on other platforms, or `~/.config` if it isn't set
+///
+/// # Errors
+///
+/// This function returns an error if the environment variable that the
+/// platform's configuration directory is based on isn't defined.
+pub fn default_path() -> Result<PathBuf, UndefinedVariable> {
+    if let Some(path) = env::var_os(PATH_VARIABLE) {
+        return Ok(PathBuf::from(path));
+    }
+
+    Ok(config_dir()?.join(APP_NAME).join(FILE_NAME))
+}
+
+
+/// The directory the program keeps its own records in, such as the journals
+/// of routines.
+///
+/// The directory is called `folder_cleaner` and is located inside the
+/// platform's directory for such records:
+///
+/// - `%LOCALAPPDATA%` on Windows
+/// - `$XDG_STATE_HOME` on other platforms, or `~/.local/state` if it isn't
+///   set
+///
+/// # Errors
+///
+/// This function returns an error if the environment variable that the
+/// platform's directory is based on isn't defined.
+pub fn state_dir() -> Result<PathBuf, UndefinedVariable> {
+    Ok(platform_state_dir()?.join(APP_NAME))
+}
+
+
+#[cfg(windows)]
+fn config_dir() -> Result<PathBuf, UndefinedVariable> {
+    variable("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(not(windows))]
+fn config_dir() -> Result<PathBuf, UndefinedVariable> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        // relative paths are invalid according to the XDG specification
+        Some(dir) if PathBuf::from(&dir).is_absolute() => Ok(PathBuf::from(dir)),
+        _ => variable("HOME").map(|home| PathBuf::from(home).join(".config"))
+    }
+}
+
+#[cfg(windows)]
+fn platform_state_dir() -> Result<PathBuf, UndefinedVariable> {
+    variable("LOCALAPPDATA").map(PathBuf::from)
+}
+
+#[cfg(not(windows))]
+fn platform_state_dir() -> Result<PathBuf, UndefinedVariable> {
+    match env::var_os("XDG_STATE_HOME") {
+        Some(dir) if PathBuf::from(&dir).is_absolute() => Ok(PathBuf::from(dir)),
+        _ => variable("HOME").map(|home| PathBuf::from(home).join(".local/state"))
+    }
+}
+
+fn variable(name: &str) -> Result<std::ffi::OsString, UndefinedVariable> {
+    env::var_os(name).ok_or_else(|| UndefinedVariable::new(name))
+}
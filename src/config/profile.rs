@@ -0,0 +1,150 @@
+//! Tools for switching between sets of routines.
+//!
+//! A configuration can define named profiles in its `profile` table. Each
+//! profile can have routines and defaults of its own, which are only used
+//! when the profile is selected while [loading](crate::config::LoadOptions)
+//! the configuration. Routines outside of any profile are always used.
+//!
+//! # Examples
+//!
+//! ```toml
+//! [[routines]]
+//! name = "downloads"
+//! directory = "~/Downloads"
+//! interval = "1d"
+//! pattern = "any"
+//!
+//! [[profile.work.routines]]
+//! name = "scratch"
+//! directory = "D:/scratch"
+//! interval = "1h"
+//! pattern = "any"
+//! ```
+
+use serde::de::Error;
+use toml::Value;
+
+
+/// The name of the table that holds profiles.
+const PROFILES_KEY: &str = "profile";
+
+
+/// Merges the selected profile of `config` into the rest of it.
+///
+/// The routines of the profile are added after the other routines, and its
+/// defaults override the other defaults. The profile table is removed from
+/// `config`.
+///
+/// Returns the names of every profile `config` defines.
+///
+/// # Errors
+///
+/// This function returns an error if the profile table, or the selected
+/// profile in it, isn't structured correctly.
+pub(crate) fn select(
+    config: &mut Value,
+    selected: Option<&str>
+) -> Result<Vec<String>, toml::de::Error> {
+    let Some(table) = config.as_table_mut() else {
+        return Ok(Vec::new());
+    };
+
+    let profiles = match table.remove(PROFILES_KEY) {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => return Err(Error::custom("profile must be a table")),
+        None => return Ok(Vec::new())
+    };
+    let names = profiles.keys().cloned().collect();
+
+    let Some(profile) = selected.and_then(|name| profiles.get(name)) else {
+        return Ok(names);
+    };
+    let Value::Table(profile) = profile else {
+        return Err(Error::custom("profiles must be tables"));
+    };
+
+    for (key, value) in profile {
+        match (key.as_str(), value, table.get_mut(key)) {
+            ("routines", Value::Array(new), Some(Value::Array(routines))) => {
+                routines.extend(new.iter().cloned());
+            }
+            ("defaults", Value::Table(new), Some(Value::Table(defaults))) => {
+                defaults.extend(new.clone());
+            }
+            // anything else is simply added, so that it gets complained
+            // about later if it doesn't make sense
+            (key, value, _) => {
+                table.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+[defaults]
+interval = "1d"
+pattern = "any"
+
+[[routines]]
+name = "downloads"
+
+[profile.work.defaults]
+interval = "1h"
+
+[[profile.work.routines]]
+name = "scratch"
+
+[profile.home]
+"#;
+
+    /// The names of the routines of `config`, in order.
+    fn names(config: &Value) -> Vec<&str> {
+        config["routines"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|routine| routine["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn profiles_are_only_used_when_selected() {
+        let mut config: Value = toml::from_str(CONFIG).unwrap();
+        let profiles = select(&mut config, None).unwrap();
+        assert_eq!(profiles, ["home", "work"]);
+        assert_eq!(names(&config), ["downloads"]);
+        assert_eq!(config["defaults"]["interval"].as_str(), Some("1d"));
+        assert!(config.get(PROFILES_KEY).is_none());
+    }
+
+    #[test]
+    fn selected_profiles_add_routines_and_override_defaults() {
+        let mut config: Value = toml::from_str(CONFIG).unwrap();
+        select(&mut config, Some("work")).unwrap();
+        assert_eq!(names(&config), ["downloads", "scratch"]);
+        assert_eq!(config["defaults"]["interval"].as_str(), Some("1h"));
+        assert_eq!(config["defaults"]["pattern"].as_str(), Some("any"));
+    }
+
+    #[test]
+    fn profiles_can_add_what_the_rest_does_not_have() {
+        let mut config: Value = toml::from_str("[[profile.work.routines]]\nname = \"scratch\"").unwrap();
+        select(&mut config, Some("work")).unwrap();
+        assert_eq!(names(&config), ["scratch"]);
+    }
+
+    #[test]
+    fn profiles_must_be_tables() {
+        for toml in ["profile = 1", "profile.work = 1"] {
+            let mut config: Value = toml::from_str(toml).unwrap();
+            assert!(select(&mut config, Some("work")).is_err(), "{toml}");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Tools for writing file sizes as human-readable strings.
+//!
+//! Sizes are written as a number followed by an optional unit, such as
+//! `"500MB"`, `"1.5GB"` or `"64KiB"`. Decimal units (`KB`, `MB`, `GB`,
+//! `TB`) are powers of 1000, binary units (`KiB`, `MiB`, `GiB`, `TiB`) are
+//! powers of 1024, and a number without a unit, or with the unit `B`, is a
+//! number of bytes. Units are case-insensitive.
+//!
+//! The [`serialize`] and [`deserialize`] functions allow this module to be
+//! used with serde's `with` attribute.
+//!
+//! # Examples
+//!
+//! ```
+//! use folder_cleaner::config::size;
+//!
+//! let limit = size::parse("500MB").unwrap();
+//! assert_eq!(limit, 500_000_000);
+//! assert_eq!(size::format(limit), "500MB");
+//! assert_eq!(size::format(2048), "2KiB");
+//! ```
+
+use serde::de::{self, Visitor};
+use serde::{Deserializer, Serializer};
+
+
+/// The supported units and their sizes in bytes, largest first.
+const UNITS: [(&str, u64); 9] = [
+    ("TiB", 1 << 40),
+    ("TB", 1_000_000_000_000),
+    ("GiB", 1 << 30),
+    ("GB", 1_000_000_000),
+    ("MiB", 1 << 20),
+    ("MB", 1_000_000),
+    ("KiB", 1 << 10),
+    ("KB", 1000),
+    ("B", 1)
+];
+
+
+/// Represents errors that occur when a string isn't a valid size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSizeError {
+    input: String,
+    reason: &'static str
+}
+
+impl ParseSizeError {
+    fn new(input: &str, reason: &'static str) -> Self {
+        ParseSizeError { input: input.to_string(), reason }
+    }
+}
+
+impl std::fmt::Display for ParseSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid size \"{}\": {} (expected something like \"500MB\")",
+            self.input,
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for ParseSizeError {}
+
+
+/// Parses a size in bytes from a string such as `"500MB"`.
+///
+/// The number can have a fractional part, as in `"1.5GB"`, and it can be
+/// separated from the unit by whitespace. Fractions of a byte are rounded
+/// down.
+///
+/// # Errors
+///
+/// This function returns an error if the string doesn't start with a
+/// number, if the unit isn't supported, or if the size is too large to be
+/// represented.
+pub fn parse(s: &str) -> Result<u64, ParseSizeError> {
+    let error = |reason| ParseSizeError::new(s, reason);
+
+    let trimmed = s.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    if !number.contains(|c: char| c.is_ascii_digit()) {
+        return Err(error("expected a number"));
+    }
+
+    let unit = unit.trim_start();
+    let (_, bytes) = match unit.is_empty() {
+        true => ("B", 1),
+        false => *UNITS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+            .ok_or_else(|| error("unknown unit"))?
+    };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let whole: u64 = match whole.is_empty() {
+        true => 0,
+        false => whole.parse().map_err(|_| error("number is too large"))?
+    };
+    if fraction.contains('.') {
+        return Err(error("expected a number"));
+    }
+
+    // the fraction is worked out in integers to avoid rounding errors
+    let mut part = 0;
+    let mut scale = bytes;
+    for digit in fraction.bytes() {
+        scale /= 10;
+        part += u64::from(digit - b'0') * scale;
+    }
+
+    whole
+        .checked_mul(bytes)
+        .and_then(|total| total.checked_add(part))
+        .ok_or_else(|| error("size is too large"))
+}
+
+/// Formats a size into a string that [`parse`] accepts.
+///
+/// The size is written using the largest unit it's a whole multiple of, so
+/// 2048 bytes becomes `"2KiB"` and 1500 bytes becomes `"1500B"`.
+pub fn format(bytes: u64) -> String {
+    let (name, size) = UNITS
+        .iter()
+        .find(|(_, size)| bytes != 0 && bytes.is_multiple_of(*size))
+        .unwrap_or(&("B", 1));
+    format!("{}{name}", bytes / size)
+}
+
+
+/// Serializes a size as a human-readable string. See [`format`](fn@format).
+pub fn serialize<S: Serializer>(bytes: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(*bytes))
+}
+
+/// Deserializes a size from a human-readable string or a number of bytes.
+/// See [`parse`].
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    deserializer.deserialize_any(SizeVisitor)
+}
+
+struct SizeVisitor;
+
+impl Visitor<'_> for SizeVisitor {
+    type Value = u64;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a size like \"500MB\" or a number of bytes")
+    }
+
+    fn visit_u64<E: de::Error>(self, bytes: u64) -> Result<Self::Value, E> {
+        Ok(bytes)
+    }
+
+    fn visit_i64<E: de::Error>(self, bytes: i64) -> Result<Self::Value, E> {
+        u64::try_from(bytes).map_err(|_| E::custom("size must not be negative"))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        parse(s).map_err(E::custom)
+    }
+}
@@ -0,0 +1,594 @@
+//! Tools for checking a configuration for problems.
+//!
+//! A configuration can be perfectly valid TOML and still describe routines
+//! that can't work as intended, for example because a directory has been
+//! misspelled. This module finds such problems so that they can be reported
+//! to the user before any routine is run.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::Config;
+use crate::fs_utils::{self, Action, FilePattern};
+use crate::routine::{Hook, Hooks, Retry, Routine};
+
+
+/// A problem found in one of the routines of a configuration.
+///
+/// Contains the index and name of the routine in the configuration, the
+/// name of the field that has the problem and a description of the problem
+/// itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    routine: usize,
+    name: String,
+    field: &'static str,
+    kind: ValidationErrorKind
+}
+
+impl ValidationError {
+    /// Creates a new error for a field of the routine at index `routine`.
+    pub fn new(
+        routine: usize,
+        name: &str,
+        field: &'static str,
+        kind: ValidationErrorKind
+    ) -> Self {
+        ValidationError { routine, name: name.to_string(), field, kind }
+    }
+
+    /// The index of the routine that has the problem.
+    pub fn routine(&self) -> usize {
+        self.routine
+    }
+
+    /// The name of the routine that has the problem.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the routine field that has the problem.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// The problem itself.
+    pub fn kind(&self) -> &ValidationErrorKind {
+        &self.kind
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "routine \"{}\" (routines[{}]) {}: {}",
+            self.name,
+            self.routine,
+            self.field,
+            self.kind
+        )
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+
+/// The kinds of problems a routine can have.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// The name is empty.
+    EmptyName,
+    /// Another routine has the same name.
+    DuplicateName,
+    /// The directory doesn't exist.
+    DirectoryNotFound(PathBuf),
+    /// The path exists but isn't a directory.
+    NotADirectory(PathBuf),
+    /// The interval is zero and the routine neither is scheduled otherwise
+    /// nor runs once, so it would run constantly.
+    ZeroInterval,
+    /// A schedule has no times in it, such as one for February 30th.
+    NeverScheduled,
+    /// An extension pattern has an empty extension.
+    EmptyExtension,
+    /// A pattern for any of a list of extensions has none in the list.
+    NoExtensions,
+    /// A pattern looks for an empty text in names, which every name has.
+    EmptyNamePart,
+    /// A content pattern looks for an empty text, which every file has.
+    EmptyContent,
+    /// A wildcard pattern isn't valid, for the given reason.
+    InvalidGlob(String),
+    /// A regular expression doesn't compile, for the given reason.
+    InvalidRegex(String),
+    /// An owner isn't written the way owners are on this platform, for the
+    /// given reason.
+    InvalidOwner(String),
+    /// A date format isn't valid, for the given reason.
+    InvalidDateFormat(String),
+    /// The action would put entries back into the directory they were
+    /// taken from.
+    DestinationIsDirectory,
+    /// A tag has neither a prefix nor a suffix, so it wouldn't change any
+    /// names.
+    EmptyTag,
+    /// A command has no program to run.
+    EmptyCommand,
+    /// A file would be shredded without being overwritten even once.
+    ZeroPasses,
+    /// An upload URL isn't an HTTP or HTTPS URL.
+    InvalidUrl(String),
+    /// A layout of dated directories isn't valid, for the given reason.
+    InvalidLayout(String),
+    /// A retry doesn't allow the action to be tried even once.
+    ZeroAttempts,
+    /// A fallback chain has no actions to try.
+    EmptyFallback,
+    /// A maximum depth is set for a routine that isn't recursive, which
+    /// only cleans the top level of its directory.
+    DepthWithoutRecursion,
+    /// The jitter is more than the interval itself.
+    JitterTooLarge(u32),
+    /// A jitter is set for a scheduled routine or one that runs once, which
+    /// don't wait for intervals.
+    JitterWithoutInterval,
+    /// The routine waits for the user to be idle on a platform where how
+    /// long they have been can't be found out, so it would never wait.
+    IdleUnsupported
+}
+
+impl std::fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyName
+            => write!(f, "name must not be empty"),
+
+            Self::DuplicateName
+            => write!(f, "another routine has the same name"),
+
+            Self::DirectoryNotFound(path)
+            => write!(f, "\"{}\" doesn't exist", path.display()),
+
+            Self::NotADirectory(path)
+            => write!(f, "\"{}\" isn't a directory", path.display()),
+
+            Self::ZeroInterval
+            => write!(f, "interval must be longer than zero"),
+
+            Self::NeverScheduled
+            => write!(f, "schedule never comes around"),
+
+            Self::EmptyExtension
+            => write!(f, "extension must not be empty"),
+
+            Self::NoExtensions
+            => write!(f, "list of extensions must not be empty"),
+
+            Self::EmptyNamePart
+            => write!(f, "text to look for in names must not be empty"),
+
+            Self::EmptyContent
+            => write!(f, "text to look for in files must not be empty"),
+
+            Self::InvalidGlob(reason)
+            => write!(f, "invalid wildcard pattern: {reason}"),
+
+            Self::InvalidRegex(reason)
+            => write!(f, "invalid regular expression: {reason}"),
+
+            Self::InvalidOwner(reason)
+            => write!(f, "invalid owner: {reason}"),
+
+            Self::InvalidDateFormat(reason)
+            => write!(f, "invalid date format: {reason}"),
+
+            Self::DestinationIsDirectory
+            => write!(f, "destination must not be the directory being cleaned"),
+
+            Self::EmptyTag
+            => write!(f, "tag must have a prefix or a suffix"),
+
+            Self::EmptyCommand
+            => write!(f, "command must have a program"),
+
+            Self::ZeroPasses
+            => write!(f, "shredding must overwrite at least once"),
+
+            Self::InvalidUrl(url)
+            => write!(f, "\"{url}\" isn't an HTTP or HTTPS URL"),
+
+            Self::InvalidLayout(reason)
+            => write!(f, "invalid layout: {reason}"),
+
+            Self::ZeroAttempts
+            => write!(f, "retry must allow at least one attempt"),
+
+            Self::EmptyFallback
+            => write!(f, "fallback must have at least one action"),
+
+            Self::DepthWithoutRecursion
+            => write!(f, "maximum depth is only used by recursive routines"),
+
+            Self::JitterTooLarge(jitter)
+            => write!(f, "jitter of {jitter}% is more than 100% of the interval"),
+
+            Self::JitterWithoutInterval
+            => write!(f, "jitter is only used by routines that run every interval"),
+
+            Self::IdleUnsupported
+            => write!(f, "waiting for the user to be idle only works on Windows")
+        }
+    }
+}
+
+
+impl Config {
+    /// Checks every routine of the configuration for problems.
+    ///
+    /// Returns every problem that was found, so an empty list means the
+    /// configuration is valid. See [`ValidationErrorKind`] for the problems
+    /// that are checked for.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use folder_cleaner::config::Config;
+    ///
+    /// let config = Config::load(r"C:\path\to\config.toml").unwrap();
+    /// for error in config.validate() {
+    ///     eprintln!("{error}");
+    /// }
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let mut names = HashSet::new();
+
+        for (i, routine) in self.routines().iter().enumerate() {
+            if !names.insert(routine.name.as_str()) {
+                errors.push(ValidationError::new(
+                    i,
+                    &routine.name,
+                    "name",
+                    ValidationErrorKind::DuplicateName
+                ));
+            }
+            errors.extend(validate_routine(i, routine));
+        }
+
+        errors
+    }
+
+    /// Removes every routine that has problems from the configuration.
+    ///
+    /// Returns the problems that were found, like [`validate`](Self::validate()).
+    /// The indices in them refer to the routines as they were before any
+    /// were removed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use folder_cleaner::config::Config;
+    ///
+    /// let mut config = Config::load(r"C:\path\to\config.toml").unwrap();
+    /// for error in config.retain_valid() {
+    ///     eprintln!("skipping {error}");
+    /// }
+    /// ```
+    pub fn retain_valid(&mut self) -> Vec<ValidationError> {
+        let errors = self.validate();
+        let mut i = 0;
+        self.routines.retain(|_| {
+            let valid = !errors.iter().any(|e| e.routine == i);
+            i += 1;
+            valid
+        });
+        errors
+    }
+}
+
+
+/// Checks a single routine for problems.
+fn validate_routine(index: usize, routine: &Routine) -> Vec<ValidationError> {
+    // the directory of a disabled routine is allowed to be missing for now
+    let directory = match routine.enabled {
+        true => check_directory(&routine.directory),
+        false => Ok(())
+    };
+
+    let checks = [
+        ("name", check_name(&routine.name)),
+        ("directory", directory),
+        ("interval", check_interval(routine)),
+        ("schedule", check_schedule(routine)),
+        ("pattern", check_pattern(&routine.pattern)),
+        ("action", check_action(routine)),
+        ("hooks", check_hooks(&routine.hooks)),
+        ("max_depth", check_depth(routine)),
+        ("retry", check_retry(routine.retry)),
+        ("jitter", check_jitter(routine)),
+        ("idle", check_idle(routine))
+    ];
+
+    checks
+        .into_iter()
+        .filter_map(|(field, check)| {
+            check.err().map(|kind| {
+                ValidationError::new(index, &routine.name, field, kind)
+            })
+        })
+        .collect()
+}
+
+fn check_name(name: &str) -> Result<(), ValidationErrorKind> {
+    match name.is_empty() {
+        true => Err(ValidationErrorKind::EmptyName),
+        false => Ok(())
+    }
+}
+
+fn check_directory(path: &Path) -> Result<(), ValidationErrorKind> {
+    match path.metadata() {
+        Ok(m) if m.is_dir() => Ok(()),
+        Ok(_) => Err(ValidationErrorKind::NotADirectory(path.to_path_buf())),
+        Err(_) => Err(ValidationErrorKind::DirectoryNotFound(path.to_path_buf()))
+    }
+}
+
+fn check_interval(routine: &Routine) -> Result<(), ValidationErrorKind> {
+    // std::time::Duration can't be negative, so zero is the only bad value,
+    // and the interval isn't used by scheduled routines or those that run
+    // once, or needed by those that watch their directory
+    let is_needed = !routine.is_scheduled() && !routine.once && routine.watch_delay().is_none();
+    match routine.interval.is_zero() && is_needed {
+        true => Err(ValidationErrorKind::ZeroInterval),
+        false => Ok(())
+    }
+}
+
+fn check_schedule(routine: &Routine) -> Result<(), ValidationErrorKind> {
+    match routine.is_scheduled() && routine.next_scheduled_run(SystemTime::now()).is_none() {
+        true => Err(ValidationErrorKind::NeverScheduled),
+        false => Ok(())
+    }
+}
+
+fn check_action(routine: &Routine) -> Result<(), ValidationErrorKind> {
+    check_action_in(&routine.action, &routine.directory)
+}
+
+/// Checks an action of a routine that cleans `directory`, along with the
+/// actions in it if it's a chain.
+fn check_action_in(action: &Action, directory: &Path) -> Result<(), ValidationErrorKind> {
+    if let Action::MoveByDate { layout, .. } = action {
+        fs_utils::check_layout(layout).map_err(ValidationErrorKind::InvalidLayout)?;
+    }
+    match action {
+        Action::Fallback(actions) if actions.is_empty()
+        => Err(ValidationErrorKind::EmptyFallback),
+
+        Action::Fallback(actions)
+        => actions.iter().try_for_each(|action| check_action_in(action, directory)),
+
+        Action::Tag { prefix, suffix } if prefix.is_empty() && suffix.is_empty()
+        => Err(ValidationErrorKind::EmptyTag),
+
+        Action::Command { program, .. } if program.is_empty()
+        => Err(ValidationErrorKind::EmptyCommand),
+
+        Action::Shred(0)
+        => Err(ValidationErrorKind::ZeroPasses),
+
+        Action::Upload(target)
+        if !["http://", "https://"].iter().any(|scheme| target.url.starts_with(scheme))
+        => Err(ValidationErrorKind::InvalidUrl(target.url.clone())),
+
+        // entries moved into the directory they came from would be matched
+        // again and renamed on every run
+        action if action.is_destination(directory, directory)
+        => Err(ValidationErrorKind::DestinationIsDirectory),
+
+        _ => Ok(())
+    }
+}
+
+fn check_hooks(hooks: &Hooks) -> Result<(), ValidationErrorKind> {
+    let hooks = [&hooks.on_start, &hooks.on_complete, &hooks.on_error];
+    match hooks.into_iter().flatten().any(|hook| *hook == Hook::Command(Vec::new())) {
+        true => Err(ValidationErrorKind::EmptyCommand),
+        false => Ok(())
+    }
+}
+
+fn check_depth(routine: &Routine) -> Result<(), ValidationErrorKind> {
+    match routine.max_depth.is_some() && !routine.recursive {
+        true => Err(ValidationErrorKind::DepthWithoutRecursion),
+        false => Ok(())
+    }
+}
+
+fn check_retry(retry: Option<Retry>) -> Result<(), ValidationErrorKind> {
+    match retry {
+        Some(Retry { attempts: 0, .. }) => Err(ValidationErrorKind::ZeroAttempts),
+        _ => Ok(())
+    }
+}
+
+fn check_jitter(routine: &Routine) -> Result<(), ValidationErrorKind> {
+    match routine.jitter {
+        Some(_) if routine.is_scheduled() || routine.once => Err(ValidationErrorKind::JitterWithoutInterval),
+        Some(jitter) if jitter > 100 => Err(ValidationErrorKind::JitterTooLarge(jitter)),
+        _ => Ok(())
+    }
+}
+
+fn check_idle(routine: &Routine) -> Result<(), ValidationErrorKind> {
+    match !routine.idle.is_zero() && !cfg!(windows) {
+        true => Err(ValidationErrorKind::IdleUnsupported),
+        false => Ok(())
+    }
+}
+
+fn check_pattern(pattern: &FilePattern) -> Result<(), ValidationErrorKind> {
+    match pattern {
+        FilePattern::Extension(ext) if ext.is_empty()
+        => Err(ValidationErrorKind::EmptyExtension),
+
+        FilePattern::Extensions(exts) | FilePattern::NotExtension(exts)
+        if exts.is_empty()
+        => Err(ValidationErrorKind::NoExtensions),
+
+        FilePattern::Extensions(exts) | FilePattern::NotExtension(exts)
+        if exts.iter().any(String::is_empty)
+        => Err(ValidationErrorKind::EmptyExtension),
+
+        FilePattern::NameStartsWith(part) | FilePattern::NameEndsWith(part) | FilePattern::NameContains(part)
+        if part.is_empty()
+        => Err(ValidationErrorKind::EmptyNamePart),
+
+        FilePattern::Content(text, _) if text.is_empty()
+        => Err(ValidationErrorKind::EmptyContent),
+
+        FilePattern::Glob(glob) | FilePattern::RelativePath(glob)
+        => fs_utils::check_glob(glob).map_err(ValidationErrorKind::InvalidGlob),
+
+        FilePattern::ContentType(mime)
+        => fs_utils::check_glob(mime).map_err(ValidationErrorKind::InvalidGlob),
+
+        FilePattern::Regex(regex) | FilePattern::ContentRegex(regex, _) => match regex.error() {
+            Some(e) => Err(ValidationErrorKind::InvalidRegex(e.to_string())),
+            None => Ok(())
+        },
+
+        FilePattern::NameDate(format, _)
+        => fs_utils::check_date_format(format).map_err(ValidationErrorKind::InvalidDateFormat),
+
+        FilePattern::Owner(owner)
+        => fs_utils::check_owner(owner).map_err(ValidationErrorKind::InvalidOwner),
+
+        FilePattern::All(patterns) | FilePattern::AnyOf(patterns)
+        => patterns.iter().try_for_each(check_pattern),
+
+        FilePattern::Not(pattern) | FilePattern::KeepNewest(_, pattern)
+        => check_pattern(pattern),
+
+        _ => Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The fields and problems of a routine with the settings in `toml`,
+    /// which cleans the current directory unless they say otherwise.
+    fn problems_with(toml: &str) -> Vec<(&'static str, ValidationErrorKind)> {
+        let toml = match toml.contains("directory") {
+            true => format!("name = \"test\"\n{toml}"),
+            false => format!("name = \"test\"\ndirectory = '.'\n{toml}")
+        };
+        let routine: Routine = toml::from_str(&toml).unwrap();
+        validate_routine(0, &routine).into_iter().map(|e| (e.field, e.kind)).collect()
+    }
+
+    /// A configuration with routines called `names`, each cleaning the
+    /// current directory every hour.
+    fn config_with(names: &[&str]) -> Config {
+        let routines: String = names
+            .iter()
+            .map(|name| format!("[[routines]]\nname = \"{name}\"\ndirectory = '.'\ninterval = \"1h\"\npattern = \"any\"\n"))
+            .collect();
+        toml::from_str(&format!("version = 4\n{routines}")).unwrap()
+    }
+
+    #[test]
+    fn valid_routines_have_no_problems() {
+        assert!(problems_with("interval = \"1h\"\npattern = \"any\"").is_empty());
+        assert!(config_with(&["a", "b"]).validate().is_empty());
+    }
+
+    #[test]
+    fn names_must_not_be_empty() {
+        let problems = config_with(&["a", ""]).validate();
+        assert_eq!(problems, [ValidationError::new(1, "", "name", ValidationErrorKind::EmptyName)]);
+    }
+
+    #[test]
+    fn names_must_be_unique() {
+        let problems = config_with(&["a", "b", "a"]).validate();
+        assert_eq!(problems, [ValidationError::new(2, "a", "name", ValidationErrorKind::DuplicateName)]);
+        assert_eq!(problems[0].to_string(), "routine \"a\" (routines[2]) name: another routine has the same name");
+    }
+
+    #[test]
+    fn directories_must_exist() {
+        let directory = fs_utils::test_dir("validate-directories");
+        let file = directory.join("file");
+        std::fs::write(&file, "").unwrap();
+        let missing = directory.join("missing");
+
+        let problems = problems_with(&format!("directory = '{}'\ninterval = \"1h\"\npattern = \"any\"", missing.display()));
+        assert_eq!(problems, [("directory", ValidationErrorKind::DirectoryNotFound(missing.clone()))]);
+        let problems = problems_with(&format!("directory = '{}'\ninterval = \"1h\"\npattern = \"any\"", file.display()));
+        assert_eq!(problems, [("directory", ValidationErrorKind::NotADirectory(file))]);
+
+        // until the routine is enabled
+        let problems = problems_with(&format!("directory = '{}'\ninterval = \"1h\"\npattern = \"any\"\nenabled = false", missing.display()));
+        assert!(problems.is_empty());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn intervals_must_not_be_zero() {
+        assert_eq!(problems_with("interval = \"0s\"\npattern = \"any\""), [("interval", ValidationErrorKind::ZeroInterval)]);
+        assert!(problems_with("interval = \"0s\"\nonce = true\npattern = \"any\"").is_empty());
+        assert!(problems_with("interval = \"0s\"\nat = [\"03:00\"]\npattern = \"any\"").is_empty());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let problems = problems_with("interval = \"0s\"\npattern = { type = \"extension\", value = \"\" }\naction = { shred = 0 }");
+        let fields: Vec<_> = problems.iter().map(|(field, _)| *field).collect();
+        assert_eq!(fields, ["interval", "pattern", "action"]);
+    }
+
+    #[test]
+    fn only_valid_routines_are_retained() {
+        let mut config = config_with(&["a", "", "b", "a"]);
+        let problems = config.retain_valid();
+        let indices: Vec<_> = problems.iter().map(ValidationError::routine).collect();
+        assert_eq!(indices, [1, 3]);
+        let names: Vec<_> = config.routines().iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+    }
+
+    #[test]
+    fn name_patterns_must_look_for_something() {
+        for pattern in ["contains(\"\")", "starts_with(\"\")", "ends_with(\"\")"] {
+            let problems = problems_with(&format!("interval = \"1h\"\npattern = '{pattern}'"));
+            assert_eq!(problems, [("pattern", ValidationErrorKind::EmptyNamePart)], "{pattern}");
+        }
+        let problems = problems_with("interval = \"1h\"\npattern = { type = \"contains\", value = \"\" }");
+        assert_eq!(problems, [("pattern", ValidationErrorKind::EmptyNamePart)]);
+        assert!(problems_with("interval = \"1h\"\npattern = \"contains(tmp)\"").is_empty());
+    }
+
+    #[test]
+    fn lists_of_extensions_must_not_be_empty() {
+        for pattern in ["{ type = \"extensions\", value = [] }", "{ type = \"not_extension\", value = [] }"] {
+            let problems = problems_with(&format!("interval = \"1h\"\npattern = {pattern}"));
+            assert_eq!(problems, [("pattern", ValidationErrorKind::NoExtensions)], "{pattern}");
+        }
+    }
+
+    #[test]
+    fn content_patterns_must_look_for_something() {
+        let problems = problems_with("interval = \"1h\"\npattern = 'content(\"\")'");
+        assert_eq!(problems, [("pattern", ValidationErrorKind::EmptyContent)]);
+        assert!(problems_with("interval = \"1h\"\npattern = \"content(SESSION CLOSED)\"").is_empty());
+    }
+}
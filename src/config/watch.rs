@@ -0,0 +1,119 @@
+//! Tools for detecting changes to the configuration file.
+//!
+//! This module allows a running program to reload its configuration when
+//! the configuration file is edited, instead of having to be restarted.
+//! Changes are detected by polling the modification time of the file, of
+//! every file in its [fragment directory](crate::config::fragment_dir) and
+//! of every file they `include`.
+//!
+//! # Examples
+//!
+//! Checks for changes to a configuration file every few seconds and prints
+//! how many routines it contains whenever it changes.
+//!
+//! ```no_run
+//! use folder_cleaner::config::watch::ConfigWatcher;
+//! use std::thread;
+//! use std::time::Duration;
+//!
+//! let mut watcher = ConfigWatcher::new(r"C:\path\to\config.toml");
+//!
+//! loop {
+//!     if let Some(Ok(config)) = watcher.poll() {
+//!         println!("{} routines", config.routines().len());
+//!     }
+//!     thread::sleep(Duration::from_secs(5));
+//! }
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::{load, Config, ConfigError, LoadOptions};
+
+
+/// Watches a configuration file for changes.
+///
+/// The watcher remembers the modification times of the configuration files
+/// at the time it was created or last [`poll`](Self::poll())ed, and reloads
+/// the configuration whenever a modification time changes or a fragment or
+/// included file is added or removed.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    options: LoadOptions,
+    modified: Option<Snapshot>
+}
+
+/// The modification times of every file making up a configuration.
+type Snapshot = Vec<(PathBuf, SystemTime)>;
+
+impl ConfigWatcher {
+    /// Starts watching the configuration file at `path`.
+    ///
+    /// The current contents of the file are considered to be already loaded,
+    /// so the first change is reported only after the file is modified.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self::with_options(path, LoadOptions::default())
+    }
+
+    /// Starts watching the configuration file at `path`, reloading it using
+    /// `options`. See [`Config::load_with`].
+    pub fn with_options<P: AsRef<Path>>(path: P, options: LoadOptions) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let modified = snapshot(&path);
+        ConfigWatcher { path, options, modified }
+    }
+
+    /// The path to the watched configuration file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reloads the configuration if the file has changed since the last poll.
+    ///
+    /// Returns `None` if no file has changed. If the main configuration file
+    /// has been removed, it isn't considered to have changed, so a running
+    /// program keeps using the configuration it last loaded successfully.
+    ///
+    /// # Errors
+    ///
+    /// The inner result is an error if the file has changed but couldn't be
+    /// loaded. See [`Config::load_with`].
+    pub fn poll(&mut self) -> Option<Result<Config, ConfigError>> {
+        let modified = snapshot(&self.path)?;
+        if self.modified.as_ref() == Some(&modified) {
+            return None;
+        }
+
+        self.modified = Some(modified);
+        Some(Config::load_with(&self.path, &self.options))
+    }
+}
+
+
+/// The modification times of the configuration file at `path`, its fragments
+/// and the files they include, or `None` if the configuration file itself
+/// can't be accessed.
+fn snapshot(path: &Path) -> Option<Snapshot> {
+    let mut files = vec![(path.to_path_buf(), modified(path)?)];
+
+    // fragments and included files that can't be accessed right now are
+    // treated as missing
+    let fragments = load::fragment_paths(path).unwrap_or_default();
+    let included = load::include_paths(path)
+        .into_iter()
+        .chain(fragments.iter().flat_map(|fragment| load::include_paths(fragment)));
+    for file in fragments.iter().cloned().chain(included) {
+        if let Some(time) = modified(&file) {
+            files.push((file, time));
+        }
+    }
+
+    Some(files)
+}
+
+/// The modification time of the file at `path`, if it can be determined.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
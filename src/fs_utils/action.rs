@@ -0,0 +1,423 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::{
+    archive_into,
+    back_up,
+    flatten,
+    gzip,
+    hard_link_copy,
+    hide,
+    make_read_only,
+    move_by_date,
+    move_into,
+    move_keeping_structure,
+    quarantine,
+    remove,
+    run_command,
+    shred,
+    tag,
+    trash,
+    truncate,
+    upload,
+    Journal,
+    MoveTarget,
+    UploadTarget
+};
+
+
+/// What a routine does with the entries its pattern matches.
+///
+/// In configuration files, actions without settings are written by name, as
+/// in `action = "trash"`, and the others as tables with the name as the key,
+/// as in `action = { move_to = "~/Staging" }`. Relative destinations in
+/// configuration files are relative to the directory the routine cleans, so
+/// `action = { move_to = "_stale" }` moves entries into a subdirectory.
+/// Actions can also be chained, so that another one is tried for an entry
+/// if the first one fails for it, as in
+/// `action = { fallback = ["trash", { move_to = "~/Staging" }] }`.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::fs_utils::Action;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Routine {
+///     #[serde(default)]
+///     action: Action
+/// }
+///
+/// let routine: Routine = toml::from_str(r#"action = "trash""#).unwrap();
+/// assert_eq!(routine.action, Action::Trash);
+/// let routine: Routine = toml::from_str("").unwrap();
+/// assert_eq!(routine.action, Action::Delete);
+/// ```
+#[non_exhaustive]
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Removes entries permanently. See [`remove`].
+    #[default]
+    Delete,
+    /// Moves entries to the trash, from where they can be restored. See
+    /// [`trash`].
+    Trash,
+    /// Overwrites the contents of files the given number of times before
+    /// removing them, as in `action = { shred = 3 }`. See [`shred`].
+    Shred(u32),
+    /// Empties files without removing them, for files that programs keep
+    /// open. See [`truncate`].
+    Truncate,
+    /// Compresses files with gzip and removes the originals, keeping them
+    /// retrievable while taking less space. See [`gzip`].
+    Gzip,
+    /// Makes entries read-only, retiring them without moving them. See
+    /// [`make_read_only`].
+    ReadOnly,
+    /// Hides entries, which renames them on platforms other than Windows.
+    /// See [`hide`].
+    Hide,
+    /// Replaces copies of files with hard links to the originals anywhere in
+    /// the directory being cleaned, which frees the space the copies took
+    /// without removing them. See [`hard_link_copy`].
+    HardLink,
+    /// Moves entries into the given directory, keeping both entries if one
+    /// with the same name is already there, and optionally keeping the
+    /// subdirectories they were found in. See [`MoveTarget`], [`move_into`]
+    /// and [`move_keeping_structure`].
+    MoveTo(MoveTarget),
+    /// Moves entries into subdirectories of the given `directory` named
+    /// after the dates they were last modified, as laid out by the
+    /// `layout`, which is `%Y/%m` unless set. See [`move_by_date`].
+    MoveByDate {
+        directory: PathBuf,
+        #[serde(default = "default_layout")]
+        layout: String
+    },
+    /// Adds entries to a zip archive of the day in the given directory and
+    /// then removes them, keeping a history of everything that was removed.
+    /// See [`archive_into`].
+    Archive(PathBuf),
+    /// Copies entries into a backup in the given directory, where they keep
+    /// the subdirectories they were found in, and removes them once the
+    /// copies have been checked. See [`back_up`].
+    Backup(PathBuf),
+    /// Uploads entries to a server, in the same subdirectories as they're
+    /// in and without replacing anything that's already there, and removes
+    /// them once it has accepted them. See [`upload`].
+    Upload(UploadTarget),
+    /// Renames entries by adding a prefix and a suffix to their names,
+    /// flagging them for review without moving them. See [`tag`].
+    Tag {
+        #[serde(default)]
+        prefix: String,
+        #[serde(default)]
+        suffix: String
+    },
+    /// Moves the files in directories into the directory being cleaned and
+    /// removes the directories, leaving files that match alone. See
+    /// [`flatten`].
+    Flatten,
+    /// Moves entries into a quarantine directory, which is `.quarantine` in
+    /// the cleaned directory unless set, from where they're removed once the
+    /// `grace` period has passed. See [`quarantine`] and
+    /// [`Routine::purge_routine`](crate::routine::Routine::purge_routine).
+    Quarantine {
+        #[serde(default = "default_quarantine")]
+        directory: PathBuf,
+        #[serde(with = "crate::config::duration")]
+        grace: Duration
+    },
+    /// Runs a program with the given arguments for entries, which can
+    /// contain placeholders for them. See [`run_command`].
+    ///
+    /// The program is run for each entry, or just `once` for all the
+    /// entries of a run, which [`apply_all`](Self::apply_all()) takes care
+    /// of. If `remove` is set, the entries are removed after the program
+    /// exits successfully.
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        once: bool,
+        #[serde(default)]
+        remove: bool
+    },
+    /// Tries the given actions in order for each entry until one of them
+    /// succeeds, such as moving entries elsewhere if they can't be moved to
+    /// the trash because they're on a network drive.
+    ///
+    /// If every action fails, the error of the last one is returned.
+    /// [`Command`](Self::Command)s in a chain are run for each entry, even if
+    /// they're run `once` on their own.
+    Fallback(Vec<Action>)
+}
+
+fn default_layout() -> String {
+    "%Y/%m".into()
+}
+
+fn default_quarantine() -> PathBuf {
+    PathBuf::from(".quarantine")
+}
+
+impl Action {
+    /// Applies the action to the entry at `path`.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the action fails, see the
+    /// functions each action uses.
+    pub fn apply(&self, path: &Path) -> Result<(), ActionError> {
+        self.apply_journaled(path, &mut Journal::new())
+    }
+
+    /// Applies the action to the entry at `path` like [`apply`](Self::apply()),
+    /// recording where it was moved in `journal` if the action moves it
+    /// somewhere it can be restored from.
+    ///
+    /// # Errors
+    ///
+    /// See [`apply`](Self::apply()).
+    pub fn apply_journaled(&self, path: &Path, journal: &mut Journal) -> Result<(), ActionError> {
+        self.apply_in(path.parent().unwrap_or(Path::new("")), path, journal)
+    }
+
+    /// Applies the action to the entry at `path`, which was found in `root`
+    /// or one of its subdirectories, like
+    /// [`apply_journaled`](Self::apply_journaled()).
+    ///
+    /// The root only matters to actions that keep the structure of the
+    /// directories entries are found in, to flattening, which moves files
+    /// into the root, to hard links, whose originals are looked for in it,
+    /// and to quarantining, whose directory is relative to it unless it's
+    /// absolute. [`apply_journaled`](Self::apply_journaled()) uses the
+    /// directory containing the entry as the root.
+    ///
+    /// # Errors
+    ///
+    /// See [`apply`](Self::apply()).
+    pub fn apply_in(&self, root: &Path, path: &Path, journal: &mut Journal) -> Result<(), ActionError> {
+        let moved_to = match self {
+            Self::Delete => remove(path).map(|_| None)?,
+            Self::Trash => trash::trash_to(path)?,
+            Self::Shred(passes) => shred(path, *passes).map(|_| None)?,
+            Self::Truncate => truncate(path).map(|_| None)?,
+            Self::Gzip => gzip(path).map(|_| None)?,
+            Self::ReadOnly => make_read_only(path).map(|_| None)?,
+            Self::Hide => Some(hide(path)?),
+            Self::HardLink => hard_link_copy(path, root).map(|_| None)?,
+            Self::MoveTo(MoveTarget { directory, keep_structure: false }) => Some(move_into(path, directory)?),
+            Self::MoveTo(MoveTarget { directory, keep_structure: true })
+            => Some(move_keeping_structure(path, root, directory)?),
+            Self::MoveByDate { directory, layout } => Some(move_by_date(path, directory, layout)?),
+            Self::Archive(directory) => archive_into(path, directory).map(|_| None)?,
+            Self::Backup(directory) => back_up(path, root, directory).map(|_| None)?,
+            Self::Upload(target) => upload(path, root, target).map(|_| None)?,
+            Self::Tag { prefix, suffix } => Some(tag(path, prefix, suffix)?),
+            Self::Flatten => flatten(path, root, journal).map(|_| None)?,
+            Self::Quarantine { directory, .. } => Some(quarantine(path, root.join(directory))?),
+            Self::Command { .. } => {
+                return match self.apply_all_journaled(&[path], journal).into_iter().next() {
+                    Some(e) => Err(e),
+                    None => Ok(())
+                };
+            }
+            Self::Fallback(actions) => {
+                let mut last_error = None;
+                for action in actions {
+                    match action.apply_in(root, path, journal) {
+                        Ok(()) => return Ok(()),
+                        Err(e) => last_error = Some(e)
+                    }
+                }
+                return Err(last_error.unwrap_or_else(|| ActionError::new(
+                    "clean",
+                    path,
+                    io::Error::new(io::ErrorKind::InvalidInput, "there are no actions to try")
+                )));
+            }
+        };
+
+        // an entry that was already tagged or hidden stays where it is
+        if let Some(moved_to) = moved_to.filter(|moved_to| moved_to != path) {
+            journal.record(path, &moved_to);
+        }
+        Ok(())
+    }
+
+    /// Applies the action to every entry in `paths`, returning the errors
+    /// for the entries it failed for.
+    ///
+    /// This is the same as applying the action to each entry, except for
+    /// [`Command`](Self::Command)s that are run `once`, which get all the
+    /// entries at the same time.
+    pub fn apply_all<P: AsRef<Path>>(&self, paths: &[P]) -> Vec<ActionError> {
+        self.apply_all_journaled(paths, &mut Journal::new())
+    }
+
+    /// Applies the action to every entry in `paths` like
+    /// [`apply_all`](Self::apply_all()), recording where they were moved in
+    /// `journal` like [`apply_journaled`](Self::apply_journaled()).
+    pub fn apply_all_journaled<P: AsRef<Path>>(
+        &self,
+        paths: &[P],
+        journal: &mut Journal
+    ) -> Vec<ActionError> {
+        self.apply_all_with(None, paths, journal)
+    }
+
+    /// Applies the action to every entry in `paths`, which were found in
+    /// `root` or its subdirectories, like
+    /// [`apply_all_journaled`](Self::apply_all_journaled()) and
+    /// [`apply_in`](Self::apply_in()).
+    pub fn apply_all_in<P: AsRef<Path>>(
+        &self,
+        root: &Path,
+        paths: &[P],
+        journal: &mut Journal
+    ) -> Vec<ActionError> {
+        self.apply_all_with(Some(root), paths, journal)
+    }
+
+    /// Applies the action to every entry in `paths`, which were found in
+    /// `root` if it's known.
+    fn apply_all_with<P: AsRef<Path>>(
+        &self,
+        root: Option<&Path>,
+        paths: &[P],
+        journal: &mut Journal
+    ) -> Vec<ActionError> {
+        let Self::Command { program, args, once, remove: then_remove } = self else {
+            return paths
+                .iter()
+                .filter_map(|path| match root {
+                    Some(root) => self.apply_in(root, path.as_ref(), journal).err(),
+                    None => self.apply_journaled(path.as_ref(), journal).err()
+                })
+                .collect();
+        };
+
+        let mut errors = Vec::new();
+        let batches: Vec<&[P]> = match once {
+            true if !paths.is_empty() => vec![paths],
+            true => Vec::new(),
+            false => paths.chunks(1).collect()
+        };
+        for batch in batches {
+            match run_command(program, args, batch) {
+                Ok(()) if *then_remove => errors.extend(
+                    batch.iter().filter_map(|path| remove(path).err().map(ActionError::from))
+                ),
+                Ok(()) => {}
+                Err(e) => errors.push(e)
+            }
+        }
+        errors
+    }
+
+    /// Checks whether `path` is where the action puts entries found in
+    /// `root`, which it must never be applied to itself.
+    pub(crate) fn is_destination(&self, root: &Path, path: &Path) -> bool {
+        let same = |destination: PathBuf| match (path.canonicalize(), destination.canonicalize()) {
+            (Ok(path), Ok(destination)) => path == destination,
+            _ => path == destination
+        };
+        self.paths(root).into_iter().any(same)
+    }
+
+    /// The action itself, or the actions of a [`Fallback`](Self::Fallback)
+    /// chain along with those of any chains in it.
+    pub(crate) fn actions(&self) -> Vec<&Action> {
+        match self {
+            Self::Fallback(actions) => actions.iter().flat_map(Self::actions).collect(),
+            action => vec![action]
+        }
+    }
+
+    /// The paths in the settings of the action, with the quarantine
+    /// resolved against `root` like [`apply_in`](Self::apply_in()) does.
+    fn paths(&self, root: &Path) -> Vec<PathBuf> {
+        match self {
+            Self::MoveTo(MoveTarget { directory, .. })
+            | Self::MoveByDate { directory, .. }
+            | Self::Archive(directory)
+            | Self::Backup(directory) => vec![directory.clone()],
+            Self::Quarantine { directory, .. } => vec![root.join(directory)],
+            Self::Fallback(actions) => actions.iter().flat_map(|action| action.paths(root)).collect(),
+            Self::Delete
+            | Self::Trash
+            | Self::Shred(_)
+            | Self::Truncate
+            | Self::Gzip
+            | Self::ReadOnly
+            | Self::Hide
+            | Self::HardLink
+            | Self::Upload(_)
+            | Self::Tag { .. }
+            | Self::Flatten
+            | Self::Command { .. } => Vec::new()
+        }
+    }
+
+    /// The paths in the settings of the action, so that environment
+    /// variables in them can be expanded.
+    pub(crate) fn paths_mut(&mut self) -> Vec<&mut PathBuf> {
+        match self {
+            Self::MoveTo(MoveTarget { directory, .. })
+            | Self::MoveByDate { directory, .. }
+            | Self::Archive(directory)
+            | Self::Backup(directory)
+            | Self::Quarantine { directory, .. } => vec![directory],
+            Self::Fallback(actions) => actions.iter_mut().flat_map(Self::paths_mut).collect(),
+            Self::Delete
+            | Self::Trash
+            | Self::Shred(_)
+            | Self::Truncate
+            | Self::Gzip
+            | Self::ReadOnly
+            | Self::Hide
+            | Self::HardLink
+            | Self::Upload(_)
+            | Self::Tag { .. }
+            | Self::Flatten
+            | Self::Command { .. } => Vec::new()
+        }
+    }
+
+    /// The name of the action in configuration files, such as `"move_to"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Trash => "trash",
+            Self::Shred(_) => "shred",
+            Self::Truncate => "truncate",
+            Self::Gzip => "gzip",
+            Self::ReadOnly => "read_only",
+            Self::Hide => "hide",
+            Self::HardLink => "hard_link",
+            Self::MoveTo(_) => "move_to",
+            Self::MoveByDate { .. } => "move_by_date",
+            Self::Archive(_) => "archive",
+            Self::Backup(_) => "backup",
+            Self::Upload(_) => "upload",
+            Self::Tag { .. } => "tag",
+            Self::Flatten => "flatten",
+            Self::Quarantine { .. } => "quarantine",
+            Self::Command { .. } => "command",
+            Self::Fallback(_) => "fallback"
+        }
+    }
+
+    /// Checks whether this is the default action, which doesn't need to be
+    /// written in configuration files.
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -0,0 +1,226 @@
+//! Keeping removed files and directories in zip archives.
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use time::OffsetDateTime;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::remove;
+
+
+/// Adds a file or directory to the archive of the day in `directory` and
+/// then removes it, returning the path to the archive.
+///
+/// The archives are named after the date they're for, such as
+/// `cleaned-2024-05-01.zip`, using the date in UTC, and entries removed on
+/// the same day are added to the same archive. The directory is created if
+/// it doesn't exist. Directories are added along with everything in them,
+/// and an entry with the same name as one that's already in the archive gets
+/// a number added to its name, so that nothing is ever overwritten.
+///
+/// # Errors
+///
+/// This function returns an error if the archive can't be created, read or
+/// written, if the entry can't be read, or if it can't be removed after
+/// being archived. Nothing is removed unless it's been archived, but an
+/// archive can be left with part of a directory that couldn't be read.
+/// Like [`move_into`](super::move_into), it's an error if `path` doesn't
+/// exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::archive_into;
+///
+/// let archive = archive_into(r"C:\Users\user\Desktop\notes.txt", r"D:\Archives").unwrap();
+/// ```
+pub fn archive_into<P: AsRef<Path>, D: AsRef<Path>>(
+    path: P,
+    directory: D
+) -> Result<PathBuf, ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("archive", path, e);
+
+    // an archive shouldn't be created for something that isn't there
+    path.symlink_metadata().map_err(error)?;
+    let archive = directory.as_ref().join(archive_name(OffsetDateTime::now_utc()));
+    fs::create_dir_all(directory.as_ref()).map_err(error)?;
+    add(&archive, path).map_err(error)?;
+    remove(path)?;
+    Ok(archive)
+}
+
+/// The name of the archive for the day of `now`.
+fn archive_name(now: OffsetDateTime) -> String {
+    format!("cleaned-{:04}-{:02}-{:02}.zip", now.year(), u8::from(now.month()), now.day())
+}
+
+/// Adds an entry to an archive, creating the archive if needed.
+fn add(archive: &Path, path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(archive)?;
+
+    let (mut writer, taken) = match file.metadata()?.len() {
+        0 => (ZipWriter::new(file), HashSet::new()),
+        _ => {
+            let names = ZipArchive::new(&file)?
+                .file_names()
+                .map(|name| name.trim_end_matches('/').to_string())
+                .collect();
+            (ZipWriter::new_append(file)?, names)
+        }
+    };
+
+    let name = free_name(path, &taken);
+    write_entry(&mut writer, path, &name)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// A name for `path` at the top of the archive that isn't taken yet.
+fn free_name(path: &Path, taken: &HashSet<String>) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{extension}")),
+        _ => (&*name, String::new())
+    };
+
+    let mut candidate = name.to_string();
+    let mut number = 2;
+    while taken.contains(&candidate) {
+        candidate = format!("{stem} ({number}){extension}");
+        number += 1;
+    }
+    candidate
+}
+
+/// Writes a file, a link or a whole directory into the archive as `name`.
+fn write_entry<W: Write + Seek>(writer: &mut ZipWriter<W>, path: &Path, name: &str) -> io::Result<()> {
+    let metadata = path.symlink_metadata()?;
+    let mut options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(metadata.len() >= u32::MAX as u64);
+    if let Some(modified) = metadata.modified().ok().and_then(zip_time) {
+        options = options.last_modified_time(modified);
+    }
+
+    if metadata.is_symlink() {
+        let target = fs::read_link(path)?;
+        writer.add_symlink(name, target.to_string_lossy(), options)?;
+    }
+    else if metadata.is_dir() {
+        writer.add_directory(format!("{name}/"), options)?;
+        for entry in path.read_dir()? {
+            let entry = entry?;
+            let child = format!("{name}/{}", entry.file_name().to_string_lossy());
+            write_entry(writer, &entry.path(), &child)?;
+        }
+    }
+    else {
+        writer.start_file(name, options)?;
+        io::copy(&mut File::open(path)?, writer)?;
+    }
+    Ok(())
+}
+
+/// Converts a modification time to the format used in archives, which can't
+/// represent times before 1980.
+fn zip_time(time: SystemTime) -> Option<DateTime> {
+    let time = OffsetDateTime::from(time);
+    DateTime::from_date_and_time(
+        u16::try_from(time.year()).ok()?,
+        u8::from(time.month()),
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second()
+    ).ok()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use crate::fs_utils::test_dir;
+
+    /// The names and contents of the entries in the archive at `path`.
+    fn contents(path: &Path) -> Vec<(String, String)> {
+        let mut archive = ZipArchive::new(File::open(path).unwrap()).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut entry = archive.by_index(i).unwrap();
+                let mut text = String::new();
+                entry.read_to_string(&mut text).unwrap();
+                (entry.name().to_string(), text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn archived_entries_never_replace_others() {
+        let directory = test_dir("archive-names");
+        let archives = directory.join("archives");
+        fs::write(directory.join("notes.txt"), "first").unwrap();
+        let first = archive_into(directory.join("notes.txt"), &archives).unwrap();
+        fs::write(directory.join("notes.txt"), "second").unwrap();
+        let second = archive_into(directory.join("notes.txt"), &archives).unwrap();
+
+        assert_eq!(first, second);
+        assert!(!directory.join("notes.txt").exists());
+        assert_eq!(contents(&first), [
+            ("notes.txt".to_string(), "first".to_string()),
+            ("notes (2).txt".to_string(), "second".to_string())
+        ]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn directories_are_archived_with_everything_in_them() {
+        let directory = test_dir("archive-directory");
+        fs::create_dir_all(directory.join("logs/old")).unwrap();
+        fs::write(directory.join("logs/old/1.log"), "one").unwrap();
+
+        let archive = archive_into(directory.join("logs"), directory.join("archives")).unwrap();
+        assert!(!directory.join("logs").exists());
+        assert_eq!(contents(&archive), [
+            ("logs/".to_string(), String::new()),
+            ("logs/old/".to_string(), String::new()),
+            ("logs/old/1.log".to_string(), "one".to_string())
+        ]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn entries_are_kept_if_they_cant_be_archived() {
+        let directory = test_dir("archive-broken");
+        let archives = directory.join("archives");
+        let archive = archives.join(archive_name(OffsetDateTime::now_utc()));
+        fs::create_dir(&archives).unwrap();
+        fs::write(&archive, "not a zip file").unwrap();
+        fs::write(directory.join("notes.txt"), "keep").unwrap();
+
+        assert!(archive_into(directory.join("notes.txt"), &archives).is_err());
+        assert_eq!(fs::read_to_string(directory.join("notes.txt")).unwrap(), "keep");
+        assert_eq!(fs::read_to_string(&archive).unwrap(), "not a zip file");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn archives_are_named_after_the_day() {
+        let date = OffsetDateTime::from_unix_timestamp(1_714_521_600).unwrap();
+        assert_eq!(archive_name(date), "cleaned-2024-05-01.zip");
+    }
+}
@@ -0,0 +1,176 @@
+//! Backing files and directories up before removing them.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::relocate::{copy_all, free_path};
+use crate::fs_utils::remove;
+
+
+/// Copies a file or directory found in `root` or one of its subdirectories
+/// into the same subdirectory of the backup in `directory`, checks the copy
+/// and then removes the original, returning the path to the copy.
+///
+/// An entry at `root/photos/2024/beach.jpg` is backed up as
+/// `directory/photos/2024/beach.jpg`, creating the subdirectories it needs,
+/// and an entry that isn't in `root` at all is backed up directly into
+/// `directory`. If an earlier backup is already in the way, a number is
+/// added to the name like [`move_into`](super::move_into) does.
+///
+/// The original is only removed if the copy has the same size and contents,
+/// and for directories, if the same goes for everything in them.
+///
+/// # Errors
+///
+/// This function returns an error if the entry can't be copied, if the copy
+/// doesn't match the original or if the original can't be removed. If
+/// anything goes wrong before the original is removed, the copy is removed
+/// again.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::back_up;
+///
+/// let copy = back_up(
+///     r"C:\Users\user\Desktop\notes\todo.txt",
+///     r"C:\Users\user\Desktop",
+///     r"D:\Backup"
+/// ).unwrap();
+/// ```
+pub fn back_up<P: AsRef<Path>, R: AsRef<Path>, D: AsRef<Path>>(
+    path: P,
+    root: R,
+    directory: D
+) -> Result<PathBuf, ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("back up", path, e);
+
+    path.symlink_metadata().map_err(error)?;
+    let relative = path
+        .parent()
+        .and_then(|parent| parent.strip_prefix(root.as_ref()).ok())
+        .unwrap_or(Path::new(""));
+    let directory = directory.as_ref().join(relative);
+    fs::create_dir_all(&directory).map_err(error)?;
+    let target = free_path(&directory, path).map_err(error)?;
+
+    let copied = copy_all(path, &target).and_then(|_| match same(path, &target)? {
+        true => Ok(()),
+        false => Err(io::Error::other("the copy doesn't match the original"))
+    });
+    if let Err(e) = copied {
+        let _ = remove(&target);
+        return Err(error(e));
+    }
+    remove(path)?;
+    Ok(target)
+}
+
+/// Checks whether `copy` has the same contents as `original`.
+fn same(original: &Path, copy: &Path) -> io::Result<bool> {
+    let (metadata, copy_metadata) = (original.symlink_metadata()?, copy.symlink_metadata()?);
+    if metadata.is_dir() {
+        let mut names = Vec::new();
+        for entry in original.read_dir()? {
+            let name = entry?.file_name();
+            if !same(&original.join(&name), &copy.join(&name))? {
+                return Ok(false);
+            }
+            names.push(name);
+        }
+        // the copy mustn't have anything the original doesn't
+        Ok(copy_metadata.is_dir() && copy.read_dir()?.count() == names.len())
+    }
+    else if metadata.is_symlink() {
+        Ok(copy_metadata.is_symlink() && fs::read_link(original)? == fs::read_link(copy)?)
+    }
+    else {
+        Ok(copy_metadata.len() == metadata.len() && same_contents(original, copy)?)
+    }
+}
+
+/// Compares two files byte by byte.
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (BufReader::new(File::open(a)?), BufReader::new(File::open(b)?));
+    let (mut buffer_a, mut buffer_b) = ([0; 8192], [0; 8192]);
+    loop {
+        let read = a.read(&mut buffer_a)?;
+        if read == 0 {
+            // the other file has to end here too
+            return Ok(b.read(&mut buffer_b[..1])? == 0);
+        }
+        b.read_exact(&mut buffer_b[..read])?;
+        if buffer_a[..read] != buffer_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_utils::test_dir;
+
+    #[test]
+    fn backups_keep_the_structure_and_never_replace_others() {
+        let directory = test_dir("backup-names");
+        let (root, backup) = (directory.join("root"), directory.join("backup"));
+        fs::create_dir_all(root.join("notes")).unwrap();
+        fs::create_dir_all(backup.join("notes")).unwrap();
+        fs::write(backup.join("notes/todo.txt"), "old").unwrap();
+        fs::write(root.join("notes/todo.txt"), "new").unwrap();
+
+        let copy = back_up(root.join("notes/todo.txt"), &root, &backup).unwrap();
+        assert_eq!(copy, backup.join("notes/todo (2).txt"));
+        assert_eq!(fs::read_to_string(copy).unwrap(), "new");
+        assert_eq!(fs::read_to_string(backup.join("notes/todo.txt")).unwrap(), "old");
+        assert!(!root.join("notes/todo.txt").exists());
+
+        // entries outside of the root go straight into the backup
+        fs::write(directory.join("elsewhere.txt"), "").unwrap();
+        let copy = back_up(directory.join("elsewhere.txt"), &root, &backup).unwrap();
+        assert_eq!(copy, backup.join("elsewhere.txt"));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn originals_are_kept_if_they_cant_be_copied() {
+        let directory = test_dir("backup-failed");
+        let (root, backup) = (directory.join("root"), directory.join("backup"));
+        fs::create_dir_all(root.join("logs")).unwrap();
+        fs::write(root.join("logs/1.log"), "one").unwrap();
+        // sockets can't be copied
+        let _socket = std::os::unix::net::UnixListener::bind(root.join("logs/socket")).unwrap();
+
+        assert!(back_up(root.join("logs"), &root, &backup).is_err());
+        assert_eq!(fs::read_to_string(root.join("logs/1.log")).unwrap(), "one");
+        assert!(root.join("logs/socket").exists());
+        assert_eq!(backup.read_dir().unwrap().count(), 0);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn copies_have_to_match_exactly() {
+        let directory = test_dir("backup-same");
+        for (name, contents) in [("a", "same"), ("b", "same"), ("c", "diff"), ("d", "longer")] {
+            fs::create_dir_all(directory.join(name)).unwrap();
+            fs::write(directory.join(name).join("file"), contents).unwrap();
+        }
+        assert!(same(&directory.join("a"), &directory.join("b")).unwrap());
+        assert!(!same(&directory.join("a"), &directory.join("c")).unwrap());
+        assert!(!same(&directory.join("a"), &directory.join("d")).unwrap());
+
+        // nor can the copy have more in it
+        fs::write(directory.join("b/extra"), "").unwrap();
+        assert!(!same(&directory.join("a"), &directory.join("b")).unwrap());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+//! Handing files and directories to other programs.
+
+use std::ffi::OsString;
+use std::io;
+use std::path::Path;
+use std::process;
+
+use crate::fs_utils::error::ActionError;
+
+
+/// The placeholders that can appear in arguments.
+const PLACEHOLDERS: [&str; 4] = ["{path}", "{name}", "{dir}", "{paths}"];
+
+
+/// Runs `program` with `args` for one or more entries, waiting for it to
+/// finish.
+///
+/// The arguments can contain placeholders that are replaced with the
+/// entries the program is run for:
+///
+/// - `{path}` is replaced with the path to the entry
+/// - `{name}` is replaced with the name of the entry
+/// - `{dir}` is replaced with the directory the entry is in
+/// - an argument that's just `{paths}` is replaced with one argument for
+///   each entry, which is mostly useful for running the program once for
+///   several entries
+///
+/// When the program is run for several entries at once, `{path}` and
+/// `{name}` are those of the first entry. If no argument contains a
+/// placeholder, the paths are added to the end of the arguments. The
+/// program inherits the standard streams of this process and is found like
+/// [`std::process::Command`] finds programs.
+///
+/// # Errors
+///
+/// This function returns an error if the program can't be started or
+/// doesn't exit successfully. The error is for the first entry, or for an
+/// empty path if there are no entries.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::run_command;
+///
+/// let args = ["--remove".to_string(), "{path}".to_string()];
+/// let result = run_command("clamscan", &args, &["/home/user/Downloads/setup.exe"]);
+/// ```
+pub fn run_command<P: AsRef<Path>>(
+    program: &str,
+    args: &[String],
+    paths: &[P]
+) -> Result<(), ActionError> {
+    let paths: Vec<&Path> = paths.iter().map(AsRef::as_ref).collect();
+    let first = paths.first().copied().unwrap_or(Path::new(""));
+    let error = |e| ActionError::new("run a command for", first, e);
+
+    let status = process::Command::new(program)
+        .args(expand_args(args, &paths))
+        .status()
+        .map_err(error)?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(error(io::Error::other(format!("{program} exited with {status}"))))
+    }
+}
+
+/// Replaces the placeholders in `args` with the entries in `paths`.
+fn expand_args(args: &[String], paths: &[&Path]) -> Vec<OsString> {
+    if !args.iter().any(|arg| PLACEHOLDERS.iter().any(|p| arg.contains(p))) {
+        return args.iter().map(OsString::from).chain(paths.iter().map(OsString::from)).collect();
+    }
+
+    let first = paths.first().copied().unwrap_or(Path::new(""));
+    let path = first.to_string_lossy();
+    let name = first.file_name().unwrap_or_default().to_string_lossy();
+    let dir = first.parent().unwrap_or(Path::new("")).to_string_lossy();
+
+    let mut expanded = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg.as_str() {
+            "{paths}" => expanded.extend(paths.iter().map(OsString::from)),
+            // passed on as it is, in case it isn't valid Unicode
+            "{path}" => expanded.push(first.into()),
+            _ => expanded.push(OsString::from(
+                arg.replace("{path}", &path)
+                    .replace("{name}", &name)
+                    .replace("{dir}", &dir)
+            ))
+        }
+    }
+    expanded
+}
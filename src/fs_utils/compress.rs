@@ -0,0 +1,147 @@
+//! Compressing files where they are.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use flate2::write::GzEncoder;
+use flate2::{Compression, GzBuilder};
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::relocate::free_path;
+
+
+/// Compresses a file with gzip, or every file in a directory, replacing it
+/// with the compressed file and returning the path to that.
+///
+/// The compressed file is named after the original with `.gz` added, so
+/// that `app.log` becomes `app.log.gz`, and a number is added to the name
+/// if it's taken like [`move_into`](super::move_into) does. It keeps the
+/// time the original was last modified, so that patterns about age still
+/// match it the same way. The original is only removed once the compressed
+/// file has been written completely. Files that are already compressed with
+/// gzip, going by their extension, are left alone, as are links.
+///
+/// Files a directory contains are compressed along with those in its
+/// subdirectories, and the path of the directory is returned.
+///
+/// # Errors
+///
+/// This function returns an error if a file can't be read, if the compressed
+/// file can't be written or if the original can't be removed afterwards. If
+/// writing fails, whatever was written is removed again.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::gzip;
+///
+/// let compressed = gzip("/var/log/myapp/debug.log").unwrap();
+/// ```
+pub fn gzip<P: AsRef<Path>>(path: P) -> Result<PathBuf, ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("compress", path, e);
+
+    let metadata = path.symlink_metadata().map_err(error)?;
+    if metadata.is_dir() {
+        for entry in path.read_dir().map_err(error)? {
+            gzip(entry.map_err(error)?.path())?;
+        }
+        return Ok(path.to_path_buf());
+    }
+    let is_compressed = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gz"));
+    if !metadata.is_file() || is_compressed {
+        return Ok(path.to_path_buf());
+    }
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let target = free_path(directory, Path::new(&format!("{name}.gz"))).map_err(error)?;
+    if let Err(e) = compress(path, &target, &name) {
+        let _ = fs::remove_file(&target);
+        return Err(error(e));
+    }
+    if let Ok(modified) = metadata.modified() {
+        let _ = File::options().write(true).open(&target).and_then(|file| file.set_modified(modified));
+    }
+    fs::remove_file(path).map_err(error)?;
+    Ok(target)
+}
+
+
+/// Writes the contents of `path` compressed into a new file at `target`,
+/// with `name` as the original name in its header.
+fn compress(path: &Path, target: &Path, name: &str) -> io::Result<()> {
+    let mut original = BufReader::new(File::open(path)?);
+    let mut encoder: GzEncoder<File> = GzBuilder::new()
+        .filename(name.as_bytes())
+        .write(File::create_new(target)?, Compression::default());
+    io::copy(&mut original, &mut encoder)?;
+    // the original is removed next, so the copy has to be on the disk
+    encoder.finish()?.sync_all()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::time::{Duration, SystemTime};
+    use flate2::read::GzDecoder;
+    use crate::fs_utils::test_dir;
+
+    fn decompress(path: &Path) -> String {
+        let mut text = String::new();
+        GzDecoder::new(File::open(path).unwrap()).read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn files_are_replaced_with_compressed_ones() {
+        let directory = test_dir("gzip");
+        let path = directory.join("app.log");
+        fs::write(&path, "lines").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_714_521_600);
+        File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        fs::write(directory.join("app.log.gz"), "taken").unwrap();
+
+        let compressed = gzip(&path).unwrap();
+        assert_eq!(compressed, directory.join("app.log (2).gz"));
+        assert_eq!(decompress(&compressed), "lines");
+        assert_eq!(compressed.metadata().unwrap().modified().unwrap(), modified);
+        assert!(!path.exists());
+        // which is already compressed, going by its name
+        assert_eq!(fs::read_to_string(directory.join("app.log.gz")).unwrap(), "taken");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn directories_are_compressed_file_by_file() {
+        let directory = test_dir("gzip-directory");
+        fs::create_dir_all(directory.join("logs/old")).unwrap();
+        fs::write(directory.join("logs/old/1.log"), "one").unwrap();
+        fs::write(directory.join("logs/2.log.gz"), "two").unwrap();
+
+        assert_eq!(gzip(directory.join("logs")).unwrap(), directory.join("logs"));
+        assert_eq!(decompress(&directory.join("logs/old/1.log.gz")), "one");
+        assert!(!directory.join("logs/old/1.log").exists());
+        assert_eq!(fs::read_to_string(directory.join("logs/2.log.gz")).unwrap(), "two");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn originals_are_kept_if_they_cant_be_compressed() {
+        let directory = test_dir("gzip-failed");
+        // too long a name once `.gz` is added
+        let path = directory.join("a".repeat(254));
+        fs::write(&path, "lines").unwrap();
+
+        assert!(gzip(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "lines");
+        assert_eq!(directory.read_dir().unwrap().count(), 1);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+//! Replacing copies of files with hard links to the originals.
+
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::relocate::free_path;
+use crate::fs_utils::same_contents;
+
+
+/// Replaces a file with a hard link to an identical file in `root` or its
+/// subdirectories on the same volume, returning the path to the file it's
+/// now linked to.
+///
+/// This reclaims the space copies take, in places like build caches,
+/// without removing anything from view. Like
+/// [`FilePattern::Duplicate`](super::FilePattern::Duplicate), identical
+/// files are the ones with the same contents byte for byte, and the one that
+/// was modified first is linked to, with ties broken by path. The link
+/// replaces the file in a single step, so that the file is never missing.
+/// Returns `None` if there's no other copy of the file, if it's the copy
+/// that was modified first, if it's already linked to the one that was or
+/// if it isn't a file, such as a directory, a link or an empty file. Links
+/// to directories aren't followed when looking for copies, and neither are
+/// directories that can't be read.
+///
+/// Since hard links share their contents, changing one of the files
+/// afterwards changes all of them.
+///
+/// # Errors
+///
+/// This function returns an error if `root` or the files can't be read or
+/// if the link can't be made, for example because the file system doesn't
+/// support hard links.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::hard_link_copy;
+///
+/// let original = hard_link_copy(
+///     r"C:\Users\user\Pictures\2024\beach (2).jpg",
+///     r"C:\Users\user\Pictures"
+/// ).unwrap();
+/// ```
+pub fn hard_link_copy<P: AsRef<Path>, R: AsRef<Path>>(path: P, root: R) -> Result<Option<PathBuf>, ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("hard link", path, e);
+
+    let metadata = path.symlink_metadata().map_err(error)?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Ok(None);
+    }
+    let Some(original) = find_original(path, &metadata, root.as_ref()).map_err(error)? else {
+        return Ok(None);
+    };
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = free_path(directory, Path::new(&format!(".{name}.link"))).map_err(error)?;
+    fs::hard_link(&original, &temporary).map_err(error)?;
+    if let Err(e) = fs::rename(&temporary, path) {
+        let _ = fs::remove_file(&temporary);
+        return Err(error(e));
+    }
+    Ok(Some(original))
+}
+
+
+/// Finds the file in `root` or its subdirectories that `path` should be
+/// linked to, if there is one and it isn't linked to it already.
+///
+/// Returns `None` if `path` is the copy that was modified first, since the
+/// others are linked to it instead, keeping its modification time and
+/// permissions.
+fn find_original(path: &Path, metadata: &Metadata, root: &Path) -> io::Result<Option<PathBuf>> {
+    let volume = platform::volume(path)?;
+    let mut candidates = Vec::new();
+    let mut directories = vec![root.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let entries = match directory.read_dir() {
+            Ok(entries) => entries,
+            Err(e) if directory == root => return Err(e),
+            Err(_) => continue
+        };
+        for entry in entries.flatten() {
+            let other = entry.path();
+            let Ok(other_metadata) = other.symlink_metadata() else {
+                continue;
+            };
+            if other_metadata.is_dir() {
+                directories.push(other);
+            }
+            // files on other volumes can't be linked to
+            else if other_metadata.is_file()
+                && other_metadata.len() == metadata.len()
+                && platform::volume(&other).is_ok_and(|other_volume| other_volume == volume)
+            {
+                let modified = other_metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                candidates.push((modified, other));
+            }
+        }
+    }
+    // the file itself is only among them if it's in the root
+    if !candidates.iter().any(|(_, other)| other == path) {
+        candidates.push((metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), path.to_path_buf()));
+    }
+    candidates.sort();
+
+    for (_, other) in candidates {
+        // the earlier files that got this far aren't copies of it
+        if other == path {
+            return Ok(None);
+        }
+        if platform::same_file(path, &other)? {
+            return Ok(None);
+        }
+        if same_contents(path, &other)? {
+            return Ok(Some(other));
+        }
+    }
+    Ok(None)
+}
+
+
+#[cfg(unix)]
+mod platform {
+    use std::io;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    /// Checks whether two paths are hard links to the same file.
+    pub(super) fn same_file(a: &Path, b: &Path) -> io::Result<bool> {
+        let (a, b) = (a.metadata()?, b.metadata()?);
+        Ok(a.dev() == b.dev() && a.ino() == b.ino())
+    }
+
+    /// The device the file at `path` is on.
+    pub(super) fn volume(path: &Path) -> io::Result<u64> {
+        Ok(path.symlink_metadata()?.dev())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use std::path::Path;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle,
+        BY_HANDLE_FILE_INFORMATION
+    };
+
+    /// Checks whether two paths are hard links to the same file.
+    pub(super) fn same_file(a: &Path, b: &Path) -> io::Result<bool> {
+        Ok(file_id(a)? == file_id(b)?)
+    }
+
+    /// The serial number of the volume the file at `path` is on.
+    pub(super) fn volume(path: &Path) -> io::Result<u64> {
+        file_id(path).map(|(volume, _)| volume.into())
+    }
+
+    /// The serial number of the volume a file is on and its index there.
+    fn file_id(path: &Path) -> io::Result<(u32, u64)> {
+        let file = File::open(path)?;
+        let mut info = BY_HANDLE_FILE_INFORMATION::default();
+
+        // SAFETY: the handle stays open during the call and the information
+        // is written into a structure of the right type
+        match unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) } {
+            0 => Err(io::Error::last_os_error()),
+            _ => Ok((
+                info.dwVolumeSerialNumber,
+                (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow)
+            ))
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::io;
+    use std::path::Path;
+
+    /// Checks whether two paths are hard links to the same file, which
+    /// can't be told on this platform.
+    pub(super) fn same_file(_a: &Path, _b: &Path) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    /// The volume the file at `path` is on, which can't be told on this
+    /// platform, so every file counts as being on the same one.
+    pub(super) fn volume(_path: &Path) -> io::Result<u64> {
+        Ok(0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+    use crate::fs_utils::test_dir;
+
+    /// Creates a file at `path` that was last modified `age` seconds after
+    /// the epoch.
+    fn create(path: &Path, contents: &str, age: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(age)).unwrap();
+    }
+
+    #[test]
+    fn copies_are_linked_to_the_oldest_one() {
+        let directory = test_dir("dedup");
+        let (original, copy) = (directory.join("a/beach.jpg"), directory.join("b/beach (2).jpg"));
+        create(&original, "sand", 1_000);
+        create(&copy, "sand", 2_000);
+        create(&directory.join("c/other.jpg"), "surf", 500);
+
+        assert_eq!(hard_link_copy(&original, &directory).unwrap(), None);
+        assert_eq!(hard_link_copy(&copy, &directory).unwrap(), Some(original.clone()));
+        assert!(platform::same_file(&original, &copy).unwrap());
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "sand");
+        // nothing is left of the link that replaced the copy
+        assert_eq!(directory.join("b").read_dir().unwrap().count(), 1);
+
+        // once linked, there's nothing left to do
+        assert_eq!(hard_link_copy(&copy, &directory).unwrap(), None);
+        // files of the same size with other contents aren't copies
+        assert_eq!(hard_link_copy(directory.join("c/other.jpg"), &directory).unwrap(), None);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn only_files_with_contents_are_linked() {
+        let directory = test_dir("dedup-empty");
+        create(&directory.join("a"), "", 1_000);
+        create(&directory.join("b"), "", 2_000);
+
+        assert_eq!(hard_link_copy(directory.join("b"), &directory).unwrap(), None);
+        assert!(!platform::same_file(&directory.join("a"), &directory.join("b")).unwrap());
+        assert_eq!(hard_link_copy(&directory, &directory).unwrap(), None);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -62,14 +62,99 @@ impl std::error::Error for FailedToRemove {
 }
 
 
+/// Represents errors that occur when an [`Action`](super::Action) can't be
+/// applied to a file or directory.
+///
+/// Contains a description of what was being done, such as
+/// `"move to the trash"`, along with the path and the underlying error.
+#[derive(Debug)]
+pub struct ActionError {
+    action: &'static str,
+    path: PathBuf,
+    source: io::Error
+}
+
+impl ActionError {
+    /// Creates a new error from what was being done, a path and an I/O
+    /// error.
+    pub fn new(action: &'static str, path: &Path, source: io::Error) -> Self {
+        ActionError {
+            action,
+            path: path.to_path_buf(),
+            source
+        }
+    }
+
+    /// What was being done to the file or directory, such as `"remove"`.
+    pub fn action(&self) -> &'static str {
+        self.action
+    }
+
+    /// The path to the file or directory the action failed for.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The lower-level source of this error. See
+    /// [`FailedToRemove::io_source`].
+    pub fn io_source(&self) -> &io::Error {
+        &self.source
+    }
+}
+
+impl From<FailedToRemove> for ActionError {
+    fn from(e: FailedToRemove) -> Self {
+        ActionError {
+            action: "remove",
+            path: e.path,
+            source: e.source
+        }
+    }
+}
+
+impl std::fmt::Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "failed to {} \"{}\": {}",
+            self.action,
+            self.path.display(),
+            self.source
+        )
+    }
+}
+
+impl std::error::Error for ActionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+
 /// Does an error signal that a path wasn't found?
 pub fn not_found(e: &io::Error) -> bool {
     e.kind() == io::ErrorKind::NotFound
 }
 
+/// Does an error signal something that's likely to pass if the same thing is
+/// tried again a little later, such as a file being used by another program?
+pub fn is_transient(e: &io::Error) -> bool {
+    // also check against the Windows error codes for sharing and lock
+    // violations, which aren't mapped to an io::ErrorKind
+    matches!(
+        e.kind(),
+        io::ErrorKind::ResourceBusy
+            | io::ErrorKind::ExecutableFileBusy
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::TimedOut
+    ) || cfg!(windows) && matches!(e.raw_os_error(), Some(32 | 33))
+}
+
 /// Does an error signal that a path was unexpectedly not a directory?
 pub fn not_a_directory(e: &io::Error) -> bool {
-    // check against the Windows error code
-    // because io::ErrorKind::NotADirectory is unstable
-    matches!(e.raw_os_error(), Some(267))
+    // also check against the Windows error code
+    // because it hasn't always been mapped to io::ErrorKind::NotADirectory
+    e.kind() == io::ErrorKind::NotADirectory
+        || cfg!(windows) && matches!(e.raw_os_error(), Some(267))
 }
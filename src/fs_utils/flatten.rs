@@ -0,0 +1,115 @@
+//! Pulling files out of directories and removing the directories.
+
+use std::fs;
+use std::path::Path;
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::{move_into, Journal};
+
+
+/// Moves every file in a directory, including those in its subdirectories,
+/// into `root`, and then removes the directories that were emptied,
+/// recording where each file went in `journal`.
+///
+/// The root is normally the directory being cleaned, which the directory
+/// was found in or somewhere below, so that a routine flattening everything
+/// in the Downloads folder leaves the files directly in it. This is for
+/// keeping files while getting rid of the folders that tools like to nest
+/// them in. If a name is taken, a number is added to it like [`move_into`]
+/// does, so nothing is ever overwritten. Links are moved like files are,
+/// without looking into what they point to, and files are left where they
+/// are, since they aren't in a directory to be flattened.
+///
+/// # Errors
+///
+/// This function returns an error if the directory can't be read, if a file
+/// can't be moved or if a directory can't be removed. The files that were
+/// moved before that stay moved and are recorded in `journal`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::{flatten, Journal};
+///
+/// // leaves everything from the album in the Downloads folder itself
+/// let mut journal = Journal::new();
+/// let result = flatten(
+///     r"C:\Users\user\Downloads\music\album",
+///     r"C:\Users\user\Downloads",
+///     &mut journal
+/// );
+/// ```
+pub fn flatten<P: AsRef<Path>, R: AsRef<Path>>(path: P, root: R, journal: &mut Journal) -> Result<(), ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("flatten", path, e);
+
+    match path.symlink_metadata().map_err(error)?.is_dir() {
+        true => pull_into(path, root.as_ref(), journal),
+        false => Ok(())
+    }
+}
+
+/// Moves the files in `directory` and its subdirectories into `target`,
+/// removing `directory` and the subdirectories once they're empty.
+fn pull_into(directory: &Path, target: &Path, journal: &mut Journal) -> Result<(), ActionError> {
+    let error = |e| ActionError::new("flatten", directory, e);
+
+    for entry in directory.read_dir().map_err(error)? {
+        let entry = entry.map_err(error)?;
+        let path = entry.path();
+        match entry.file_type().map_err(error)?.is_dir() {
+            true => pull_into(&path, target, journal)?,
+            false => {
+                let moved_to = move_into(&path, target)?;
+                journal.record(&path, &moved_to);
+            }
+        }
+    }
+    fs::remove_dir(directory).map_err(error)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_utils::test_dir;
+
+    #[test]
+    fn files_are_pulled_out_without_replacing_others() {
+        let directory = test_dir("flatten");
+        fs::create_dir_all(directory.join("album/disc 2")).unwrap();
+        fs::write(directory.join("cover.jpg"), "old").unwrap();
+        fs::write(directory.join("album/cover.jpg"), "new").unwrap();
+        fs::write(directory.join("album/disc 2/song.mp3"), "").unwrap();
+
+        let mut journal = Journal::new();
+        flatten(directory.join("album"), &directory, &mut journal).unwrap();
+        assert!(!directory.join("album").exists());
+        assert_eq!(fs::read_to_string(directory.join("cover.jpg")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(directory.join("cover (2).jpg")).unwrap(), "new");
+        assert!(directory.join("song.mp3").exists());
+        assert_eq!(journal.entries().len(), 2);
+
+        // and put back where they were when undone
+        assert!(journal.restore().is_empty());
+        assert_eq!(fs::read_to_string(directory.join("album/cover.jpg")).unwrap(), "new");
+        assert!(directory.join("album/disc 2/song.mp3").exists());
+        assert!(!directory.join("song.mp3").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn files_are_left_where_they_are() {
+        let directory = test_dir("flatten-file");
+        fs::create_dir(directory.join("a")).unwrap();
+        fs::write(directory.join("a/notes.txt"), "").unwrap();
+
+        let mut journal = Journal::new();
+        flatten(directory.join("a/notes.txt"), &directory, &mut journal).unwrap();
+        assert!(directory.join("a/notes.txt").exists());
+        assert!(journal.is_empty());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+//! Recording where entries were moved so that they can be put back.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::fs_utils::error::{self, ActionError};
+use crate::fs_utils::relocate::copy_all;
+use crate::fs_utils::{remove, trash};
+
+
+/// A record of where entries were moved, which can be used to
+/// [`restore`](Self::restore()) them.
+///
+/// Actions that move entries, such as [`Action::MoveTo`](super::Action) and
+/// [`Action::Trash`](super::Action), record them when they're applied with
+/// a journal. Entries sent to the Recycle Bin on Windows aren't recorded,
+/// since they can only be restored from the Recycle Bin itself.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::{Action, Journal};
+/// use std::path::Path;
+///
+/// let mut journal = Journal::new();
+/// let action = Action::MoveTo(r"D:\Staging".into());
+/// action.apply_journaled(Path::new(r"C:\Users\user\Desktop\notes.txt"), &mut journal).unwrap();
+///
+/// // changed my mind
+/// for error in journal.restore() {
+///     eprintln!("{error}");
+/// }
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Journal {
+    entries: Vec<JournalEntry>
+}
+
+/// An entry that was moved from its `original` path.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub original: PathBuf,
+    pub moved_to: PathBuf
+}
+
+impl Journal {
+    /// Creates an empty journal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the entry at `original` was moved to `moved_to`.
+    pub fn record(&mut self, original: &Path, moved_to: &Path) {
+        self.entries.push(JournalEntry {
+            original: original.to_path_buf(),
+            moved_to: moved_to.to_path_buf()
+        });
+    }
+
+    /// The entries in the journal, in the order they were moved.
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Checks whether nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Reads a journal from a file.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the file can't be read or isn't a
+    /// journal.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the journal to a file, creating the directory it's in if
+    /// needed.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the file can't be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+
+        // a journal that's only half written would be worse than none, so
+        // the old one is only replaced once the new one is complete
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(temporary, path)
+    }
+
+    /// Moves every entry back to where it was, the last one first, and
+    /// removes them from the journal.
+    ///
+    /// Returns the errors for the entries that couldn't be restored, which
+    /// stay in the journal so that they can be tried again. That includes
+    /// entries whose original path has been taken by something else since,
+    /// because nothing is ever overwritten.
+    pub fn restore(&mut self) -> Vec<ActionError> {
+        let mut errors = Vec::new();
+        let mut left = Vec::new();
+        for entry in self.entries.drain(..).rev() {
+            if let Err(e) = restore(&entry) {
+                errors.push(e);
+                left.push(entry);
+            }
+        }
+        left.reverse();
+        self.entries = left;
+        errors
+    }
+}
+
+
+/// Moves an entry back to its original path.
+fn restore(entry: &JournalEntry) -> Result<(), ActionError> {
+    let error = |e| ActionError::new("restore", &entry.original, e);
+
+    match entry.original.symlink_metadata() {
+        Ok(_) => return Err(error(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "something else is there now"
+        ))),
+        Err(e) if error::not_found(&e) => {}
+        Err(e) => return Err(error(e))
+    }
+    if let Some(parent) = entry.original.parent() {
+        fs::create_dir_all(parent).map_err(error)?;
+    }
+
+    match fs::rename(&entry.moved_to, &entry.original) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_all(&entry.moved_to, &entry.original) {
+                let _ = remove(&entry.original);
+                return Err(error(e));
+            }
+            remove(&entry.moved_to)?;
+        }
+        Err(e) => return Err(error(e))
+    }
+    trash::forget(&entry.moved_to);
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_utils::{test_dir, Action};
+
+    #[test]
+    fn moved_entries_are_restored() {
+        let directory = test_dir("journal-restore");
+        let original = directory.join("desktop/notes/todo.txt");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, "todo").unwrap();
+
+        let mut journal = Journal::new();
+        Action::MoveTo(directory.join("staging").into()).apply_journaled(&original, &mut journal).unwrap();
+        assert_eq!(journal.entries(), [JournalEntry {
+            original: original.clone(),
+            moved_to: directory.join("staging/todo.txt")
+        }]);
+
+        // the directory the entry was in is put back too
+        fs::remove_dir(original.parent().unwrap()).unwrap();
+        assert!(journal.restore().is_empty());
+        assert!(journal.is_empty());
+        assert_eq!(fs::read_to_string(&original).unwrap(), "todo");
+        assert!(!directory.join("staging/todo.txt").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn restoring_never_replaces_anything() {
+        let directory = test_dir("journal-taken");
+        let (original, moved_to) = (directory.join("notes.txt"), directory.join("moved.txt"));
+        fs::write(&original, "new").unwrap();
+        fs::write(&moved_to, "old").unwrap();
+
+        let mut journal = Journal::new();
+        journal.record(&original, &moved_to);
+        let errors = journal.restore();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].io_source().kind(), io::ErrorKind::AlreadyExists);
+        // so that it can be tried again
+        assert_eq!(journal.entries().len(), 1);
+        assert_eq!(fs::read_to_string(&original).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&moved_to).unwrap(), "old");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn the_last_move_is_undone_first() {
+        let directory = test_dir("journal-order");
+        let [a, b, c] = ["a", "b", "c"].map(|name| directory.join(name));
+        fs::write(&c, "").unwrap();
+
+        let mut journal = Journal::new();
+        journal.record(&a, &b);
+        journal.record(&b, &c);
+        assert!(journal.restore().is_empty());
+        assert!(a.exists());
+        assert!(!b.exists());
+        assert!(!c.exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn journals_can_be_saved_and_loaded() {
+        let directory = test_dir("journal-save");
+        let path = directory.join("journals/test.json");
+
+        let mut journal = Journal::new();
+        journal.record(Path::new("/a"), Path::new("/b"));
+        journal.save(&path).unwrap();
+        assert_eq!(Journal::load(&path).unwrap(), journal);
+        assert!(!path.with_extension("tmp").exists());
+
+        fs::write(&path, "not a journal").unwrap();
+        assert_eq!(Journal::load(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! Marking files and directories as retired without removing them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fs_utils::error::ActionError;
+
+
+/// Makes a file read-only, or a directory and everything in it.
+///
+/// This leaves entries where they are but keeps them from being changed,
+/// which is a way to retire them visibly while getting to trust a pattern
+/// before letting it remove anything. Links are left alone, since what they
+/// point to isn't theirs to change. On Unix, nobody is left with write
+/// permission for the entries.
+///
+/// # Errors
+///
+/// This function returns an error if an entry's permissions can't be read
+/// or changed, for example because it doesn't exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::make_read_only;
+///
+/// let result = make_read_only(r"C:\Users\user\Documents\old-report.docx");
+/// ```
+pub fn make_read_only<P: AsRef<Path>>(path: P) -> Result<(), ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("make read-only", path, e);
+
+    let metadata = path.symlink_metadata().map_err(error)?;
+    if metadata.is_symlink() {
+        return Ok(());
+    }
+    if metadata.is_dir() {
+        for entry in path.read_dir().map_err(error)? {
+            make_read_only(entry.map_err(error)?.path())?;
+        }
+    }
+    let mut permissions = metadata.permissions();
+    if !permissions.readonly() {
+        permissions.set_readonly(true);
+        fs::set_permissions(path, permissions).map_err(error)?;
+    }
+    Ok(())
+}
+
+/// Hides a file or directory, returning its path afterwards.
+///
+/// On Windows, the entry's hidden attribute is set and its path stays the
+/// same. Elsewhere, entries are hidden by adding a dot to the start of
+/// their names, adding a number to the name if it's taken like
+/// [`move_into`](super::move_into) does. Entries that are already hidden
+/// are left as they are.
+///
+/// # Errors
+///
+/// This function returns an error if the entry can't be hidden, for
+/// example because it doesn't exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::hide;
+///
+/// let hidden = hide(r"C:\Users\user\Desktop\notes.txt").unwrap();
+/// ```
+pub fn hide<P: AsRef<Path>>(path: P) -> Result<PathBuf, ActionError> {
+    let path = path.as_ref();
+    platform::hide(path).map_err(|e| ActionError::new("hide", path, e))
+}
+
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::MetadataExt;
+    use std::path::{Path, PathBuf};
+    use windows_sys::Win32::Storage::FileSystem::{
+        SetFileAttributesW,
+        FILE_ATTRIBUTE_HIDDEN,
+        FILE_ATTRIBUTE_NORMAL
+    };
+
+    pub(super) fn hide(path: &Path) -> io::Result<PathBuf> {
+        let attributes = path.symlink_metadata()?.file_attributes();
+        if attributes & FILE_ATTRIBUTE_HIDDEN != 0 {
+            return Ok(path.to_path_buf());
+        }
+        // the normal attribute is only valid on its own
+        let attributes = attributes & !FILE_ATTRIBUTE_NORMAL | FILE_ATTRIBUTE_HIDDEN;
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+
+        // SAFETY: the path ends with a null and outlives the call
+        match unsafe { SetFileAttributesW(wide.as_ptr(), attributes) } {
+            0 => Err(io::Error::last_os_error()),
+            _ => Ok(path.to_path_buf())
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use crate::fs_utils::relocate::free_path;
+
+    pub(super) fn hide(path: &Path) -> io::Result<PathBuf> {
+        path.symlink_metadata()?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with('.') {
+            return Ok(path.to_path_buf());
+        }
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let target = free_path(directory, Path::new(&format!(".{name}")))?;
+        fs::rename(path, &target)?;
+        Ok(target)
+    }
+}
@@ -3,11 +3,84 @@
 //! This module provides miscellaneous tools that make interacting with
 //! the file system more convenient.
 
+mod action;
+mod archive;
+mod backup;
+mod command;
+mod compress;
+mod dedup;
 pub mod error;
+mod flatten;
+mod journal;
+mod mark;
 mod op;
 mod pattern;
+mod quarantine;
+mod relocate;
+mod shred;
+mod tag;
+mod trash;
+mod truncate;
+mod upload;
 
 #[doc(inline)]
-pub use op::remove;
+pub use action::Action;
 #[doc(inline)]
-pub use pattern::FilePattern;
+pub use archive::archive_into;
+#[doc(inline)]
+pub use backup::back_up;
+#[doc(inline)]
+pub use command::run_command;
+#[doc(inline)]
+pub use compress::gzip;
+#[doc(inline)]
+pub use dedup::hard_link_copy;
+#[doc(inline)]
+pub use flatten::flatten;
+#[doc(inline)]
+pub use journal::{Journal, JournalEntry};
+#[doc(inline)]
+pub use mark::{hide, make_read_only};
+#[doc(inline)]
+pub use op::{remove, remove_empty_parents};
+#[doc(inline)]
+pub use quarantine::{purge_pattern, quarantine};
+#[doc(inline)]
+pub use relocate::{move_by_date, move_into, move_keeping_structure, MoveTarget};
+#[doc(inline)]
+pub use shred::shred;
+#[doc(inline)]
+pub use tag::tag;
+#[doc(inline)]
+pub use trash::trash;
+#[doc(inline)]
+pub use truncate::truncate;
+#[doc(inline)]
+pub use upload::{upload, UploadTarget};
+#[doc(inline)]
+pub use pattern::{
+    CustomPattern,
+    DEFAULT_CONTENT_MAX_SIZE,
+    FileAttribute,
+    FilePattern,
+    MatchContext,
+    MatchExplanation,
+    MatchOptions,
+    ParsePatternError,
+    PatternBuilder,
+    RegexPattern,
+    Timestamp
+};
+pub use pattern::ignore;
+pub(crate) use pattern::{check_date_format, check_glob, check_owner, same_contents, write_date};
+pub(crate) use relocate::{check_layout, free_path, numbered};
+
+
+/// An empty directory for a test to work in, called `name`.
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> std::path::PathBuf {
+    let directory = std::env::temp_dir().join(format!("folder_cleaner-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
@@ -38,6 +38,53 @@ pub fn remove<P: AsRef<Path>>(path: P) -> Result<(), FailedToRemove> {
     }
 }
 
+/// Removes the directories above `path` that are empty, up to but not
+/// including `root`.
+/// 
+/// This is meant to be used after removing `path`, to also remove the
+/// directories that only existed to hold it. Nothing is removed if `path`
+/// isn't inside `root`, and the first directory that isn't empty stops the
+/// walk up.
+/// 
+/// # Errors
+/// 
+/// This function returns an error if an empty directory can't be removed.
+/// Directories that aren't empty or that don't exist aren't errors.
+/// 
+/// # Examples
+/// 
+/// ```no_run
+/// use folder_cleaner::fs_utils::{remove, remove_empty_parents};
+/// 
+/// let path = r"C:\Users\user\Downloads\old\stuff\setup.exe";
+/// remove(path).unwrap();
+/// remove_empty_parents(path, r"C:\Users\user\Downloads").unwrap();
+/// ```
+pub fn remove_empty_parents<P: AsRef<Path>, R: AsRef<Path>>(
+    path: P,
+    root: R
+) -> Result<(), FailedToRemove> {
+    let root = root.as_ref();
+    let parents = path.as_ref()
+        .ancestors()
+        .skip(1)
+        .take_while(|parent| *parent != root && parent.starts_with(root));
+
+    for parent in parents {
+        match fs::remove_dir(parent) {
+            Ok(()) => {}
+            Err(e) if error::not_found(&e) => {}
+            // a directory that isn't empty ends the walk, which has to be
+            // told from other errors by looking inside it
+            Err(e) => return match parent.read_dir().map(|mut entries| entries.next().is_some()) {
+                Ok(true) => Ok(()),
+                _ => Err(FailedToRemove::new(parent, e))
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Removes a directory.
 /// 
 /// One half of [`remove`]. The directory does not need to be empty.
@@ -95,7 +142,7 @@ fn remove_file<P: AsRef<Path>>(path: P) -> Result<(), FailedToRemove> {
 /// [`std::fs::remove_file`] as is, but Rust doesn't like that and I have
 /// no idea how to get around it without closures.
 /// 
-/// ```no_run
+/// ```ignore
 /// remove_with(
 ///     |p| {fs::remove_file(p)},
 ///     r"C:\path\to\file"
@@ -0,0 +1,82 @@
+use std::path::Path;
+use serde::Deserialize;
+
+
+/// File attributes that patterns can match on.
+///
+/// Apart from [`ReadOnly`](Self::ReadOnly), these are Windows file
+/// attributes. On other platforms, entries whose names start with a dot
+/// count as hidden, as that's how they're hidden there, and the other
+/// Windows attributes never match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileAttribute {
+    /// The entry is hidden from ordinary directory listings.
+    Hidden,
+    /// The entry is used by the operating system, like `desktop.ini`.
+    System,
+    /// The entry is meant to be temporary.
+    Temporary,
+    /// The entry can't be written to. Excluding read-only entries lets
+    /// users protect files from a routine by marking them read-only.
+    ///
+    /// On Unix, an entry is read-only if nobody has write permission for it.
+    ReadOnly
+}
+
+impl FileAttribute {
+    const ALL: [Self; 4] = [Self::Hidden, Self::System, Self::Temporary, Self::ReadOnly];
+
+    /// The name of the attribute in patterns.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hidden => "hidden",
+            Self::System => "system",
+            Self::Temporary => "temporary",
+            Self::ReadOnly => "read_only"
+        }
+    }
+
+    /// The attribute with the given [name](Self::name()).
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|attribute| attribute.name() == name)
+    }
+
+    /// Checks whether the entry at `path` has this attribute.
+    ///
+    /// Entries whose attributes can't be read don't have any.
+    #[cfg(windows)]
+    pub(crate) fn is_set(self, path: &Path) -> bool {
+        use std::os::windows::fs::MetadataExt;
+
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+        const FILE_ATTRIBUTE_TEMPORARY: u32 = 0x100;
+
+        let flag = match self {
+            Self::Hidden => FILE_ATTRIBUTE_HIDDEN,
+            Self::System => FILE_ATTRIBUTE_SYSTEM,
+            Self::Temporary => FILE_ATTRIBUTE_TEMPORARY,
+            // the read-only attribute is what the permissions are based on
+            Self::ReadOnly => return is_read_only(path)
+        };
+        path.symlink_metadata()
+            .is_ok_and(|m| m.file_attributes() & flag != 0)
+    }
+
+    /// Checks whether the entry at `path` has this attribute.
+    #[cfg(not(windows))]
+    pub(crate) fn is_set(self, path: &Path) -> bool {
+        match self {
+            Self::Hidden => path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.')),
+            Self::ReadOnly => is_read_only(path),
+            Self::System | Self::Temporary => false
+        }
+    }
+}
+
+fn is_read_only(path: &Path) -> bool {
+    path.symlink_metadata().is_ok_and(|m| m.permissions().readonly())
+}
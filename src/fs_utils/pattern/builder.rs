@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use super::{FilePattern, RegexPattern, Timestamp};
+
+
+/// Builds a [`FilePattern`] out of conditions that must all be met.
+///
+/// Every method adds a condition, and [`build`](Self::build()) combines them
+/// into an [`All`](FilePattern::All) pattern. A builder without any
+/// conditions builds [`Any`](FilePattern::Any), and one with a single
+/// condition builds just that condition.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::fs_utils::{FilePattern, PatternBuilder};
+/// use std::time::Duration;
+///
+/// let pattern = PatternBuilder::new()
+///     .extension("log")
+///     .older_than(Duration::from_secs(7 * 24 * 60 * 60))
+///     .exclude_name("keep")
+///     .build();
+/// assert_eq!(pattern, "ext(log) and older(1w) and not name(keep)".parse().unwrap());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatternBuilder {
+    patterns: Vec<FilePattern>
+}
+
+impl PatternBuilder {
+    /// Creates a builder without any conditions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires entries to match `pattern`.
+    pub fn matching(mut self, pattern: FilePattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Requires entries not to match `pattern`.
+    pub fn exclude(self, pattern: FilePattern) -> Self {
+        self.matching(FilePattern::Not(Box::new(pattern)))
+    }
+
+    /// Requires entries to match at least one of `patterns`.
+    pub fn any_of<I: IntoIterator<Item = FilePattern>>(self, patterns: I) -> Self {
+        self.matching(FilePattern::AnyOf(patterns.into_iter().collect()))
+    }
+
+    /// Requires entries to have the given extension. See
+    /// [`FilePattern::Extension`].
+    pub fn extension<S: Into<String>>(self, ext: S) -> Self {
+        self.matching(FilePattern::Extension(ext.into()))
+    }
+
+    /// Requires entries to have one of the given extensions. See
+    /// [`FilePattern::Extensions`].
+    pub fn extensions<I, S>(self, exts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>
+    {
+        self.matching(FilePattern::Extensions(exts.into_iter().map(Into::into).collect()))
+    }
+
+    /// Requires entries not to have the given extension.
+    pub fn exclude_extension<S: Into<String>>(self, ext: S) -> Self {
+        self.matching(FilePattern::NotExtension(vec![ext.into()]))
+    }
+
+    /// Requires the names of entries to match a wildcard pattern. See
+    /// [`FilePattern::Glob`].
+    pub fn glob<S: Into<String>>(self, glob: S) -> Self {
+        self.matching(FilePattern::Glob(glob.into()))
+    }
+
+    /// Requires the relative paths of entries to match a wildcard pattern.
+    /// See [`FilePattern::RelativePath`].
+    pub fn path<S: Into<String>>(self, glob: S) -> Self {
+        self.matching(FilePattern::RelativePath(glob.into()))
+    }
+
+    /// Requires the names of entries to contain the given text.
+    pub fn name_contains<S: Into<String>>(self, part: S) -> Self {
+        self.matching(FilePattern::NameContains(part.into()))
+    }
+
+    /// Requires the names of entries not to contain the given text.
+    pub fn exclude_name<S: Into<String>>(self, part: S) -> Self {
+        self.exclude(FilePattern::NameContains(part.into()))
+    }
+
+    /// Requires the names of entries to match a regular expression. See
+    /// [`FilePattern::Regex`].
+    pub fn regex<S: Into<String>>(self, regex: S) -> Self {
+        self.matching(FilePattern::Regex(RegexPattern::new(regex)))
+    }
+
+    /// Requires entries to have been modified longer ago than `age`.
+    pub fn older_than(self, age: Duration) -> Self {
+        self.older_than_by(age, Timestamp::Modified)
+    }
+
+    /// Requires the given timestamp of entries to be older than `age`. See
+    /// [`FilePattern::OlderThan`].
+    pub fn older_than_by(self, age: Duration, timestamp: Timestamp) -> Self {
+        self.matching(FilePattern::OlderThan(age, timestamp))
+    }
+
+    /// Requires entries to be files larger than `bytes`.
+    pub fn larger_than(self, bytes: u64) -> Self {
+        self.matching(FilePattern::LargerThan(bytes))
+    }
+
+    /// Requires entries to be files smaller than `bytes`.
+    pub fn smaller_than(self, bytes: u64) -> Self {
+        self.matching(FilePattern::SmallerThan(bytes))
+    }
+
+    /// Requires entries to be files.
+    pub fn files(self) -> Self {
+        self.matching(FilePattern::IsFile)
+    }
+
+    /// Requires entries to be directories.
+    pub fn dirs(self) -> Self {
+        self.matching(FilePattern::IsDir)
+    }
+
+    /// Combines the conditions into a pattern.
+    pub fn build(mut self) -> FilePattern {
+        match self.patterns.len() {
+            0 => FilePattern::Any,
+            1 => self.patterns.remove(0),
+            _ => FilePattern::All(self.patterns)
+        }
+    }
+}
+
+impl From<PatternBuilder> for FilePattern {
+    fn from(builder: PatternBuilder) -> Self {
+        builder.build()
+    }
+}
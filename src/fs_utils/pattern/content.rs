@@ -0,0 +1,30 @@
+//! Matching files by what they contain.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+
+/// How much of a file is read by default when searching its contents.
+pub const DEFAULT_CONTENT_MAX_SIZE: u64 = 10_000_000;
+
+
+/// Reads the file at `path` as text, unless it's larger than `max_size`.
+///
+/// Invalid UTF-8 is replaced rather than rejected, so that logs with the odd
+/// broken character can still be searched. Returns `None` for directories,
+/// links and files that can't be read.
+pub(crate) fn read_text(path: &Path, max_size: u64) -> Option<String> {
+    let metadata = path.symlink_metadata().ok()?;
+    if !metadata.is_file() || metadata.len() > max_size {
+        return None;
+    }
+
+    let mut bytes = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(max_size)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
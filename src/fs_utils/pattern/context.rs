@@ -0,0 +1,172 @@
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::ignore::{IgnoreFile, IGNORE_FILE_NAME};
+use super::{duplicate, fold, retention, FilePattern, MatchOptions};
+
+
+/// What a pattern needs to know about the directory it's matching in.
+///
+/// Some patterns depend on the other entries in the directory as well as
+/// the one being matched, such as [`Duplicate`](super::FilePattern::Duplicate)
+/// and [`KeepNewest`](super::FilePattern::KeepNewest).
+/// The context works out what they need the first time it's needed and
+/// remembers it, so matching every entry of a directory with the same
+/// context only scans the directory once.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::{FilePattern, MatchContext, MatchOptions};
+///
+/// let directory = r"C:\Users\user\Pictures\Screenshots";
+/// let context = MatchContext::new(directory, MatchOptions::default());
+/// for entry in std::fs::read_dir(directory).unwrap().flatten() {
+///     if FilePattern::Duplicate.matches_in(&entry.path(), &context) {
+///         println!("{} is a copy", entry.path().display());
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MatchContext {
+    directory: PathBuf,
+    root: PathBuf,
+    options: MatchOptions,
+    duplicates: OnceCell<HashSet<PathBuf>>,
+    /// The ignore files from the root down to the directory, along with the
+    /// directories they're in.
+    ignore_files: OnceCell<Vec<(PathBuf, IgnoreFile)>>,
+    /// The entries kept by each `KeepNewest` pattern matched so far.
+    kept: RefCell<Vec<(usize, FilePattern, HashSet<PathBuf>)>>
+}
+
+impl MatchContext {
+    /// Creates a context for matching entries of `directory`.
+    ///
+    /// The directory is also the [root](Self::root()) of the context.
+    pub fn new<P: Into<PathBuf>>(directory: P, options: MatchOptions) -> Self {
+        let directory = directory.into();
+        MatchContext {
+            root: directory.clone(),
+            directory,
+            options,
+            duplicates: OnceCell::new(),
+            ignore_files: OnceCell::new(),
+            kept: RefCell::new(Vec::new())
+        }
+    }
+
+    /// Sets the [root](Self::root()) of the context.
+    pub fn with_root<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// The directory whose entries are being matched.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// The directory that [`RelativePath`](super::FilePattern::RelativePath)
+    /// patterns are relative to, such as the directory of a routine when its
+    /// subdirectories are being cleaned too.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path of `path` relative to the [root](Self::root()), with its
+    /// components separated by `/` on every platform.
+    ///
+    /// Returns `None` if `path` isn't inside the root.
+    pub(crate) fn relative_path(&self, path: &Path) -> Option<String> {
+        relative_to(&self.root, path)
+    }
+
+    /// The options patterns are matched with.
+    pub fn options(&self) -> MatchOptions {
+        self.options
+    }
+
+    /// Checks whether `path` is a copy of another file in the directory.
+    pub(crate) fn is_duplicate(&self, path: &Path) -> bool {
+        self.duplicates
+            .get_or_init(|| duplicate::find(&self.directory))
+            .contains(path)
+    }
+
+    /// Checks whether the entry at `path` is protected by a
+    /// [`.cleanerignore`](super::ignore) file in the directory or in any
+    /// directory between it and the [root](Self::root()).
+    ///
+    /// Deeper ignore files take precedence, and the ignore files themselves
+    /// are always protected.
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if path.file_name().is_some_and(|name| name == IGNORE_FILE_NAME) {
+            return true;
+        }
+
+        let is_dir = path.symlink_metadata().is_ok_and(|m| m.is_dir());
+        let fold = |text: &str| fold(text, self.options).into_owned();
+        let mut ignored = false;
+        for (directory, file) in self.ignore_files() {
+            let Some(relative) = relative_to(directory, path) else {
+                continue;
+            };
+            if let Some(decision) = file.decide(&relative, is_dir, fold) {
+                ignored = decision;
+            }
+        }
+        ignored
+    }
+
+    fn ignore_files(&self) -> &[(PathBuf, IgnoreFile)] {
+        self.ignore_files.get_or_init(|| {
+            let mut directories: Vec<&Path> = self.directory
+                .ancestors()
+                .take_while(|dir| dir.starts_with(&self.root))
+                .collect();
+            if directories.is_empty() {
+                directories.push(&self.directory);
+            }
+
+            directories
+                .into_iter()
+                .rev()
+                .filter_map(|dir| IgnoreFile::read(dir).map(|file| (dir.to_path_buf(), file)))
+                .collect()
+        })
+    }
+
+    /// Checks whether `path` is one of the `count` newest entries in the
+    /// directory that match `pattern`.
+    pub(crate) fn is_kept(&self, path: &Path, count: usize, pattern: &FilePattern) -> bool {
+        let cached = self.kept
+            .borrow()
+            .iter()
+            .find(|(n, p, _)| *n == count && p == pattern)
+            .map(|(_, _, kept)| kept.contains(path));
+        if let Some(is_kept) = cached {
+            return is_kept;
+        }
+
+        // the borrow can't be held while matching, since the pattern might
+        // contain patterns that are kept too
+        let kept = retention::newest(&self.directory, count, pattern, self);
+        let is_kept = kept.contains(path);
+        self.kept.borrow_mut().push((count, pattern.clone(), kept));
+        is_kept
+    }
+}
+
+
+/// The path of `path` relative to `base`, with its components separated by
+/// `/` on every platform.
+fn relative_to(base: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    let components: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    Some(components.join("/"))
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+
+/// Arbitrary matching logic used as a [`FilePattern`](super::FilePattern).
+///
+/// Custom patterns let applications that embed this crate select entries in
+/// ways the other patterns can't express, while still using routines to
+/// remove them. They only exist in code, so they can't be written in
+/// configuration files, and serializing a pattern that contains one fails.
+///
+/// Clones share the same function, and patterns are only equal to their own
+/// clones.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use folder_cleaner::fs_utils::{CustomPattern, FilePattern};
+///
+/// let pattern = FilePattern::Custom(CustomPattern::new(|path| {
+///     path.to_string_lossy().len() > 100
+/// }));
+/// assert!(!pattern.matches(Path::new("short.txt")));
+/// ```
+#[derive(Clone)]
+pub struct CustomPattern {
+    predicate: Arc<dyn Fn(&Path) -> bool + Send + Sync>
+}
+
+impl CustomPattern {
+    /// Creates a pattern that matches the entries `predicate` returns `true`
+    /// for.
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static
+    {
+        CustomPattern { predicate: Arc::new(predicate) }
+    }
+
+    /// Checks whether the entry at `path` matches.
+    pub fn is_match(&self, path: &Path) -> bool {
+        (self.predicate)(path)
+    }
+}
+
+impl PartialEq for CustomPattern {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.predicate, &other.predicate)
+    }
+}
+
+impl Eq for CustomPattern {}
+
+impl fmt::Debug for CustomPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomPattern").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,152 @@
+//! Finding files with identical contents.
+//!
+//! Files are first grouped by size, since files of different sizes can't be
+//! identical, then by a hash of their contents, and finally compared byte by
+//! byte so that a hash collision can never cause a file to be removed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+
+/// Finds the files directly inside `directory` that are copies of another
+/// file there.
+///
+/// Of every set of identical files, the one that was modified first is
+/// considered the original, with ties broken by name, and the rest are
+/// returned. Empty files aren't considered copies of each other, and files
+/// that can't be read are left out.
+pub(crate) fn find(directory: &Path) -> HashSet<PathBuf> {
+    let mut by_size: HashMap<u64, Vec<(SystemTime, PathBuf)>> = HashMap::new();
+    for entry in directory.read_dir().into_iter().flatten().flatten() {
+        let Ok(metadata) = entry.path().symlink_metadata() else {
+            continue;
+        };
+        if metadata.is_file() && metadata.len() > 0 {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            by_size.entry(metadata.len()).or_default().push((modified, entry.path()));
+        }
+    }
+
+    let mut copies = HashSet::new();
+    for files in by_size.into_values().filter(|files| files.len() > 1) {
+        let mut by_hash: HashMap<u64, Vec<(SystemTime, PathBuf)>> = HashMap::new();
+        for (modified, path) in files {
+            if let Ok(hash) = hash_file(&path) {
+                by_hash.entry(hash).or_default().push((modified, path));
+            }
+        }
+
+        for mut files in by_hash.into_values().filter(|files| files.len() > 1) {
+            files.sort();
+            let mut originals: Vec<PathBuf> = Vec::new();
+            for (_, path) in files {
+                // a hash collision shouldn't make different files copies
+                let is_copy = originals
+                    .iter()
+                    .any(|original| same_contents(original, &path).unwrap_or(false));
+                match is_copy {
+                    true => {
+                        copies.insert(path);
+                    }
+                    false => originals.push(path)
+                }
+            }
+        }
+    }
+    copies
+}
+
+fn hash_file(path: &Path) -> io::Result<u64> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&buffer[..read]);
+    }
+}
+
+pub(super) fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (BufReader::new(File::open(a)?), BufReader::new(File::open(b)?));
+    let (mut buffer_a, mut buffer_b) = ([0; 8192], [0; 8192]);
+    loop {
+        let read = read_full(&mut a, &mut buffer_a)?;
+        if read != read_full(&mut b, &mut buffer_b)? || buffer_a[..read] != buffer_b[..read] {
+            return Ok(false);
+        }
+        if read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Reads until `buffer` is full or the end of the file is reached.
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buffer.len() {
+        match reader.read(&mut buffer[total..])? {
+            0 => break,
+            read => total += read
+        }
+    }
+    Ok(total)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use crate::fs_utils::test_dir;
+
+    /// Creates a file in `directory` that was last modified `age` seconds
+    /// after the epoch.
+    fn create(directory: &Path, name: &str, contents: &str, age: u64) -> PathBuf {
+        let path = directory.join(name);
+        fs::write(&path, contents).unwrap();
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(age)).unwrap();
+        path
+    }
+
+    #[test]
+    fn the_oldest_copy_is_the_original() {
+        let directory = test_dir("duplicates");
+        create(&directory, "photo.jpg", "pixels", 1_000);
+        let copy = create(&directory, "photo (2).jpg", "pixels", 2_000);
+        // modified at the same time, so the name decides
+        let tie = create(&directory, "z.jpg", "pixels", 1_000);
+        create(&directory, "other.jpg", "pixelz", 500);
+        create(&directory, "empty", "", 1_000);
+        create(&directory, "empty (2)", "", 2_000);
+        fs::create_dir(directory.join("photos")).unwrap();
+
+        assert_eq!(find(&directory), HashSet::from([copy, tie]));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn contents_are_compared_to_the_end() {
+        let directory = test_dir("duplicates-contents");
+        let long = "x".repeat(10_000);
+        let a = create(&directory, "a", &long, 0);
+        let b = create(&directory, "b", &format!("{long}y"), 0);
+        let c = create(&directory, "c", &format!("{long}z"), 0);
+        assert!(same_contents(&a, &a).unwrap());
+        assert!(!same_contents(&a, &b).unwrap());
+        assert!(!same_contents(&b, &c).unwrap());
+        assert!(find(&directory).is_empty());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
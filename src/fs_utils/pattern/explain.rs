@@ -0,0 +1,96 @@
+use std::fmt;
+use std::path::Path;
+
+use super::{FilePattern, MatchContext};
+
+
+/// The result of matching an entry against a pattern, along with the results
+/// of every pattern nested inside it.
+///
+/// Unlike when simply matching, every nested pattern is checked, even the
+/// ones that couldn't change the result, so the explanation shows every
+/// reason an entry did or didn't match.
+///
+/// An explanation is [displayed](fmt::Display) as an indented tree with one
+/// pattern per line.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::fs_utils::FilePattern;
+/// use std::path::Path;
+///
+/// let pattern: FilePattern = "ext(iso) and not name(keep)".parse().unwrap();
+/// let explanation = pattern.explain(Path::new("keep-this.iso"));
+/// assert!(!explanation.is_match());
+/// assert_eq!(explanation.to_string(), "\
+/// all(ext(iso), not(contains(keep))): no match
+///   ext(iso): match
+///   not(contains(keep)): no match
+///     contains(keep): match
+/// ");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchExplanation<'a> {
+    pattern: &'a FilePattern,
+    matched: bool,
+    children: Vec<MatchExplanation<'a>>
+}
+
+impl<'a> MatchExplanation<'a> {
+    pub(super) fn new(pattern: &'a FilePattern, path: &Path, context: &MatchContext) -> Self {
+        let explain = |patterns: &'a [FilePattern]| -> Vec<Self> {
+            patterns.iter().map(|p| Self::new(p, path, context)).collect()
+        };
+        let (matched, children) = match pattern {
+            FilePattern::All(patterns) => {
+                let children = explain(patterns);
+                (children.iter().all(Self::is_match), children)
+            }
+            FilePattern::AnyOf(patterns) => {
+                let children = explain(patterns);
+                (children.iter().any(Self::is_match), children)
+            }
+            FilePattern::Not(pattern) => {
+                let children = explain(std::slice::from_ref(pattern));
+                (!children[0].is_match(), children)
+            }
+            _ => (pattern.matches_in(path, context), Vec::new())
+        };
+        MatchExplanation { pattern, matched, children }
+    }
+
+    /// The pattern this explanation is about.
+    pub fn pattern(&self) -> &'a FilePattern {
+        self.pattern
+    }
+
+    /// Whether the entry matched the [pattern](Self::pattern()).
+    pub fn is_match(&self) -> bool {
+        self.matched
+    }
+
+    /// The explanations of the patterns directly inside a composite pattern,
+    /// in order. Other patterns have none.
+    pub fn children(&self) -> &[MatchExplanation<'a>] {
+        &self.children
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let result = match self.matched {
+            true => "match",
+            false => "no match"
+        };
+        writeln!(f, "{:indent$}{}: {result}", "", self.pattern, indent = depth * 2)?;
+        for child in &self.children {
+            child.write(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MatchExplanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
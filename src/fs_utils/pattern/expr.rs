@@ -0,0 +1,254 @@
+//! Parsing of pattern expressions, which combine patterns in their short
+//! form with `and`, `or`, `not` and parentheses. See
+//! [`FilePattern`](crate::fs_utils::FilePattern#expressions) for the syntax.
+
+use crate::fs_utils::FilePattern;
+use super::repr::{parse_args, ParsePatternError};
+
+
+/// Parses a pattern expression.
+pub(super) fn parse(input: &str) -> Result<FilePattern, ParsePatternError> {
+    let mut parser = Parser { input, pos: 0 };
+    let pattern = parser.or()?;
+
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(pattern),
+        Some(')') => Err(parser.error("unmatched closing parenthesis")),
+        Some(_) => Err(parser.error("expected \"and\" or \"or\""))
+    }
+}
+
+
+/// A parser that keeps track of its position in the input.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize
+}
+
+impl<'a> Parser<'a> {
+    /// Parses patterns separated by `or`.
+    fn or(&mut self) -> Result<FilePattern, ParsePatternError> {
+        let mut patterns = vec![self.and()?];
+        while self.keyword("or") {
+            patterns.push(self.and()?);
+        }
+        Ok(match patterns.len() {
+            1 => patterns.remove(0),
+            _ => FilePattern::AnyOf(patterns)
+        })
+    }
+
+    /// Parses patterns separated by `and`.
+    fn and(&mut self) -> Result<FilePattern, ParsePatternError> {
+        let mut patterns = vec![self.unary()?];
+        while self.keyword("and") {
+            patterns.push(self.unary()?);
+        }
+        Ok(match patterns.len() {
+            1 => patterns.remove(0),
+            _ => FilePattern::All(patterns)
+        })
+    }
+
+    /// Parses a pattern that may be negated or in parentheses.
+    fn unary(&mut self) -> Result<FilePattern, ParsePatternError> {
+        if self.keyword("not") {
+            return Ok(FilePattern::Not(Box::new(self.unary()?)));
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let pattern = self.or()?;
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(')') => {
+                        self.pos += 1;
+                        Ok(pattern)
+                    }
+                    _ => Err(self.error("missing closing parenthesis"))
+                }
+            }
+            _ => self.pattern()
+        }
+    }
+
+    /// Parses a single pattern in its short form, such as `ext(lnk)`.
+    fn pattern(&mut self) -> Result<FilePattern, ParsePatternError> {
+        let start = self.pos;
+        let name = self.identifier();
+        if name.is_empty() {
+            return Err(match self.peek() {
+                None if start == 0 => self.error("missing pattern name"),
+                None => self.error("expected a pattern after the operator"),
+                Some(c) => self.error(format!("unexpected \"{c}\""))
+            });
+        }
+        if ["and", "or", "not"].contains(&name) {
+            self.pos = start;
+            return Err(self.error(format!("expected a pattern before \"{name}\"")));
+        }
+
+        let after_name = self.pos;
+        self.skip_whitespace();
+        let args = match self.peek() {
+            Some('(') => {
+                let inner = self.arguments()?;
+                parse_args(inner).map_err(|reason| self.error(reason))?
+            }
+            _ => {
+                self.pos = after_name;
+                Vec::new()
+            }
+        };
+
+        FilePattern::from_short(name, args).map_err(|reason| self.error_at(start, reason))
+    }
+
+    /// Consumes the parenthesized arguments of a pattern, returning what's
+    /// inside the parentheses.
+    fn arguments(&mut self) -> Result<&'a str, ParsePatternError> {
+        let input = self.input;
+        let open = self.pos;
+        let mut depth = 0;
+        let mut chars = input[open..].char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos = open + i + 1;
+                        return Ok(&input[open + 1..open + i]);
+                    }
+                }
+                '"' => loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => {
+                            chars.next();
+                        }
+                        Some(_) => {}
+                        None => return Err(self.error_at(open, "missing closing quote"))
+                    }
+                },
+                _ => {}
+            }
+        }
+        Err(self.error_at(open, "missing closing parenthesis"))
+    }
+
+    /// Consumes `word` if it's the next word in the input.
+    fn keyword(&mut self, word: &str) -> bool {
+        let start = self.pos;
+        self.skip_whitespace();
+        match self.identifier() == word {
+            true => true,
+            false => {
+                self.pos = start;
+                false
+            }
+        }
+    }
+
+    /// Consumes a name made of letters, digits and underscores.
+    fn identifier(&mut self) -> &'a str {
+        let input = self.input;
+        let start = self.pos;
+        let rest = &input[start..];
+        let len = rest
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &input[start..self.pos]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn error<R: Into<String>>(&self, reason: R) -> ParsePatternError {
+        self.error_at(self.pos, reason)
+    }
+
+    fn error_at<R: Into<String>>(&self, pos: usize, reason: R) -> ParsePatternError {
+        ParsePatternError::at(self.input, pos, reason)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ext(ext: &str) -> FilePattern {
+        FilePattern::Extension(ext.into())
+    }
+
+    fn not(pattern: FilePattern) -> FilePattern {
+        FilePattern::Not(Box::new(pattern))
+    }
+
+    /// The reason `input` isn't a valid expression and where the problem is.
+    fn error(input: &str) -> (String, Option<usize>) {
+        let error = parse(input).unwrap_err();
+        (error.reason().to_string(), error.position())
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("ext(a) or ext(b) and ext(c)").unwrap(),
+            FilePattern::AnyOf(vec![ext("a"), FilePattern::All(vec![ext("b"), ext("c")])])
+        );
+        assert_eq!(
+            parse("ext(a) and ext(b) or ext(c)").unwrap(),
+            FilePattern::AnyOf(vec![FilePattern::All(vec![ext("a"), ext("b")]), ext("c")])
+        );
+        assert_eq!(
+            parse("(ext(a) or ext(b)) and ext(c)").unwrap(),
+            FilePattern::All(vec![FilePattern::AnyOf(vec![ext("a"), ext("b")]), ext("c")])
+        );
+        // a chain of the same operator is a single pattern
+        assert_eq!(parse("ext(a) or ext(b) or ext(c)").unwrap(), FilePattern::AnyOf(vec![ext("a"), ext("b"), ext("c")]));
+    }
+
+    #[test]
+    fn not_applies_to_the_next_pattern_only() {
+        assert_eq!(parse("not ext(a) and ext(b)").unwrap(), FilePattern::All(vec![not(ext("a")), ext("b")]));
+        assert_eq!(parse("not (ext(a) and ext(b))").unwrap(), not(FilePattern::All(vec![ext("a"), ext("b")])));
+        assert_eq!(parse("not not ext(a)").unwrap(), not(not(ext("a"))));
+        // the call form of not is a pattern like any other
+        assert_eq!(parse("not(ext(a)) and ext(b)").unwrap(), FilePattern::All(vec![not(ext("a")), ext("b")]));
+    }
+
+    #[test]
+    fn keywords_are_only_operators_between_patterns() {
+        assert_eq!(parse("contains(\"this or that\")").unwrap(), FilePattern::NameContains("this or that".into()));
+        assert_eq!(parse("contains(android)").unwrap(), FilePattern::NameContains("android".into()));
+        assert_eq!(parse("  any  ").unwrap(), FilePattern::Any);
+        assert_eq!(parse("dir and empty_dir").unwrap(), FilePattern::All(vec![FilePattern::IsDir, FilePattern::EmptyDir]));
+    }
+
+    #[test]
+    fn problems_are_reported_where_they_are() {
+        assert_eq!(error(""), ("missing pattern name".into(), Some(0)));
+        assert_eq!(error("ext(iso) and"), ("expected a pattern after the operator".into(), Some(12)));
+        assert_eq!(error("ext(iso) and and"), ("expected a pattern before \"and\"".into(), Some(13)));
+        assert_eq!(error("ext(iso) ext(img)"), ("expected \"and\" or \"or\"".into(), Some(9)));
+        assert_eq!(error("(ext(iso)"), ("missing closing parenthesis".into(), Some(9)));
+        assert_eq!(error("ext(iso))"), ("unmatched closing parenthesis".into(), Some(8)));
+        assert_eq!(error("ext(iso"), ("missing closing parenthesis".into(), Some(3)));
+        assert_eq!(error("contains(\"iso)"), ("missing closing quote".into(), Some(8)));
+        assert_eq!(error("any and nonsense(1)"), ("unknown pattern \"nonsense\"".into(), Some(8)));
+        assert_eq!(error("ext(iso) & ext(img)"), ("expected \"and\" or \"or\"".into(), Some(9)));
+    }
+}
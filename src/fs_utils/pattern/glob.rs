@@ -0,0 +1,162 @@
+//! Matching of names against shell-style wildcard patterns.
+//!
+//! The supported wildcards are `?` for any single character, `*` for any
+//! number of characters other than `/`, `**` for any number of characters
+//! including `/`, and `[...]` for any one of the characters in the brackets.
+//! A bracket expression can contain ranges like `[0-9]`, and it's negated if
+//! it starts with `!` or `^`. A `**/` matches zero or more whole directories,
+//! so `**/*.tmp` also matches `a.tmp`.
+
+
+/// Checks whether `text` matches the whole of `glob`.
+///
+/// Invalid bracket expressions never match anything, see [`check`].
+pub(crate) fn matches(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&glob, &text)
+}
+
+/// Checks that `glob` is a valid pattern, returning the reason if it isn't.
+pub(crate) fn check(glob: &str) -> Result<(), String> {
+    let glob: Vec<char> = glob.chars().collect();
+    let mut i = 0;
+    while i < glob.len() {
+        if glob[i] == '[' {
+            let (_, end) = bracket(&glob[i..], '\0')
+                .ok_or_else(|| "missing closing bracket".to_string())?;
+            i += end;
+        }
+        else {
+            i += 1;
+        }
+    }
+    Ok(())
+}
+
+
+fn match_from(glob: &[char], text: &[char]) -> bool {
+    match glob {
+        [] => text.is_empty(),
+
+        ['*', '*', '/', rest @ ..] => {
+            // zero directories, or one more directory before trying again
+            match_from(rest, text)
+                || text.iter().enumerate().any(|(i, c)| {
+                    *c == '/' && match_from(glob, &text[i + 1..])
+                })
+        }
+
+        ['*', '*', rest @ ..]
+        => (0..=text.len()).any(|i| match_from(rest, &text[i..])),
+
+        ['*', rest @ ..] => {
+            let limit = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+            (0..=limit).any(|i| match_from(rest, &text[i..]))
+        }
+
+        ['?', rest @ ..]
+        => matches!(text, [c, ..] if *c != '/') && match_from(rest, &text[1..]),
+
+        ['[', ..] => match text {
+            [c, text @ ..] => match bracket(glob, *c) {
+                Some((true, end)) => match_from(&glob[end..], text),
+                _ => false
+            },
+            [] => false
+        },
+
+        [g, rest @ ..] => match text {
+            [c, text @ ..] if c == g => match_from(rest, text),
+            _ => false
+        }
+    }
+}
+
+/// Checks whether `c` matches the bracket expression at the start of `glob`.
+///
+/// Returns whether it matched and the length of the expression, or `None`
+/// if the expression isn't closed.
+fn bracket(glob: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negated = matches!(glob.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        match glob.get(i)? {
+            // a closing bracket right at the start is an ordinary character
+            ']' if !first => return Some((matched != negated, i + 1)),
+            start => {
+                let (start, end) = match (glob.get(i + 1), glob.get(i + 2)) {
+                    (Some('-'), Some(end)) if *end != ']' => {
+                        i += 2;
+                        (*start, *end)
+                    }
+                    _ => (*start, *start)
+                };
+                matched |= (start..=end).contains(&c);
+            }
+        }
+        i += 1;
+        first = false;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stars_stay_within_a_directory_unless_doubled() {
+        assert!(matches("*.tmp", "a.tmp"));
+        assert!(!matches("*.tmp", "cache/a.tmp"));
+        assert!(matches("**.tmp", "cache/a.tmp"));
+        assert!(matches("**/*.tmp", "a.tmp"));
+        assert!(matches("**/*.tmp", "cache/old/a.tmp"));
+        assert!(matches("cache/**/a.tmp", "cache/a.tmp"));
+        assert!(matches("cache/**/a.tmp", "cache/x/y/a.tmp"));
+        assert!(!matches("cache/**/a.tmp", "other/x/a.tmp"));
+        assert!(!matches("?.tmp", "/.tmp"));
+        assert!(matches("screenshot_????.png", "screenshot_0001.png"));
+        assert!(!matches("screenshot_????.png", "screenshot_001.png"));
+    }
+
+    #[test]
+    fn brackets_match_one_of_their_characters() {
+        assert!(matches("IMG_[0-9][0-9].jpg", "IMG_42.jpg"));
+        assert!(!matches("IMG_[0-9][0-9].jpg", "IMG_4a.jpg"));
+        assert!(matches("[!.]*", "notes"));
+        assert!(!matches("[!.]*", ".hidden"));
+        assert!(!matches("[^.]*", ".hidden"));
+        assert!(matches("[a-cx]", "x"));
+        assert!(!matches("[a-cx]", "d"));
+        // a dash at the end is an ordinary character
+        assert!(matches("[a-]", "-"));
+    }
+
+    #[test]
+    fn wildcards_in_brackets_stand_for_themselves() {
+        assert!(matches("what[?]", "what?"));
+        assert!(!matches("what[?]", "whats"));
+        assert!(matches("[*]*", "*starred"));
+        assert!(!matches("[*]*", "starred"));
+        assert!(matches("[[]draft]*", "[draft] notes"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[!]]", "a"));
+        assert!(!matches("[!]]", "]"));
+    }
+
+    #[test]
+    fn brackets_have_to_be_closed() {
+        assert_eq!(check("IMG_[0-9].jpg"), Ok(()));
+        assert_eq!(check("[]]"), Ok(()));
+        assert!(check("IMG_[0-9.jpg").is_err());
+        assert!(check("[]").is_err());
+        assert!(!matches("IMG_[0-9.jpg", "IMG_1.jpg"));
+    }
+}
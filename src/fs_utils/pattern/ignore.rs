@@ -0,0 +1,174 @@
+//! Reading `.cleanerignore` files, which let whoever owns a directory
+//! protect entries in it from being removed by routines.
+//!
+//! The files are written like `.gitignore` files. Each line is a wildcard
+//! pattern as in [`FilePattern::Glob`](super::FilePattern::Glob), and:
+//!
+//! - blank lines and lines starting with `#` are ignored
+//! - a pattern starting with `!` protects nothing, and instead makes an
+//!   entry that an earlier line protected unprotected again
+//! - a pattern ending with `/` only applies to directories
+//! - a pattern containing a `/` anywhere else is matched against the path
+//!   relative to the directory the file is in, while other patterns are
+//!   matched against the name of an entry at any depth
+//!
+//! The last line that applies to an entry decides whether it's protected.
+
+use std::fs;
+use std::path::Path;
+
+use super::glob;
+
+
+/// The name of the files that protect entries from being removed.
+pub const IGNORE_FILE_NAME: &str = ".cleanerignore";
+
+
+/// The rules of a single ignore file.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IgnoreFile {
+    rules: Vec<Rule>
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    glob: String,
+    negated: bool,
+    dirs_only: bool,
+    anchored: bool
+}
+
+impl IgnoreFile {
+    /// Reads the ignore file in `directory`, if there is one.
+    pub(crate) fn read(directory: &Path) -> Option<Self> {
+        fs::read_to_string(directory.join(IGNORE_FILE_NAME))
+            .ok()
+            .map(|text| Self::parse(&text))
+    }
+
+    /// Parses the contents of an ignore file.
+    pub(crate) fn parse(text: &str) -> Self {
+        let rules = text
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (negated, line) = match line.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, line)
+                };
+                let (dirs_only, line) = match line.strip_suffix('/') {
+                    Some(rest) => (true, rest),
+                    None => (false, line)
+                };
+                let anchored = line.contains('/');
+                let glob = line.strip_prefix('/').unwrap_or(line).to_string();
+                Rule { glob, negated, dirs_only, anchored }
+            })
+            .collect();
+        IgnoreFile { rules }
+    }
+
+    /// Decides whether an entry is protected, given its path relative to the
+    /// directory of the file with `/` separators.
+    ///
+    /// Returns `None` if no rule applies to the entry. `fold` prepares text
+    /// for comparison, such as by lowercasing it.
+    pub(crate) fn decide(
+        &self,
+        relative: &str,
+        is_dir: bool,
+        fold: impl Fn(&str) -> String
+    ) -> Option<bool> {
+        let relative = fold(relative);
+        let name = relative.rsplit('/').next().unwrap_or(&relative);
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                let glob = fold(&rule.glob);
+                (is_dir || !rule.dirs_only)
+                    && match rule.anchored {
+                        true => glob::matches(&glob, &relative),
+                        false => glob::matches(&glob, name)
+                    }
+            })
+            .map(|rule| !rule.negated)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_utils::{test_dir, MatchContext, MatchOptions};
+
+    fn decide(file: &IgnoreFile, relative: &str, is_dir: bool) -> Option<bool> {
+        file.decide(relative, is_dir, str::to_string)
+    }
+
+    #[test]
+    fn the_last_rule_that_applies_decides() {
+        let file = IgnoreFile::parse("# keep the reports\n\n*.pdf\n!draft-*.pdf\ndraft-final.pdf\n");
+        assert_eq!(decide(&file, "report.pdf", false), Some(true));
+        assert_eq!(decide(&file, "draft-1.pdf", false), Some(false));
+        assert_eq!(decide(&file, "draft-final.pdf", false), Some(true));
+        assert_eq!(decide(&file, "notes.txt", false), None);
+        assert_eq!(decide(&file, "# keep the reports", false), None);
+    }
+
+    #[test]
+    fn names_match_at_any_depth_and_paths_from_the_file() {
+        let file = IgnoreFile::parse("*.pdf\n/top.txt\nold/*.log\n");
+        assert_eq!(decide(&file, "a/b/report.pdf", false), Some(true));
+        assert_eq!(decide(&file, "top.txt", false), Some(true));
+        assert_eq!(decide(&file, "a/top.txt", false), None);
+        assert_eq!(decide(&file, "old/1.log", false), Some(true));
+        assert_eq!(decide(&file, "a/old/1.log", false), None);
+    }
+
+    #[test]
+    fn trailing_slashes_only_apply_to_directories() {
+        let file = IgnoreFile::parse("keep/\n");
+        assert_eq!(decide(&file, "keep", true), Some(true));
+        assert_eq!(decide(&file, "a/keep", true), Some(true));
+        assert_eq!(decide(&file, "keep", false), None);
+    }
+
+    #[test]
+    fn deeper_files_take_precedence() {
+        let directory = test_dir("cleanerignore");
+        fs::create_dir_all(directory.join("a/b")).unwrap();
+        fs::write(directory.join(IGNORE_FILE_NAME), "*.pdf\n").unwrap();
+        fs::write(directory.join("a").join(IGNORE_FILE_NAME), "!*.pdf\nb/keep.txt\n").unwrap();
+
+        let options = MatchOptions::default();
+        let top = MatchContext::new(&directory, options);
+        assert!(top.is_ignored(&directory.join("report.pdf")));
+        assert!(top.is_ignored(&directory.join(IGNORE_FILE_NAME)));
+        assert!(!top.is_ignored(&directory.join("notes.txt")));
+
+        let nested = MatchContext::new(directory.join("a/b"), options).with_root(&directory);
+        assert!(!nested.is_ignored(&directory.join("a/b/report.pdf")));
+        assert!(nested.is_ignored(&directory.join("a/b/keep.txt")));
+
+        // files above the root don't apply
+        let below = MatchContext::new(directory.join("a/b"), options).with_root(directory.join("a/b"));
+        assert!(!below.is_ignored(&directory.join("a/b/keep.txt")));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn case_follows_the_options() {
+        let directory = test_dir("cleanerignore-case");
+        fs::write(directory.join(IGNORE_FILE_NAME), "*.PDF\n").unwrap();
+
+        let insensitive = MatchContext::new(&directory, MatchOptions { case_sensitive: false });
+        assert!(insensitive.is_ignored(&directory.join("report.pdf")));
+        let sensitive = MatchContext::new(&directory, MatchOptions { case_sensitive: true });
+        assert!(!sensitive.is_ignored(&directory.join("report.pdf")));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
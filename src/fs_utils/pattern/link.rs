@@ -0,0 +1,32 @@
+//! Recognizing symbolic links and other entries that redirect elsewhere.
+//!
+//! Links are always inspected by themselves, so matching never follows them
+//! into their targets.
+
+use std::path::Path;
+
+
+/// Checks whether the entry at `path` is a symbolic link, or on Windows any
+/// reparse point, which includes junctions.
+#[cfg(windows)]
+pub(crate) fn is_link(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+
+    path.symlink_metadata().is_ok_and(|m| {
+        m.file_type().is_symlink() || m.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0
+    })
+}
+
+/// Checks whether the entry at `path` is a symbolic link.
+#[cfg(not(windows))]
+pub(crate) fn is_link(path: &Path) -> bool {
+    path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink())
+}
+
+/// Checks whether the entry at `path` is a [link](is_link) whose target
+/// doesn't exist or can't be reached.
+pub(crate) fn is_broken_link(path: &Path) -> bool {
+    is_link(path) && path.metadata().is_err()
+}
@@ -0,0 +1,46 @@
+//! Detecting files that other programs are using.
+
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+
+/// Checks whether another program has the file at `path` open, which on
+/// Windows is detected by trying to open it exclusively.
+#[cfg(windows)]
+pub(crate) fn is_in_use(path: &Path) -> bool {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    if !path.symlink_metadata().is_ok_and(|m| m.is_file()) {
+        return false;
+    }
+    match OpenOptions::new().read(true).share_mode(0).open(path) {
+        Ok(file) => is_locked(&file),
+        Err(e) => matches!(
+            e.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+        )
+    }
+}
+
+/// Checks whether another program has locked the file at `path`.
+///
+/// Only locks taken with `flock` or similar are noticed, since other
+/// platforms don't prevent files that are open from being removed.
+#[cfg(not(windows))]
+pub(crate) fn is_in_use(path: &Path) -> bool {
+    if !path.symlink_metadata().is_ok_and(|m| m.is_file()) {
+        return false;
+    }
+    OpenOptions::new()
+        .read(true)
+        .open(path)
+        .is_ok_and(|file| is_locked(&file))
+}
+
+fn is_locked(file: &File) -> bool {
+    // the lock is dropped along with the file
+    file.try_lock().is_err()
+}
@@ -0,0 +1,574 @@
+use std::borrow::Cow;
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+mod attribute;
+mod builder;
+mod content;
+mod context;
+mod custom;
+mod duplicate;
+mod explain;
+mod expr;
+mod glob;
+pub mod ignore;
+mod link;
+mod lock;
+mod name_date;
+mod owner;
+mod preset;
+mod regex_pattern;
+mod repr;
+mod retention;
+mod signature;
+mod zone;
+
+pub use attribute::FileAttribute;
+pub use builder::PatternBuilder;
+pub use content::DEFAULT_CONTENT_MAX_SIZE;
+pub use context::MatchContext;
+pub use custom::CustomPattern;
+pub use explain::MatchExplanation;
+pub use regex_pattern::RegexPattern;
+pub use repr::ParsePatternError;
+
+
+/// Patterns for selecting files and directories based on certain criteria.
+///
+/// In configuration files, patterns are written either as short strings
+/// like `"ext(lnk)"` or as tables like `{ type = "extension", value = "lnk" }`.
+/// Short strings can also be [parsed](std::str::FromStr) directly.
+///
+/// # Expressions
+///
+/// Patterns in their short form can be combined with the operators `and`,
+/// `or` and `not`, as in `ext(iso, zip) and older(7d) and not name(keep)`.
+/// `not` binds tighter than `and`, which binds tighter than `or`, and
+/// parentheses can be used for grouping, as in
+/// `(ext(iso) or ext(zip)) and older(7d)`. Expressions are parsed into
+/// [`All`](Self::All), [`AnyOf`](Self::AnyOf) and [`Not`](Self::Not)
+/// patterns, and they're accepted anywhere a short form pattern is.
+///
+/// ```
+/// use folder_cleaner::fs_utils::FilePattern;
+///
+/// let pattern: FilePattern = "ext(lnk) or not file".parse().unwrap();
+/// assert_eq!(pattern, FilePattern::AnyOf(vec![
+///     FilePattern::Extension("lnk".into()),
+///     FilePattern::Not(Box::new(FilePattern::IsFile))
+/// ]));
+/// ```
+///
+/// # Examples
+///
+/// Patterns are serialized in their short form, and both forms deserialize
+/// into the same pattern.
+///
+/// ```
+/// use folder_cleaner::fs_utils::FilePattern;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, PartialEq, Debug)]
+/// struct Routine {
+///     pattern: FilePattern
+/// }
+///
+/// let routine = Routine { pattern: FilePattern::Extension("lnk".into()) };
+/// let text = toml::to_string(&routine).unwrap();
+/// assert_eq!(text.trim(), r#"pattern = "ext(lnk)""#);
+/// assert_eq!(toml::from_str::<Routine>(&text).unwrap(), routine);
+///
+/// let table = r#"pattern = { type = "extension", value = "lnk" }"#;
+/// assert_eq!(toml::from_str::<Routine>(table).unwrap(), routine);
+/// ```
+#[non_exhaustive]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FilePattern {
+    /// Matches everything. Written as `"any"`.
+    Any,
+    /// Matches entries with the given extension, not including the leading
+    /// dot. Written as `"ext(lnk)"`.
+    ///
+    /// This and the other patterns that compare names and extensions
+    /// respect [`MatchOptions::case_sensitive`].
+    Extension(String),
+    /// Matches entries with any of the given extensions. Written as
+    /// `"exts(tmp, log, bak)"`, or as `"ext(tmp, log, bak)"`.
+    Extensions(Vec<String>),
+    /// Matches entries that have none of the given extensions, including
+    /// entries without an extension and directories. Written as
+    /// `"not_ext(pdf, docx)"`.
+    ///
+    /// This is the same as negating [`Extensions`](Self::Extensions), and
+    /// it's useful for clearing a directory of everything but a few kinds of
+    /// files.
+    NotExtension(Vec<String>),
+    /// Matches entries whose name matches a wildcard pattern, such as
+    /// `*.log` or `screenshot_????.png`. Written as `"glob(*.log)"`.
+    ///
+    /// `?` matches any single character, `*` any number of characters and
+    /// `[abc]` any one of the characters in the brackets.
+    Glob(String),
+    /// Matches entries whose path relative to the
+    /// [root](MatchContext::root()) of the directory being cleaned matches a
+    /// wildcard pattern, such as `cache/**/*.js`. Written as
+    /// `"path(cache/**/*.js)"`.
+    ///
+    /// The components of the path are separated by `/` on every platform.
+    /// `**` matches any number of directories, while `*` stays within one.
+    RelativePath(String),
+    /// Matches entries whose name starts with the given text. Written as
+    /// `"starts_with(~$)"`.
+    NameStartsWith(String),
+    /// Matches entries whose name ends with the given text. Written as
+    /// `"ends_with(_old)"`.
+    NameEndsWith(String),
+    /// Matches entries whose name contains the given text. Written as
+    /// `"contains(\"(copy)\")"`.
+    NameContains(String),
+    /// Matches entries whose name matches a regular expression, such as
+    /// `^backup-\d{8}\.zip$`. Written as `"regex(^backup-\d{8}\.zip$)"`.
+    ///
+    /// Backslashes are removed from quoted arguments, so expressions that
+    /// need quotes are easier to write in the table form, as in
+    /// `{ type = "regex", value = '^(a|b)\.txt$' }`.
+    Regex(RegexPattern),
+    /// Matches entries whose [timestamp](Timestamp) is older than the given
+    /// duration. Written as `"older(30d)"` to use the modification time, or
+    /// as `"older(30d, created)"` to use another timestamp. The duration is
+    /// written as in [`config::duration`](crate::config::duration).
+    ///
+    /// Entries that haven't been opened in a while are written as
+    /// `"not_accessed(1y)"`, which is the same as `"older(1y, accessed)"`.
+    /// See [`Timestamp::Accessed`] for when that's reliable.
+    ///
+    /// Entries whose timestamp can't be read never match.
+    OlderThan(Duration, Timestamp),
+    /// Matches entries whose name contains a date older than the given
+    /// duration, read using a format such as `report-%Y-%m-%d.csv`. Written
+    /// as `"name_date(report-%Y-%m-%d.csv, 30d)"`.
+    ///
+    /// This is useful when timestamps can't be trusted, such as after files
+    /// have been copied. The format can contain `%Y` for a four-digit year,
+    /// `%y` for a two-digit year in the 2000s, `%m`, `%d`, `%H`, `%M` and
+    /// `%S` for the month, day, hour, minute and second, which take two
+    /// digits each, and `%%` for a literal `%`. It has to contain a year,
+    /// and `*` and `?` are wildcards as in [`Glob`](Self::Glob).
+    ///
+    /// Dates are taken to be in UTC. Entries whose name doesn't have the
+    /// format or contains a date that doesn't exist never match.
+    NameDate(String, Duration),
+    /// Matches files larger than the given number of bytes. Written as
+    /// `"larger(500MB)"`, with the size written as in
+    /// [`config::size`](crate::config::size). Directories never match.
+    LargerThan(u64),
+    /// Matches files smaller than the given number of bytes. Written as
+    /// `"smaller(1KB)"`. Directories never match.
+    SmallerThan(u64),
+    /// Matches files. Written as `"file"`.
+    ///
+    /// Symbolic links are neither files nor directories, whatever they point
+    /// to.
+    IsFile,
+    /// Matches directories. Written as `"dir"`.
+    IsDir,
+    /// Matches directories that contain no entries at all. Written as
+    /// `"empty_dir"`.
+    EmptyDir,
+    /// Matches files that are zero bytes long. Written as `"empty_file"`.
+    EmptyFile,
+    /// Matches files whose contents look like the given MIME type, such as
+    /// `video/mp4`. Written as `"mime(video/mp4)"`.
+    ///
+    /// The type is detected from the first bytes of the file rather than
+    /// its extension, so renamed files are matched too. The type can
+    /// contain wildcards like in [`Glob`](Self::Glob), as in `video/*`,
+    /// and a type without a subtype, such as `video`, matches every subtype.
+    /// Files whose type can't be detected never match.
+    ContentType(String),
+    /// Matches text files that contain the given text and are at most the
+    /// given number of bytes long. Written as `"content(SESSION CLOSED)"`
+    /// to use [`DEFAULT_CONTENT_MAX_SIZE`], or as `"content(SESSION CLOSED, 1MB)"`.
+    ///
+    /// Files larger than the limit never match, so that a routine doesn't
+    /// read huge files every time it runs. The text is matched exactly,
+    /// whatever [`MatchOptions::case_sensitive`] is.
+    Content(String, u64),
+    /// Matches text files whose contents match a regular expression and are
+    /// at most the given number of bytes long. Written as
+    /// `"content_regex((?m)^ERROR, 1MB)"`, with the limit being optional
+    /// like in [`Content`](Self::Content).
+    ///
+    /// `^` and `$` match at the start and end of the whole file, unless the
+    /// expression starts with `(?m)` to make them match at every line.
+    ContentRegex(RegexPattern, u64),
+    /// Matches files that start with the given bytes, which are written in
+    /// hexadecimal, as in `"magic(4D5A)"` for Windows executables or
+    /// `"magic(504B)"` for zip archives and the formats based on them.
+    ///
+    /// Unlike [`ContentType`](Self::ContentType), this only compares the
+    /// bytes, so it works for any format with a known signature. Spaces
+    /// between the bytes are allowed.
+    Signature(Vec<u8>),
+    /// Matches entries that have the given [attribute](FileAttribute).
+    /// Written as `"attr(hidden)"`, or as `"read_only"` for
+    /// [`FileAttribute::ReadOnly`].
+    Attribute(FileAttribute),
+    /// Matches files that another program is using, such as downloads that
+    /// are still in progress and open documents. Written as `"in_use"`.
+    ///
+    /// On Windows, a file is in use if it can't be opened exclusively.
+    /// Elsewhere, open files can be removed just fine, so only files that are
+    /// locked with `flock` or similar are in use. Negating this, as in
+    /// `ext(pdf) and not in_use`, makes a routine leave active files alone.
+    InUse,
+    /// Matches files that Windows has marked as downloaded from the
+    /// internet, such as installers and email attachments. Written as
+    /// `"downloaded"`.
+    ///
+    /// The mark is a `Zone.Identifier` alternate data stream, so this never
+    /// matches on other platforms.
+    Downloaded,
+    /// Matches entries owned by the given user. Written as `"owner(1000)"`.
+    ///
+    /// On Windows, the user is identified by their security identifier, such
+    /// as `S-1-5-21-...-1001`, and elsewhere by their numeric user ID.
+    Owner(String),
+    /// Matches files that have the same contents as another file in the
+    /// same directory, apart from the one that was modified first. Written
+    /// as `"duplicate"`.
+    ///
+    /// Removing every file this matches leaves exactly one copy of each
+    /// file. Empty files aren't considered copies of each other.
+    Duplicate,
+    /// Matches symbolic links, as well as junctions and other reparse points
+    /// on Windows. Written as `"link"`.
+    ///
+    /// Links are judged by themselves, so removing one never touches what
+    /// it points to.
+    Link,
+    /// Matches [links](Self::Link) whose target no longer exists. Written as
+    /// `"broken_link"`.
+    BrokenLink,
+    /// Matches entries using logic supplied by the application, see
+    /// [`CustomPattern`]. Written as `"custom"`, but it can't be parsed or
+    /// deserialized, and serializing it fails.
+    Custom(CustomPattern),
+    /// Matches entries that match the given pattern, apart from the given
+    /// number of most recently modified ones. Written as
+    /// `"keep_newest(5, ext(zip))"`.
+    ///
+    /// This is useful for rotating backups and logs, as removing every
+    /// entry this matches leaves only the newest ones.
+    KeepNewest(usize, Box<FilePattern>),
+    /// Matches the files programs commonly leave behind temporarily and the
+    /// folder metadata operating systems create, such as `~$report.docx`,
+    /// `*.tmp`, `*.crdownload`, `*.part`, `.DS_Store` and `Thumbs.db`.
+    /// Written as `"temp_artifacts"`.
+    ///
+    /// The names are matched whatever their case.
+    TempArtifacts,
+    /// Matches image files by their extension, such as `jpg`, `png` and
+    /// `heic`. Written as `"images"`.
+    ///
+    /// This and the other category presets match extensions whatever their
+    /// case, and they're meant to be combined with other patterns, as in
+    /// `videos and older(90d)`.
+    Images,
+    /// Matches video files by their extension, such as `mp4`, `mkv` and
+    /// `mov`. Written as `"videos"`.
+    Videos,
+    /// Matches audio files by their extension, such as `mp3`, `flac` and
+    /// `wav`. Written as `"audio"`.
+    Audio,
+    /// Matches documents, spreadsheets and presentations by their
+    /// extension, such as `pdf`, `docx` and `xlsx`. Written as
+    /// `"documents"`.
+    Documents,
+    /// Matches archives and disk images by their extension, such as `zip`,
+    /// `7z` and `iso`. Written as `"archives"`.
+    Archives,
+    /// Matches entries that match every one of the given patterns. Written
+    /// as `"all(ext(iso), older(7d))"`. An empty list matches everything.
+    All(Vec<FilePattern>),
+    /// Matches entries that match at least one of the given patterns.
+    /// Written as `"any_of(ext(iso), ext(zip))"`. An empty list matches
+    /// nothing.
+    AnyOf(Vec<FilePattern>),
+    /// Matches entries that don't match the given pattern. Written as
+    /// `"not(ext(pdf))"`.
+    Not(Box<FilePattern>)
+}
+
+
+/// The timestamps of an entry that its age can be measured from.
+///
+/// Which one makes sense depends on the directory: a downloaded file is as
+/// old as its creation time, while a log file keeps getting modified for as
+/// long as it's in use. Not every platform or file system records every
+/// timestamp.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Timestamp {
+    /// When the entry was last modified.
+    #[default]
+    Modified,
+    /// When the entry was created.
+    Created,
+    /// When the entry was last accessed.
+    ///
+    /// Many systems only update this lazily or not at all to save disk
+    /// writes, such as Linux with the `noatime` mount option and Windows
+    /// when last access updates are disabled, in which case entries look
+    /// like they haven't been accessed since they were created or modified.
+    Accessed
+}
+
+impl Timestamp {
+    /// The name of the timestamp in patterns.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Modified => "modified",
+            Self::Created => "created",
+            Self::Accessed => "accessed"
+        }
+    }
+
+    /// The timestamp with the given [name](Self::name()).
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Modified, Self::Created, Self::Accessed]
+            .into_iter()
+            .find(|timestamp| timestamp.name() == name)
+    }
+
+    /// Reads this timestamp from the metadata of an entry.
+    fn read(self, metadata: &Metadata) -> io::Result<SystemTime> {
+        match self {
+            Self::Modified => metadata.modified(),
+            Self::Created => metadata.created(),
+            Self::Accessed => metadata.accessed()
+        }
+    }
+}
+
+/// Options that affect how patterns are matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchOptions {
+    /// Whether names and extensions have to match in case, so that
+    /// `ext(lnk)` doesn't match `SHORTCUT.LNK`.
+    ///
+    /// Patterns are case-insensitive by default on Windows, where file names
+    /// are too, and case-sensitive elsewhere. Regular expressions can be
+    /// made case-insensitive with `(?i)` instead.
+    pub case_sensitive: bool
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions { case_sensitive: !cfg!(windows) }
+    }
+}
+
+
+impl FilePattern {
+    /// Checks whether the entry at `path` matches the pattern, using the
+    /// default [options](MatchOptions).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use folder_cleaner::fs_utils::FilePattern;
+    /// use std::path::Path;
+    ///
+    /// let pattern = FilePattern::Glob("screenshot_????.png".into());
+    /// assert!(pattern.matches(Path::new("screenshot_0001.png")));
+    /// assert!(!pattern.matches(Path::new("screenshot_1.png")));
+    /// ```
+    pub fn matches(&self, path: &Path) -> bool {
+        self.matches_with(path, MatchOptions::default())
+    }
+
+    /// Checks whether the entry at `path` matches the pattern.
+    ///
+    /// Matching many entries of the same directory is faster with
+    /// [`matches_in`](Self::matches_in()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use folder_cleaner::fs_utils::{FilePattern, MatchOptions};
+    /// use std::path::Path;
+    ///
+    /// let pattern = FilePattern::Extension("lnk".into());
+    /// let options = MatchOptions { case_sensitive: false };
+    /// assert!(pattern.matches_with(Path::new("SHORTCUT.LNK"), options));
+    /// ```
+    pub fn matches_with(&self, path: &Path, options: MatchOptions) -> bool {
+        let directory = path.parent().unwrap_or(Path::new(""));
+        self.matches_in(path, &MatchContext::new(directory, options))
+    }
+
+    /// Works out why the entry at `path` does or doesn't match the pattern,
+    /// using the default [options](MatchOptions). See [`MatchExplanation`].
+    pub fn explain(&self, path: &Path) -> MatchExplanation<'_> {
+        let directory = path.parent().unwrap_or(Path::new(""));
+        self.explain_in(path, &MatchContext::new(directory, MatchOptions::default()))
+    }
+
+    /// Works out why the entry at `path`, which is in the
+    /// [directory](MatchContext::directory()) of `context`, does or doesn't
+    /// match the pattern. See [`MatchExplanation`].
+    pub fn explain_in(&self, path: &Path, context: &MatchContext) -> MatchExplanation<'_> {
+        MatchExplanation::new(self, path, context)
+    }
+
+    /// Checks whether the entry at `path`, which is in the
+    /// [directory](MatchContext::directory()) of `context`, matches the
+    /// pattern.
+    pub fn matches_in(&self, path: &Path, context: &MatchContext) -> bool {
+        let options = context.options();
+        let name = || fold(&file_name(path), options).into_owned();
+        let text = |text: &str| fold(text, options).into_owned();
+
+        match self {
+            Self::Any => true,
+            Self::Extension(ext) => has_extension(path, ext, options),
+            Self::Extensions(exts) => exts.iter().any(|ext| has_extension(path, ext, options)),
+            Self::NotExtension(exts) => !exts.iter().any(|ext| has_extension(path, ext, options)),
+            Self::Glob(glob) => glob::matches(&text(glob), &name()),
+            Self::RelativePath(glob) => context
+                .relative_path(path)
+                .is_some_and(|relative| glob::matches(&text(glob), &text(&relative))),
+            Self::NameStartsWith(prefix) => name().starts_with(&text(prefix)),
+            Self::NameEndsWith(suffix) => name().ends_with(&text(suffix)),
+            Self::NameContains(part) => name().contains(&text(part)),
+            Self::Regex(regex) => regex.is_match(&file_name(path)),
+            Self::OlderThan(age, timestamp) => is_older_than(path, *age, *timestamp),
+            Self::NameDate(format, age) => name_date::is_older_than(
+                format,
+                &file_name(path),
+                *age,
+                options.case_sensitive
+            ),
+            Self::LargerThan(size) => file_size(path).is_some_and(|len| len > *size),
+            Self::SmallerThan(size) => file_size(path).is_some_and(|len| len < *size),
+            Self::IsFile => path.symlink_metadata().is_ok_and(|m| m.is_file()),
+            Self::IsDir => path.symlink_metadata().is_ok_and(|m| m.is_dir()),
+            Self::EmptyDir => is_empty_dir(path),
+            Self::EmptyFile => file_size(path) == Some(0),
+            Self::ContentType(mime) => has_content_type(path, mime),
+            Self::Content(text, max_size) => content::read_text(path, *max_size)
+                .is_some_and(|contents| contents.contains(text.as_str())),
+            Self::ContentRegex(regex, max_size) => content::read_text(path, *max_size)
+                .is_some_and(|contents| regex.is_match(&contents)),
+            Self::Signature(bytes) => signature::starts_with(path, bytes),
+            Self::Attribute(attribute) => attribute.is_set(path),
+            Self::InUse => lock::is_in_use(path),
+            Self::Downloaded => zone::is_downloaded(path),
+            Self::Owner(owner) => owner::is_owned_by(path, owner),
+            Self::Duplicate => context.is_duplicate(path),
+            Self::Link => link::is_link(path),
+            Self::BrokenLink => link::is_broken_link(path),
+            Self::Custom(custom) => custom.is_match(path),
+            Self::TempArtifacts => preset::matches_any(&preset::TEMP_ARTIFACTS, path),
+            Self::Images => preset::has_any_extension(&preset::IMAGES, path),
+            Self::Videos => preset::has_any_extension(&preset::VIDEOS, path),
+            Self::Audio => preset::has_any_extension(&preset::AUDIO, path),
+            Self::Documents => preset::has_any_extension(&preset::DOCUMENTS, path),
+            Self::Archives => preset::has_any_extension(&preset::ARCHIVES, path),
+            Self::KeepNewest(count, pattern)
+            => pattern.matches_in(path, context) && !context.is_kept(path, *count, pattern),
+
+            Self::All(patterns) => patterns.iter().all(|p| p.matches_in(path, context)),
+            Self::AnyOf(patterns) => patterns.iter().any(|p| p.matches_in(path, context)),
+            Self::Not(pattern) => !pattern.matches_in(path, context)
+        }
+    }
+}
+
+fn has_extension(path: &Path, ext: &str, options: MatchOptions) -> bool {
+    let actual = path.extension().unwrap_or_default().to_string_lossy();
+    fold(&actual, options) == fold(ext, options)
+}
+
+fn is_older_than(path: &Path, age: Duration, timestamp: Timestamp) -> bool {
+    // links are judged by themselves rather than by what they point to,
+    // since the link is what gets removed
+    path.symlink_metadata()
+        .and_then(|m| timestamp.read(&m))
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|elapsed| elapsed > age)
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    // a link to an empty directory isn't an empty directory itself
+    path.symlink_metadata().is_ok_and(|m| m.is_dir())
+        && path.read_dir().is_ok_and(|mut entries| entries.next().is_none())
+}
+
+fn has_content_type(path: &Path, mime: &str) -> bool {
+    if file_size(path).is_none() {
+        return false;
+    }
+    let Ok(Some(detected)) = infer::get_from_path(path) else {
+        return false;
+    };
+
+    match mime.contains('/') {
+        true => glob::matches(mime, detected.mime_type()),
+        false => detected.mime_type().split('/').next() == Some(mime)
+    }
+}
+
+/// The size of the file at `path`, or `None` if it isn't a file.
+fn file_size(path: &Path) -> Option<u64> {
+    path.symlink_metadata()
+        .ok()
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+}
+
+fn file_name(path: &Path) -> Cow<'_, str> {
+    path.file_name().unwrap_or_default().to_string_lossy()
+}
+
+/// Lowercases text if case doesn't matter, so that it can be compared.
+fn fold(text: &str, options: MatchOptions) -> Cow<'_, str> {
+    match options.case_sensitive {
+        true => Cow::Borrowed(text),
+        false => Cow::Owned(text.to_lowercase())
+    }
+}
+
+/// Checks whether a wildcard pattern is valid, returning the reason if it
+/// isn't.
+pub(crate) fn check_glob(glob: &str) -> Result<(), String> {
+    glob::check(glob)
+}
+
+/// Checks that a date format is valid, returning the reason if it isn't.
+pub(crate) fn check_date_format(format: &str) -> Result<(), String> {
+    name_date::check(format)
+}
+
+/// Writes `date` the way a date `layout` says, returning the reason if the
+/// layout isn't valid.
+pub(crate) fn write_date(layout: &str, date: OffsetDateTime) -> Result<String, String> {
+    name_date::write(layout, date)
+}
+
+/// Checks whether two files have the same contents, byte by byte.
+pub(crate) fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    duplicate::same_contents(a, b)
+}
+
+/// Checks whether an owner is written the way owners are on this platform,
+/// returning the reason if it isn't.
+pub(crate) fn check_owner(owner: &str) -> Result<(), String> {
+    owner::check(owner)
+}
@@ -0,0 +1,262 @@
+//! Reading dates out of the names of entries.
+//!
+//! A date format is a name in which some parts are replaced by
+//! placeholders, such as `report-%Y-%m-%d.csv`. The placeholders are:
+//!
+//! - `%Y` for a four-digit year and `%y` for a two-digit year in the 2000s
+//! - `%m` for the month and `%d` for the day of the month
+//! - `%H`, `%M` and `%S` for the hour, minute and second
+//! - `%%` for a literal `%`
+//!
+//! Every placeholder except the year takes exactly two digits. As in
+//! [`FilePattern::Glob`](super::FilePattern::Glob), `*` matches any number
+//! of characters and `?` any single character. A format has to contain a
+//! year, and parts of the date it doesn't contain are taken to be at the
+//! start of the year, month or day.
+//!
+//! The same placeholders are used to write dates into paths, as in the
+//! layouts of [`move_by_date`](crate::fs_utils::move_by_date), which can't
+//! contain wildcards but don't need a year.
+
+use std::time::{Duration, SystemTime};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    AnyChars,
+    AnyChar,
+    Field(Field)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Year,
+    ShortYear,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second
+}
+
+impl Field {
+    fn width(self) -> usize {
+        match self {
+            Self::Year => 4,
+            _ => 2
+        }
+    }
+}
+
+/// The parts of a date read out of a name.
+#[derive(Debug, Clone, Copy)]
+struct Parts {
+    year: i32,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8
+}
+
+
+/// Checks that `format` is a valid date format, returning the reason if it
+/// isn't.
+pub(crate) fn check(format: &str) -> Result<(), String> {
+    parse_format(format).map(|_| ())
+}
+
+/// Checks whether `name` has the given format and a date older than `age`.
+///
+/// Names that don't have the format, or whose date doesn't exist, such as
+/// February 30th, never match, and neither does anything if the format is
+/// invalid. Dates are taken to be in UTC.
+pub(crate) fn is_older_than(format: &str, name: &str, age: Duration, case_sensitive: bool) -> bool {
+    let Ok(tokens) = parse_format(format) else {
+        return false;
+    };
+    let name: Vec<char> = name.chars().collect();
+    let parts = Parts { year: 0, month: 1, day: 1, hour: 0, minute: 0, second: 0 };
+
+    read(&tokens, &name, parts, case_sensitive)
+        .and_then(to_time)
+        .and_then(|date| SystemTime::now().duration_since(date).ok())
+        .is_some_and(|elapsed| elapsed > age)
+}
+
+
+/// Writes `date` the way `layout` says, returning the reason if the layout
+/// isn't valid.
+pub(crate) fn write(layout: &str, date: OffsetDateTime) -> Result<String, String> {
+    let mut text = String::new();
+    for token in tokenize(layout)? {
+        match token {
+            Token::Literal(c) => text.push(c),
+            Token::AnyChars | Token::AnyChar
+            => return Err(format!("\"{layout}\" contains a wildcard (* or ?)")),
+            Token::Field(field) => {
+                let value = match field {
+                    Field::Year => date.year(),
+                    Field::ShortYear => date.year() % 100,
+                    Field::Month => u8::from(date.month()).into(),
+                    Field::Day => date.day().into(),
+                    Field::Hour => date.hour().into(),
+                    Field::Minute => date.minute().into(),
+                    Field::Second => date.second().into()
+                };
+                text.push_str(&format!("{value:0width$}", width = field.width()));
+            }
+        }
+    }
+    Ok(text)
+}
+
+
+fn parse_format(format: &str) -> Result<Vec<Token>, String> {
+    let tokens = tokenize(format)?;
+    let has_year = tokens
+        .iter()
+        .any(|t| matches!(t, Token::Field(Field::Year | Field::ShortYear)));
+    match has_year {
+        true => Ok(tokens),
+        false => Err(format!("\"{format}\" doesn't contain a year (%Y or %y)"))
+    }
+}
+
+fn tokenize(format: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '*' => Token::AnyChars,
+            '?' => Token::AnyChar,
+            '%' => match chars.next() {
+                Some('Y') => Token::Field(Field::Year),
+                Some('y') => Token::Field(Field::ShortYear),
+                Some('m') => Token::Field(Field::Month),
+                Some('d') => Token::Field(Field::Day),
+                Some('H') => Token::Field(Field::Hour),
+                Some('M') => Token::Field(Field::Minute),
+                Some('S') => Token::Field(Field::Second),
+                Some('%') => Token::Literal('%'),
+                Some(other) => return Err(format!("unknown placeholder \"%{other}\"")),
+                None => return Err("a \"%\" at the end must be written as \"%%\"".into())
+            },
+            c => Token::Literal(c)
+        });
+    }
+    Ok(tokens)
+}
+
+/// Matches `name` against `tokens`, returning the parts of the date if it
+/// matched.
+fn read(tokens: &[Token], name: &[char], mut parts: Parts, case_sensitive: bool) -> Option<Parts> {
+    match tokens {
+        [] => name.is_empty().then_some(parts),
+
+        [Token::AnyChars, rest @ ..]
+        => (0..=name.len()).find_map(|i| read(rest, &name[i..], parts, case_sensitive)),
+
+        [Token::AnyChar, rest @ ..]
+        => name.get(1..).and_then(|name| read(rest, name, parts, case_sensitive)),
+
+        [Token::Literal(l), rest @ ..] => {
+            let c = name.first()?;
+            let same = match case_sensitive {
+                true => c == l,
+                false => c.to_lowercase().eq(l.to_lowercase())
+            };
+            same.then(|| read(rest, &name[1..], parts, case_sensitive)).flatten()
+        }
+
+        [Token::Field(field), rest @ ..] => {
+            let digits = name.get(..field.width())?;
+            if !digits.iter().all(char::is_ascii_digit) {
+                return None;
+            }
+            let value: u32 = digits.iter().collect::<String>().parse().ok()?;
+            match field {
+                Field::Year => parts.year = value as i32,
+                Field::ShortYear => parts.year = 2000 + value as i32,
+                Field::Month => parts.month = value as u8,
+                Field::Day => parts.day = value as u8,
+                Field::Hour => parts.hour = value as u8,
+                Field::Minute => parts.minute = value as u8,
+                Field::Second => parts.second = value as u8
+            }
+            read(rest, &name[field.width()..], parts, case_sensitive)
+        }
+    }
+}
+
+fn to_time(parts: Parts) -> Option<SystemTime> {
+    let date = Date::from_calendar_date(parts.year, Month::try_from(parts.month).ok()?, parts.day).ok()?;
+    let time = Time::from_hms(parts.hour, parts.minute, parts.second).ok()?;
+    let utc: OffsetDateTime = PrimitiveDateTime::new(date, time).assume_utc();
+    Some(utc.into())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The date in `name` going by `format`, in seconds since the epoch.
+    fn date_in(format: &str, name: &str) -> Option<i64> {
+        let parts = Parts { year: 0, month: 1, day: 1, hour: 0, minute: 0, second: 0 };
+        let name: Vec<char> = name.chars().collect();
+        read(&parse_format(format).unwrap(), &name, parts, true)
+            .and_then(to_time)
+            .map(|time| OffsetDateTime::from(time).unix_timestamp())
+    }
+
+    #[test]
+    fn dates_are_read_out_of_names() {
+        // May 1st, 2024
+        let may = 1_714_521_600;
+        assert_eq!(date_in("report-%Y-%m-%d.csv", "report-2024-05-01.csv"), Some(may));
+        assert_eq!(date_in("%y%m%d_*", "240501_notes.txt"), Some(may));
+        assert_eq!(date_in("IMG_%Y%m%d_%H%M%S.jpg", "IMG_20240501_143000.jpg"), Some(may + 14 * 3600 + 30 * 60));
+        // what the format leaves out is at the start of the month or year
+        assert_eq!(date_in("%Y-%m ?.log", "2024-05 a.log"), Some(may));
+        assert_eq!(date_in("100%% %Y", "100% 2024"), Some(1_704_067_200));
+    }
+
+    #[test]
+    fn names_without_a_real_date_never_match() {
+        assert_eq!(date_in("%Y-%m-%d", "2024-02-30"), None);
+        assert_eq!(date_in("%Y-%m-%d", "2024-5-01"), None);
+        assert_eq!(date_in("%Y-%m-%d", "2024-05-01.bak"), None);
+        assert_eq!(date_in("report-%Y", "notes-2024"), None);
+        assert!(!is_older_than("%Y-%m-%d", "2024-02-30", Duration::ZERO, true));
+    }
+
+    #[test]
+    fn only_dates_older_than_the_age_match() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert!(is_older_than("backup-%Y-%m-%d", "backup-2000-01-01", day, true));
+        assert!(!is_older_than("backup-%Y-%m-%d", "backup-2999-01-01", day, true));
+        assert!(!is_older_than("backup-%Y-%m-%d", "BACKUP-2000-01-01", day, true));
+        assert!(is_older_than("backup-%Y-%m-%d", "BACKUP-2000-01-01", day, false));
+    }
+
+    #[test]
+    fn formats_need_a_year_and_known_placeholders() {
+        assert!(check("%Y-%m-%d").is_ok());
+        assert!(check("%y").is_ok());
+        assert!(check("%m-%d").unwrap_err().contains("doesn't contain a year"));
+        assert!(check("%Y-%q").unwrap_err().contains("unknown placeholder \"%q\""));
+        assert!(check("%Y%").is_err());
+    }
+
+    #[test]
+    fn layouts_write_dates_without_wildcards() {
+        let date = OffsetDateTime::from_unix_timestamp(1_714_573_805).unwrap();
+        assert_eq!(write("%Y/%m", date).unwrap(), "2024/05");
+        assert_eq!(write("%y-%m-%d_%H-%M-%S", date).unwrap(), "24-05-01_14-30-05");
+        assert_eq!(write("%d%%", date).unwrap(), "01%");
+        assert!(write("%Y/*", date).is_err());
+    }
+}
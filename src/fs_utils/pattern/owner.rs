@@ -0,0 +1,91 @@
+//! Looking up who owns an entry.
+//!
+//! Owners are identified by their security identifier, such as
+//! `S-1-5-21-...-1001`, on Windows and by their numeric user ID, such as
+//! `1000`, elsewhere.
+
+use std::path::Path;
+
+
+/// Checks whether the entry at `path` is owned by `owner`.
+///
+/// Entries whose owner can't be read never match.
+pub(crate) fn is_owned_by(path: &Path, owner: &str) -> bool {
+    owner_of(path).is_some_and(|actual| actual.eq_ignore_ascii_case(owner.trim()))
+}
+
+/// Checks that `owner` is written the way owners are on this platform,
+/// returning the reason if it isn't.
+pub(crate) fn check(owner: &str) -> Result<(), String> {
+    let owner = owner.trim();
+    let valid = match cfg!(windows) {
+        true => owner.len() > 2 && owner[..2].eq_ignore_ascii_case("S-"),
+        false => owner.parse::<u32>().is_ok()
+    };
+
+    match valid {
+        true => Ok(()),
+        false if cfg!(windows) => Err(format!("\"{owner}\" isn't a security identifier like \"S-1-5-18\"")),
+        false => Err(format!("\"{owner}\" isn't a numeric user ID"))
+    }
+}
+
+
+#[cfg(unix)]
+fn owner_of(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    path.symlink_metadata().ok().map(|m| m.uid().to_string())
+}
+
+#[cfg(windows)]
+fn owner_of(path: &Path) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+    use windows_sys::Win32::Security::{OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID};
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertSidToStringSidW,
+        GetNamedSecurityInfoW,
+        SE_FILE_OBJECT
+    };
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut owner: PSID = ptr::null_mut();
+    let mut descriptor: PSECURITY_DESCRIPTOR = ptr::null_mut();
+
+    // SAFETY: the name is null-terminated, and the owner points into the
+    // descriptor, which is freed only after the owner has been converted
+    unsafe {
+        let status = GetNamedSecurityInfoW(
+            wide.as_ptr(),
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION,
+            &mut owner,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut descriptor
+        );
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+
+        let mut text = ptr::null_mut();
+        let converted = ConvertSidToStringSidW(owner, &mut text) != 0;
+        LocalFree(descriptor);
+        if !converted {
+            return None;
+        }
+
+        let len = (0..).take_while(|&i| *text.add(i) != 0).count();
+        let sid = String::from_utf16_lossy(std::slice::from_raw_parts(text, len));
+        LocalFree(text.cast());
+        Some(sid)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn owner_of(_path: &Path) -> Option<String> {
+    None
+}
@@ -0,0 +1,67 @@
+//! Built-in patterns for common kinds of files.
+
+use std::path::Path;
+
+use super::glob;
+
+
+/// Names of files that programs leave behind temporarily or that operating
+/// systems create for their own use, in lowercase.
+pub(crate) const TEMP_ARTIFACTS: [&str; 10] = [
+    // lock files of open Office and LibreOffice documents
+    "~$*",
+    ".~lock.*#",
+    "*.tmp",
+    "*.temp",
+    // unfinished downloads of Chrome, Firefox, Safari and others
+    "*.crdownload",
+    "*.part",
+    "*.partial",
+    "*.download",
+    // folder metadata of macOS and Windows
+    ".ds_store",
+    "thumbs.db"
+];
+
+
+/// Extensions of image files.
+pub(crate) const IMAGES: [&str; 16] = [
+    "jpg", "jpeg", "png", "gif", "bmp", "tif", "tiff", "webp",
+    "heic", "heif", "avif", "svg", "ico", "raw", "cr2", "nef"
+];
+
+/// Extensions of video files.
+pub(crate) const VIDEOS: [&str; 12] = [
+    "mp4", "m4v", "mkv", "mov", "avi", "wmv", "webm", "flv", "mpg", "mpeg", "3gp", "ts"
+];
+
+/// Extensions of audio files.
+pub(crate) const AUDIO: [&str; 10] = [
+    "mp3", "wav", "flac", "aac", "m4a", "ogg", "opus", "wma", "aiff", "mid"
+];
+
+/// Extensions of documents, spreadsheets and presentations.
+pub(crate) const DOCUMENTS: [&str; 16] = [
+    "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "xls", "xlsx", "ods",
+    "csv", "ppt", "pptx", "odp", "epub", "pages"
+];
+
+/// Extensions of archives and disk images.
+pub(crate) const ARCHIVES: [&str; 14] = [
+    "zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "zst", "iso", "dmg",
+    "cab", "lz", "lzma"
+];
+
+
+/// Checks whether the entry at `path` has any of `exts`, whatever its case.
+pub(crate) fn has_any_extension(exts: &[&str], path: &Path) -> bool {
+    let ext = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    exts.contains(&ext.as_str())
+}
+
+/// Checks whether the name of the entry at `path` matches any of `globs`,
+/// whatever its case.
+pub(crate) fn matches_any(globs: &[&str], path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    globs.iter().any(|g| glob::matches(g, &name))
+}
@@ -0,0 +1,101 @@
+use std::fmt;
+
+use regex::Regex;
+
+
+/// A regular expression used as a [`FilePattern`](super::FilePattern).
+///
+/// The expression is compiled once when the pattern is created rather than
+/// every time it's matched. An expression that fails to compile never
+/// matches anything, and the reason it failed is available through
+/// [`error`](Self::error()) so that it can be reported when the
+/// configuration is [validated](crate::config::Config::validate()).
+///
+/// Patterns are compared by their source text.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::fs_utils::RegexPattern;
+///
+/// let regex = RegexPattern::new(r"^backup-\d{8}\.zip$");
+/// assert!(regex.is_match("backup-20240501.zip"));
+/// assert!(RegexPattern::new("(unclosed").error().is_some());
+/// ```
+#[derive(Clone)]
+pub struct RegexPattern {
+    source: String,
+    compiled: Result<Regex, regex::Error>
+}
+
+impl RegexPattern {
+    /// Compiles a regular expression.
+    pub fn new<S: Into<String>>(source: S) -> Self {
+        let source = source.into();
+        let compiled = Regex::new(&source);
+        RegexPattern { source, compiled }
+    }
+
+    /// The source text of the expression.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// The reason the expression failed to compile, if it did.
+    pub fn error(&self) -> Option<&regex::Error> {
+        self.compiled.as_ref().err()
+    }
+
+    /// Checks whether the expression matches somewhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.compiled.as_ref().is_ok_and(|regex| regex.is_match(text))
+    }
+}
+
+impl PartialEq for RegexPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for RegexPattern {}
+
+impl fmt::Debug for RegexPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RegexPattern").field(&self.source).finish()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::fs_utils::FilePattern;
+
+    #[test]
+    fn expressions_match_anywhere_in_names() {
+        let pattern = FilePattern::Regex(RegexPattern::new(r"\d{4}-\d{2}"));
+        assert!(pattern.matches(Path::new("/backups/db-2024-05.sql")));
+        assert!(!pattern.matches(Path::new("/2024-05/db.sql")));
+
+        let anchored = FilePattern::Regex(RegexPattern::new(r"^db-\d+\.sql$"));
+        assert!(anchored.matches(Path::new("db-1.sql")));
+        assert!(!anchored.matches(Path::new("old-db-1.sql")));
+    }
+
+    #[test]
+    fn invalid_expressions_never_match() {
+        let regex = RegexPattern::new("(unclosed");
+        assert!(regex.error().is_some());
+        assert!(!regex.is_match("(unclosed"));
+        assert!(RegexPattern::new("closed").error().is_none());
+    }
+
+    #[test]
+    fn expressions_are_compared_by_source() {
+        assert_eq!(RegexPattern::new("a+"), RegexPattern::new("a+"));
+        assert_ne!(RegexPattern::new("a+"), RegexPattern::new("aa*"));
+        assert_eq!(RegexPattern::new("(").as_str(), "(");
+    }
+}
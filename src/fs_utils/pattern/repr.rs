@@ -0,0 +1,824 @@
+//! The serialized form of file patterns.
+//!
+//! Patterns have two interchangeable forms. The short form is a string with
+//! the name of the pattern, followed by its arguments in parentheses if it
+//! has any, such as `"any"` or `"ext(lnk)"`. Arguments containing special
+//! characters can be quoted, as in `"ext(\"tar gz\")"`. The table form has
+//! the name of the pattern in its `type` key and the arguments in named keys,
+//! such as `{ type = "extension", value = "lnk" }`.
+//!
+//! Patterns are serialized in the short form, which is also what they're
+//! [displayed](fmt::Display) as. Both forms are accepted when deserializing.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::config::{duration, size};
+use crate::fs_utils::{FileAttribute, FilePattern, RegexPattern, Timestamp, DEFAULT_CONTENT_MAX_SIZE};
+use super::{expr, name_date, signature};
+
+
+/// Represents errors that occur when a string isn't a valid pattern.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::fs_utils::FilePattern;
+///
+/// let error = "ext(iso) and and".parse::<FilePattern>().unwrap_err();
+/// assert_eq!(error.position(), Some(13));
+/// assert_eq!(error.reason(), "expected a pattern before \"and\"");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePatternError {
+    input: String,
+    reason: String,
+    position: Option<usize>
+}
+
+impl ParsePatternError {
+    /// Creates an error about a problem at the byte offset `position` of
+    /// `input`.
+    pub(super) fn at<R: Into<String>>(input: &str, position: usize, reason: R) -> Self {
+        ParsePatternError {
+            input: input.to_string(),
+            reason: reason.into(),
+            position: Some(position)
+        }
+    }
+
+    /// The string that failed to parse.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// A description of what's wrong with the string.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// The byte offset in the [input](Self::input()) where the problem was
+    /// found, if it's known.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+}
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern \"{}\": {}", self.input, self.reason)?;
+        match self.position {
+            Some(position) => write!(f, " (at position {position})"),
+            None => Ok(())
+        }
+    }
+}
+
+impl std::error::Error for ParsePatternError {}
+
+
+/// The table form of a pattern.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum PatternTable {
+    Any,
+    #[serde(alias = "ext")]
+    Extension { value: String },
+    #[serde(alias = "exts")]
+    Extensions { value: Vec<String> },
+    #[serde(rename = "not_extension", alias = "not_ext")]
+    NotExtension { value: Vec<String> },
+    Glob { value: String },
+    #[serde(rename = "path")]
+    RelativePath { value: String },
+    #[serde(rename = "starts_with")]
+    NameStartsWith { value: String },
+    #[serde(rename = "ends_with")]
+    NameEndsWith { value: String },
+    #[serde(rename = "contains", alias = "name")]
+    NameContains { value: String },
+    Regex { value: String },
+    #[serde(alias = "older")]
+    OlderThan {
+        #[serde(with = "duration")]
+        value: Duration,
+        #[serde(default)]
+        timestamp: Timestamp
+    },
+    NotAccessed {
+        #[serde(with = "duration")]
+        value: Duration
+    },
+    NameDate {
+        format: String,
+        #[serde(with = "duration")]
+        older_than: Duration
+    },
+    #[serde(alias = "larger")]
+    LargerThan {
+        #[serde(with = "size")]
+        value: u64
+    },
+    #[serde(alias = "smaller")]
+    SmallerThan {
+        #[serde(with = "size")]
+        value: u64
+    },
+    #[serde(rename = "file")]
+    IsFile,
+    #[serde(rename = "dir", alias = "directory")]
+    IsDir,
+    EmptyDir,
+    EmptyFile,
+    #[serde(alias = "mime")]
+    ContentType { value: String },
+    Content {
+        value: String,
+        #[serde(default = "default_max_size", with = "size")]
+        max_size: u64
+    },
+    ContentRegex {
+        value: String,
+        #[serde(default = "default_max_size", with = "size")]
+        max_size: u64
+    },
+    #[serde(rename = "magic", alias = "signature")]
+    Signature {
+        #[serde(deserialize_with = "signature::deserialize")]
+        value: Vec<u8>
+    },
+    #[serde(alias = "attr")]
+    Attribute { value: FileAttribute },
+    InUse,
+    Downloaded,
+    Owner { value: String },
+    Duplicate,
+    #[serde(alias = "symlink")]
+    Link,
+    BrokenLink,
+    TempArtifacts,
+    Images,
+    Videos,
+    Audio,
+    Documents,
+    Archives,
+    KeepNewest {
+        count: usize,
+        #[serde(alias = "pattern")]
+        value: Box<FilePattern>
+    },
+    All {
+        #[serde(alias = "patterns")]
+        value: Vec<FilePattern>
+    },
+    AnyOf {
+        #[serde(alias = "patterns")]
+        value: Vec<FilePattern>
+    },
+    Not {
+        #[serde(alias = "pattern")]
+        value: Box<FilePattern>
+    }
+}
+
+impl From<PatternTable> for FilePattern {
+    fn from(table: PatternTable) -> Self {
+        match table {
+            PatternTable::Any => Self::Any,
+            PatternTable::Extension { value } => Self::Extension(value),
+            PatternTable::Extensions { value } => Self::Extensions(value),
+            PatternTable::NotExtension { value } => Self::NotExtension(value),
+            PatternTable::Glob { value } => Self::Glob(value),
+            PatternTable::RelativePath { value } => Self::RelativePath(value),
+            PatternTable::NameStartsWith { value } => Self::NameStartsWith(value),
+            PatternTable::NameEndsWith { value } => Self::NameEndsWith(value),
+            PatternTable::NameContains { value } => Self::NameContains(value),
+            PatternTable::Regex { value } => Self::Regex(RegexPattern::new(value)),
+            PatternTable::OlderThan { value, timestamp } => Self::OlderThan(value, timestamp),
+            PatternTable::NotAccessed { value } => Self::OlderThan(value, Timestamp::Accessed),
+            PatternTable::NameDate { format, older_than } => Self::NameDate(format, older_than),
+            PatternTable::LargerThan { value } => Self::LargerThan(value),
+            PatternTable::SmallerThan { value } => Self::SmallerThan(value),
+            PatternTable::IsFile => Self::IsFile,
+            PatternTable::IsDir => Self::IsDir,
+            PatternTable::EmptyDir => Self::EmptyDir,
+            PatternTable::EmptyFile => Self::EmptyFile,
+            PatternTable::ContentType { value } => Self::ContentType(value),
+            PatternTable::Content { value, max_size } => Self::Content(value, max_size),
+            PatternTable::ContentRegex { value, max_size }
+            => Self::ContentRegex(RegexPattern::new(value), max_size),
+
+            PatternTable::Signature { value } => Self::Signature(value),
+            PatternTable::Attribute { value } => Self::Attribute(value),
+            PatternTable::InUse => Self::InUse,
+            PatternTable::Downloaded => Self::Downloaded,
+            PatternTable::Owner { value } => Self::Owner(value),
+            PatternTable::Duplicate => Self::Duplicate,
+            PatternTable::Link => Self::Link,
+            PatternTable::BrokenLink => Self::BrokenLink,
+            PatternTable::TempArtifacts => Self::TempArtifacts,
+            PatternTable::Images => Self::Images,
+            PatternTable::Videos => Self::Videos,
+            PatternTable::Audio => Self::Audio,
+            PatternTable::Documents => Self::Documents,
+            PatternTable::Archives => Self::Archives,
+            PatternTable::KeepNewest { count, value } => Self::KeepNewest(count, value),
+            PatternTable::All { value } => Self::All(value),
+            PatternTable::AnyOf { value } => Self::AnyOf(value),
+            PatternTable::Not { value } => Self::Not(value)
+        }
+    }
+}
+
+
+impl FilePattern {
+    /// Creates a pattern from the name and arguments of its short form.
+    pub(super) fn from_short(name: &str, args: Vec<String>) -> Result<Self, String> {
+        let expect = |count: usize| match args.len() == count {
+            true => Ok(()),
+            false => Err(format!("{name} takes {count} argument(s), not {}", args.len()))
+        };
+        let expect_between = |min: usize, max: usize| match (min..=max).contains(&args.len()) {
+            true => Ok(()),
+            false => Err(format!("{name} takes {min} to {max} arguments, not {}", args.len()))
+        };
+
+        match name {
+            "any" => {
+                expect(0)?;
+                Ok(Self::Any)
+            }
+            "ext" | "extension" if args.len() > 1 => Ok(Self::Extensions(args)),
+            "ext" | "extension" => {
+                expect(1)?;
+                Ok(Self::Extension(args.into_iter().next().unwrap_or_default()))
+            }
+            "exts" | "extensions" | "not_ext" | "not_extension" if args.is_empty() => {
+                Err(format!("{name} takes at least 1 argument"))
+            }
+            "exts" | "extensions" => Ok(Self::Extensions(args)),
+            "not_ext" | "not_extension" => Ok(Self::NotExtension(args)),
+            "glob" => {
+                expect(1)?;
+                Ok(Self::Glob(args.into_iter().next().unwrap_or_default()))
+            }
+            "path" => {
+                expect(1)?;
+                Ok(Self::RelativePath(args.into_iter().next().unwrap_or_default()))
+            }
+            "starts_with" => {
+                expect(1)?;
+                Ok(Self::NameStartsWith(args.into_iter().next().unwrap_or_default()))
+            }
+            "ends_with" => {
+                expect(1)?;
+                Ok(Self::NameEndsWith(args.into_iter().next().unwrap_or_default()))
+            }
+            "contains" | "name" => {
+                expect(1)?;
+                Ok(Self::NameContains(args.into_iter().next().unwrap_or_default()))
+            }
+            "regex" => {
+                expect(1)?;
+                Ok(Self::Regex(RegexPattern::new(args.into_iter().next().unwrap_or_default())))
+            }
+            "older" | "older_than" => {
+                expect_between(1, 2)?;
+                let age = duration::parse(&args[0]).map_err(|e| e.to_string())?;
+                let timestamp = match args.get(1) {
+                    None => Timestamp::default(),
+                    Some(name) => Timestamp::from_name(name)
+                        .ok_or_else(|| format!("unknown timestamp \"{name}\""))?
+                };
+                Ok(Self::OlderThan(age, timestamp))
+            }
+            "not_accessed" => {
+                expect(1)?;
+                let age = duration::parse(&args[0]).map_err(|e| e.to_string())?;
+                Ok(Self::OlderThan(age, Timestamp::Accessed))
+            }
+            "name_date" => {
+                expect(2)?;
+                name_date::check(&args[0])?;
+                let age = duration::parse(&args[1]).map_err(|e| e.to_string())?;
+                Ok(Self::NameDate(args[0].clone(), age))
+            }
+            "larger" | "larger_than" => {
+                expect(1)?;
+                Ok(Self::LargerThan(size::parse(&args[0]).map_err(|e| e.to_string())?))
+            }
+            "smaller" | "smaller_than" => {
+                expect(1)?;
+                Ok(Self::SmallerThan(size::parse(&args[0]).map_err(|e| e.to_string())?))
+            }
+            "file" => {
+                expect(0)?;
+                Ok(Self::IsFile)
+            }
+            "dir" | "directory" => {
+                expect(0)?;
+                Ok(Self::IsDir)
+            }
+            "empty_dir" => {
+                expect(0)?;
+                Ok(Self::EmptyDir)
+            }
+            "empty_file" => {
+                expect(0)?;
+                Ok(Self::EmptyFile)
+            }
+            "mime" | "content_type" => {
+                expect(1)?;
+                Ok(Self::ContentType(args.into_iter().next().unwrap_or_default()))
+            }
+            "content" | "content_regex" => {
+                expect_between(1, 2)?;
+                let max_size = match args.get(1) {
+                    None => DEFAULT_CONTENT_MAX_SIZE,
+                    Some(max_size) => size::parse(max_size).map_err(|e| e.to_string())?
+                };
+                let value = args.into_iter().next().unwrap_or_default();
+                Ok(match name {
+                    "content" => Self::Content(value, max_size),
+                    _ => Self::ContentRegex(RegexPattern::new(value), max_size)
+                })
+            }
+            "magic" | "signature" => {
+                expect(1)?;
+                Ok(Self::Signature(signature::parse(&args[0])?))
+            }
+            "attr" | "attribute" => {
+                expect(1)?;
+                let attribute = FileAttribute::from_name(&args[0])
+                    .ok_or_else(|| format!("unknown attribute \"{}\"", args[0]))?;
+                Ok(Self::Attribute(attribute))
+            }
+            "read_only" => {
+                expect(0)?;
+                Ok(Self::Attribute(FileAttribute::ReadOnly))
+            }
+            "in_use" => {
+                expect(0)?;
+                Ok(Self::InUse)
+            }
+            "downloaded" => {
+                expect(0)?;
+                Ok(Self::Downloaded)
+            }
+            "owner" => {
+                expect(1)?;
+                Ok(Self::Owner(args.into_iter().next().unwrap_or_default()))
+            }
+            "duplicate" => {
+                expect(0)?;
+                Ok(Self::Duplicate)
+            }
+            "link" | "symlink" => {
+                expect(0)?;
+                Ok(Self::Link)
+            }
+            "broken_link" => {
+                expect(0)?;
+                Ok(Self::BrokenLink)
+            }
+            "temp_artifacts" => {
+                expect(0)?;
+                Ok(Self::TempArtifacts)
+            }
+            "images" => {
+                expect(0)?;
+                Ok(Self::Images)
+            }
+            "videos" => {
+                expect(0)?;
+                Ok(Self::Videos)
+            }
+            "audio" => {
+                expect(0)?;
+                Ok(Self::Audio)
+            }
+            "documents" => {
+                expect(0)?;
+                Ok(Self::Documents)
+            }
+            "archives" => {
+                expect(0)?;
+                Ok(Self::Archives)
+            }
+            "keep_newest" => {
+                expect(2)?;
+                let count = args[0]
+                    .parse()
+                    .map_err(|_| format!("\"{}\" isn't a number of entries", args[0]))?;
+                Ok(Self::KeepNewest(count, Box::new(parse_patterns(&args[1..])?.remove(0))))
+            }
+            "all" => Ok(Self::All(parse_patterns(&args)?)),
+            "any_of" => Ok(Self::AnyOf(parse_patterns(&args)?)),
+            "not" => {
+                expect(1)?;
+                Ok(Self::Not(Box::new(parse_patterns(&args)?.remove(0))))
+            }
+            _ => Err(format!("unknown pattern \"{name}\""))
+        }
+    }
+}
+
+impl FromStr for FilePattern {
+    type Err = ParsePatternError;
+
+    /// Parses a pattern from its short form, such as `"ext(lnk)"`, or from
+    /// an [expression](FilePattern#expressions) that combines several
+    /// patterns, such as
+    /// `"ext(iso) and not name(keep)"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use folder_cleaner::fs_utils::FilePattern;
+    ///
+    /// let pattern: FilePattern = "ext(lnk)".parse().unwrap();
+    /// assert_eq!(pattern, FilePattern::Extension("lnk".into()));
+    ///
+    /// let pattern: FilePattern = "not(ext(pdf))".parse().unwrap();
+    /// assert_eq!(pattern, FilePattern::Not(Box::new(FilePattern::Extension("pdf".into()))));
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        expr::parse(s)
+    }
+}
+
+
+impl fmt::Display for FilePattern {
+    /// Writes the pattern in its short form.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, args): (&str, Vec<String>) = match self {
+            Self::Any => ("any", vec![]),
+            Self::Extension(ext) => ("ext", vec![ext.clone()]),
+            Self::Extensions(exts) => ("exts", exts.clone()),
+            Self::NotExtension(exts) => ("not_ext", exts.clone()),
+            Self::Glob(glob) => ("glob", vec![glob.clone()]),
+            Self::RelativePath(glob) => ("path", vec![glob.clone()]),
+            Self::NameStartsWith(text) => ("starts_with", vec![text.clone()]),
+            Self::NameEndsWith(text) => ("ends_with", vec![text.clone()]),
+            Self::NameContains(text) => ("contains", vec![text.clone()]),
+            Self::Regex(regex) => ("regex", vec![regex.as_str().to_string()]),
+            Self::OlderThan(age, Timestamp::Modified)
+            => ("older", vec![duration::format(*age)]),
+
+            Self::OlderThan(age, Timestamp::Accessed)
+            => ("not_accessed", vec![duration::format(*age)]),
+
+            Self::OlderThan(age, timestamp)
+            => ("older", vec![duration::format(*age), timestamp.name().into()]),
+
+            Self::NameDate(format, age)
+            => ("name_date", vec![format.clone(), duration::format(*age)]),
+
+            Self::LargerThan(bytes) => ("larger", vec![size::format(*bytes)]),
+            Self::SmallerThan(bytes) => ("smaller", vec![size::format(*bytes)]),
+            Self::IsFile => ("file", vec![]),
+            Self::IsDir => ("dir", vec![]),
+            Self::EmptyDir => ("empty_dir", vec![]),
+            Self::EmptyFile => ("empty_file", vec![]),
+            Self::ContentType(mime) => ("mime", vec![mime.clone()]),
+            Self::Content(text, max_size)
+            => ("content", with_max_size(text.clone(), *max_size)),
+
+            Self::ContentRegex(regex, max_size)
+            => ("content_regex", with_max_size(regex.as_str().to_string(), *max_size)),
+
+            Self::Signature(bytes) => ("magic", vec![signature::format(bytes)]),
+            Self::Attribute(attribute) => ("attr", vec![attribute.name().into()]),
+            Self::InUse => ("in_use", vec![]),
+            Self::Downloaded => ("downloaded", vec![]),
+            Self::Owner(owner) => ("owner", vec![owner.clone()]),
+            Self::Duplicate => ("duplicate", vec![]),
+            Self::Link => ("link", vec![]),
+            Self::BrokenLink => ("broken_link", vec![]),
+            Self::Custom(_) => ("custom", vec![]),
+            Self::TempArtifacts => ("temp_artifacts", vec![]),
+            Self::Images => ("images", vec![]),
+            Self::Videos => ("videos", vec![]),
+            Self::Audio => ("audio", vec![]),
+            Self::Documents => ("documents", vec![]),
+            Self::Archives => ("archives", vec![]),
+            Self::KeepNewest(count, pattern) => return write!(f, "keep_newest({count}, {pattern})"),
+            Self::All(patterns) => return write_nested(f, "all", patterns),
+            Self::AnyOf(patterns) => return write_nested(f, "any_of", patterns),
+            Self::Not(pattern) => return write_nested(f, "not", std::slice::from_ref(pattern))
+        };
+
+        if args.is_empty() {
+            return write!(f, "{name}");
+        }
+        let args: Vec<String> = args.iter().map(|arg| quote(arg)).collect();
+        write!(f, "{name}({})", args.join(", "))
+    }
+}
+
+
+impl FilePattern {
+    /// Checks whether the pattern is or contains a custom pattern.
+    fn has_custom(&self) -> bool {
+        match self {
+            Self::Custom(_) => true,
+            Self::All(patterns) | Self::AnyOf(patterns) => patterns.iter().any(Self::has_custom),
+            Self::Not(pattern) | Self::KeepNewest(_, pattern) => pattern.has_custom(),
+            _ => false
+        }
+    }
+}
+
+/// The arguments of a content pattern, leaving out the default limit.
+fn with_max_size(value: String, max_size: u64) -> Vec<String> {
+    match max_size == DEFAULT_CONTENT_MAX_SIZE {
+        true => vec![value],
+        false => vec![value, size::format(max_size)]
+    }
+}
+
+fn default_max_size() -> u64 {
+    DEFAULT_CONTENT_MAX_SIZE
+}
+
+/// Writes a composite pattern in its short form.
+///
+/// Nested patterns are written as they are rather than quoted.
+fn write_nested(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    patterns: &[FilePattern]
+) -> fmt::Result {
+    let patterns: Vec<String> = patterns.iter().map(FilePattern::to_string).collect();
+    write!(f, "{name}({})", patterns.join(", "))
+}
+
+
+impl Serialize for FilePattern {
+    /// Serializes the pattern in its short form.
+    ///
+    /// # Errors
+    ///
+    /// Patterns that contain a [`Custom`](FilePattern::Custom) pattern can't
+    /// be serialized, since there's no way to write the logic down.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.has_custom() {
+            true => Err(ser::Error::custom("custom patterns can't be serialized")),
+            false => serializer.collect_str(self)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FilePattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(PatternVisitor)
+    }
+}
+
+struct PatternVisitor;
+
+impl<'de> Visitor<'de> for PatternVisitor {
+    type Value = FilePattern;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a pattern string like \"ext(lnk)\" or a table with a type")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        s.parse().map_err(E::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        let deserializer = de::value::MapAccessDeserializer::new(map);
+        PatternTable::deserialize(deserializer).map(FilePattern::from)
+    }
+}
+
+
+/// Splits the arguments of a short form pattern, removing any quotes.
+pub(super) fn parse_args(s: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut chars = s.trim().chars().peekable();
+    if chars.peek().is_none() {
+        return Ok(args);
+    }
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut arg = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => arg.extend(chars.next()),
+                    Some(c) => arg.push(c),
+                    None => return Err("missing closing quote".into())
+                }
+            }
+        }
+        else {
+            // nested patterns are kept whole, parentheses, quotes and all
+            let mut depth = 0;
+            while let Some(c) = chars.next_if(|c| depth > 0 || *c == '(' || !is_special(*c)) {
+                arg.push(c);
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    '"' => loop {
+                        match chars.next() {
+                            Some('\\') => {
+                                arg.push('\\');
+                                arg.extend(chars.next());
+                            }
+                            Some(c) => {
+                                arg.push(c);
+                                if c == '"' {
+                                    break;
+                                }
+                            }
+                            None => return Err("missing closing quote".into())
+                        }
+                    },
+                    _ => {}
+                }
+            }
+            if depth > 0 {
+                return Err("missing closing parenthesis".into());
+            }
+            arg.truncate(arg.trim_end().len());
+        }
+        args.push(arg);
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.next() {
+            None => return Ok(args),
+            Some(',') => continue,
+            Some(c) => return Err(format!("unexpected \"{c}\" in arguments"))
+        }
+    }
+}
+
+/// Parses the arguments of a composite pattern as patterns themselves.
+fn parse_patterns(args: &[String]) -> Result<Vec<FilePattern>, String> {
+    args.iter()
+        .map(|arg| {
+            arg.parse().map_err(|e: ParsePatternError| {
+                format!("in \"{}\": {}", e.input(), e.reason())
+            })
+        })
+        .collect()
+}
+
+/// Quotes an argument if it couldn't be parsed back without quotes.
+fn quote(arg: &str) -> String {
+    let needs_quotes = arg.is_empty()
+        || arg.trim() != arg
+        || arg.chars().any(is_special);
+
+    match needs_quotes {
+        true => format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"")),
+        false => arg.to_string()
+    }
+}
+
+/// Characters that can't appear in an argument without quotes.
+///
+/// Backslashes only escape characters inside quotes, so that arguments like
+/// Windows paths can be written without quotes.
+fn is_special(c: char) -> bool {
+    matches!(c, '(' | ')' | ',' | '"')
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn ext(ext: &str) -> FilePattern {
+        FilePattern::Extension(ext.into())
+    }
+
+    #[test]
+    fn lists_of_extensions_need_an_extension() {
+        for pattern in ["exts()", "not_ext()"] {
+            let error = pattern.parse::<FilePattern>().unwrap_err();
+            assert!(error.reason().contains("at least 1 argument"), "{pattern}: {error}");
+        }
+        let pattern: FilePattern = "exts(iso, img)".parse().unwrap();
+        assert_eq!(pattern, FilePattern::Extensions(vec!["iso".into(), "img".into()]));
+    }
+
+    #[test]
+    fn composite_patterns_nest_without_quotes() {
+        let pattern = FilePattern::All(vec![
+            FilePattern::AnyOf(vec![ext("iso"), ext("img")]),
+            FilePattern::Not(Box::new(FilePattern::NameContains("keep, please".into())))
+        ]);
+        let text = "all(any_of(ext(iso), ext(img)), not(contains(\"keep, please\")))";
+        assert_eq!(pattern.to_string(), text);
+        assert_eq!(text.parse::<FilePattern>().unwrap(), pattern);
+    }
+
+    #[test]
+    fn composite_patterns_combine_their_parts() {
+        let pattern: FilePattern = "all(any_of(ext(iso), ext(img)), not(contains(keep)))".parse().unwrap();
+        assert!(pattern.matches(Path::new("ubuntu.iso")));
+        assert!(pattern.matches(Path::new("disk.img")));
+        assert!(!pattern.matches(Path::new("ubuntu-keep.iso")));
+        assert!(!pattern.matches(Path::new("notes.txt")));
+    }
+
+    #[test]
+    fn composite_patterns_can_be_tables() {
+        #[derive(Deserialize)]
+        struct Routine {
+            pattern: FilePattern
+        }
+        let routine: Routine = toml::from_str(r#"
+            pattern = { type = "all", value = ["ext(iso)", { type = "not", value = "contains(keep)" }] }
+        "#).unwrap();
+        assert_eq!(routine.pattern.to_string(), "all(ext(iso), not(contains(keep)))");
+    }
+
+    #[test]
+    fn parts_of_composite_patterns_have_to_be_valid() {
+        let error = "all(ext(iso), nonsense)".parse::<FilePattern>().unwrap_err();
+        assert!(error.reason().contains("unknown pattern \"nonsense\""), "{error}");
+        assert!("not(ext(iso), ext(img))".parse::<FilePattern>().is_err());
+    }
+
+    #[test]
+    fn short_forms_parse_back_to_the_same_pattern() {
+        for text in [
+            "any",
+            "ext(lnk)",
+            "exts(iso, img)",
+            "not_ext(pdf, docx)",
+            "glob(*.tmp)",
+            "path(cache/**)",
+            "starts_with(~$)",
+            "ends_with(.part)",
+            "contains(\" copy\")",
+            "regex(^IMG_\\d+$)",
+            "older(1w)",
+            "older(1d, created)",
+            "not_accessed(2w)",
+            "name_date(%Y-%m-%d, 1d)",
+            "larger(1GB)",
+            "smaller(1KB)",
+            "file",
+            "dir",
+            "empty_dir",
+            "empty_file",
+            "mime(image/*)",
+            "content(secret)",
+            "content(secret, 1MB)",
+            "content_regex(\\d{4})",
+            "attr(hidden)",
+            "in_use",
+            "downloaded",
+            "owner(root)",
+            "duplicate",
+            "link",
+            "broken_link",
+            "temp_artifacts",
+            "images",
+            "keep_newest(3, glob(backup-*))",
+            "not(all(ext(iso), any_of(larger(1GB), older(1w))))"
+        ] {
+            let pattern: FilePattern = text.parse().unwrap_or_else(|e| panic!("{text}: {e}"));
+            assert_eq!(pattern.to_string(), text);
+            assert_eq!(pattern.to_string().parse::<FilePattern>().unwrap(), pattern, "{text}");
+        }
+    }
+
+    #[test]
+    fn arguments_are_only_quoted_when_they_have_to_be() {
+        for (arg, written) in [
+            (r"C:\Temp\*.tmp", r"C:\Temp\*.tmp"),
+            ("a, b", r#""a, b""#),
+            ("(draft)", r#""(draft)""#),
+            (r#"say "hi""#, r#""say \"hi\"""#),
+            (r#"a\"b"#, r#""a\\\"b""#),
+            (" padded ", r#"" padded ""#),
+            ("", r#""""#)
+        ] {
+            let pattern = FilePattern::Glob(arg.into());
+            assert_eq!(pattern.to_string(), format!("glob({written})"));
+            assert_eq!(pattern.to_string().parse::<FilePattern>().unwrap(), pattern, "{arg}");
+        }
+    }
+
+    #[test]
+    fn expressions_are_written_as_composite_patterns() {
+        let pattern: FilePattern = "ext(iso) and not contains(keep) or dir".parse().unwrap();
+        assert_eq!(pattern.to_string(), "any_of(all(ext(iso), not(contains(keep))), dir)");
+        assert_eq!(pattern.to_string().parse::<FilePattern>().unwrap(), pattern);
+    }
+}
@@ -0,0 +1,77 @@
+//! Keeping the newest of a set of files.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{FilePattern, MatchContext};
+
+
+/// Finds the `count` most recently modified entries of `directory` that
+/// match `pattern`.
+///
+/// Entries whose modification time can't be read are considered older than
+/// any other, and ties are broken by name so that the result doesn't depend
+/// on the order the entries are listed in.
+pub(crate) fn newest(
+    directory: &Path,
+    count: usize,
+    pattern: &FilePattern,
+    context: &MatchContext
+) -> HashSet<PathBuf> {
+    let mut entries: Vec<(SystemTime, PathBuf)> = directory
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| pattern.matches_in(path, context))
+        .map(|path| {
+            let modified = path
+                .symlink_metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (modified, path)
+        })
+        .collect();
+
+    entries.sort_by(|a, b| b.cmp(a));
+    entries.into_iter().take(count).map(|(_, path)| path).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::time::Duration;
+    use crate::fs_utils::{test_dir, MatchOptions};
+    use super::*;
+
+    #[test]
+    fn all_but_the_newest_entries_match() {
+        let directory = test_dir("keep-newest");
+        for (name, age) in [("backup-1", 1), ("backup-2", 2), ("backup-3", 3), ("backup-4", 3), ("notes", 9)] {
+            fs::write(directory.join(name), "").unwrap();
+            let file = File::options().write(true).open(directory.join(name)).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(age * 1_000)).unwrap();
+        }
+
+        let pattern: FilePattern = "keep_newest(2, glob(backup-*))".parse().unwrap();
+        let context = MatchContext::new(&directory, MatchOptions::default());
+        let matches = |name: &str| pattern.matches_in(&directory.join(name), &context);
+        assert!(matches("backup-1"));
+        assert!(matches("backup-2"));
+        assert!(!matches("backup-3"));
+        assert!(!matches("backup-4"));
+        // newer, but not one of the entries that are counted
+        assert!(!matches("notes"));
+
+        // of entries modified at the same time, the name decides
+        let pattern: FilePattern = "keep_newest(1, glob(backup-*))".parse().unwrap();
+        let context = MatchContext::new(&directory, MatchOptions::default());
+        assert!(pattern.matches_in(&directory.join("backup-3"), &context));
+        assert!(!pattern.matches_in(&directory.join("backup-4"), &context));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
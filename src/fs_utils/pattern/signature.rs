@@ -0,0 +1,60 @@
+//! Matching files by the bytes they start with.
+//!
+//! Signatures are written in hexadecimal, such as `4D5A` for the `MZ` that
+//! Windows executables start with. Spaces between the bytes are allowed, as
+//! in `50 4B 03 04`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use serde::{Deserialize, Deserializer};
+
+
+/// Checks whether the file at `path` starts with `signature`.
+///
+/// Directories, links and files that can't be read never match.
+pub(crate) fn starts_with(path: &Path, signature: &[u8]) -> bool {
+    if !path.symlink_metadata().is_ok_and(|m| m.is_file()) {
+        return false;
+    }
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+
+    let mut start = Vec::with_capacity(signature.len());
+    file.take(signature.len() as u64)
+        .read_to_end(&mut start)
+        .is_ok_and(|_| start == signature)
+}
+
+/// Parses a signature from hexadecimal, returning the reason if it isn't
+/// valid.
+pub(crate) fn parse(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: String = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.is_empty() {
+        return Err("signature must not be empty".into());
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("\"{hex}\" has an odd number of hexadecimal digits"));
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            digits.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| format!("\"{hex}\" isn't hexadecimal"))
+        })
+        .collect()
+}
+
+/// Formats a signature into hexadecimal that [`parse`] accepts.
+pub(crate) fn format(signature: &[u8]) -> String {
+    signature.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+/// Deserializes a signature from hexadecimal.
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    parse(&hex).map_err(serde::de::Error::custom)
+}
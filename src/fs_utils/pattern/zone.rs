@@ -0,0 +1,28 @@
+//! Recognizing files downloaded from the internet.
+//!
+//! Windows marks downloaded files and attachments with a `Zone.Identifier`
+//! alternate data stream, known as the Mark of the Web, which is what makes
+//! it warn before opening them.
+
+use std::path::Path;
+
+
+/// Checks whether the file at `path` has a `Zone.Identifier` stream.
+#[cfg(windows)]
+pub(crate) fn is_downloaded(path: &Path) -> bool {
+    use std::ffi::OsString;
+
+    if !path.symlink_metadata().is_ok_and(|m| m.is_file()) {
+        return false;
+    }
+    let mut stream = OsString::from(path.as_os_str());
+    stream.push(":Zone.Identifier");
+    Path::new(&stream).metadata().is_ok()
+}
+
+/// Checks whether the file at `path` has a `Zone.Identifier` stream, which
+/// only exists on Windows.
+#[cfg(not(windows))]
+pub(crate) fn is_downloaded(_path: &Path) -> bool {
+    false
+}
@@ -0,0 +1,114 @@
+//! Keeping files and directories aside for a while before removing them.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::{move_into, FilePattern};
+
+
+/// The date format of the subdirectories of a quarantine, for
+/// [`FilePattern::NameDate`].
+const BATCH_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+
+/// Moves a file or directory into the quarantine in `directory`, returning
+/// where it ended up.
+///
+/// The entry is moved into a subdirectory of the quarantine named after the
+/// current time, such as `2024-05-01_14-30-00`, in UTC, using
+/// [`move_into`]. Those subdirectories can then be purged once they're
+/// older than a grace period, without depending on when the entries in them
+/// were last modified. See [`purge_pattern`].
+///
+/// # Errors
+///
+/// This function returns an error if the entry can't be moved, see
+/// [`move_into`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::quarantine;
+///
+/// let moved_to = quarantine(r"C:\Users\user\Desktop\notes.txt", r"D:\Quarantine").unwrap();
+/// ```
+pub fn quarantine<P: AsRef<Path>, D: AsRef<Path>>(
+    path: P,
+    directory: D
+) -> Result<PathBuf, ActionError> {
+    let now = OffsetDateTime::now_utc();
+    let batch = format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        now.year(),
+        u8::from(now.month()),
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second()
+    );
+    move_into(path, directory.as_ref().join(batch))
+}
+
+/// A pattern matching the subdirectories of a quarantine that were
+/// quarantined more than `grace` ago.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::fs_utils::purge_pattern;
+/// use std::time::Duration;
+///
+/// let pattern = purge_pattern(Duration::from_secs(7 * 24 * 60 * 60));
+/// assert_eq!(pattern.to_string(), "all(dir, name_date(%Y-%m-%d_%H-%M-%S, 1w))");
+/// ```
+pub fn purge_pattern(grace: Duration) -> FilePattern {
+    FilePattern::All(vec![
+        FilePattern::IsDir,
+        FilePattern::NameDate(BATCH_FORMAT.into(), grace)
+    ])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::fs_utils::test_dir;
+
+    #[test]
+    fn quarantined_entries_are_purged_after_the_grace_period() {
+        let directory = test_dir("quarantine-purge");
+        let quarantined = directory.join("quarantine");
+        fs::write(directory.join("notes.txt"), "").unwrap();
+
+        let moved_to = quarantine(directory.join("notes.txt"), &quarantined).unwrap();
+        assert!(moved_to.exists());
+        let batch = moved_to.parent().unwrap();
+        assert_eq!(batch.parent(), Some(quarantined.as_path()));
+        assert!(purge_pattern(Duration::ZERO).matches(batch));
+        assert!(!purge_pattern(Duration::from_secs(60 * 60)).matches(batch));
+        // only whole batches are purged
+        assert!(!purge_pattern(Duration::ZERO).matches(&moved_to));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn quarantined_entries_never_replace_others() {
+        let directory = test_dir("quarantine-names");
+        let quarantined = directory.join("quarantine");
+        fs::write(directory.join("notes.txt"), "first").unwrap();
+        let first = quarantine(directory.join("notes.txt"), &quarantined).unwrap();
+        fs::write(directory.join("notes.txt"), "second").unwrap();
+        // within the same second, both end up in the same batch
+        let second = quarantine(directory.join("notes.txt"), &quarantined).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(first).unwrap(), "first");
+        assert_eq!(fs::read_to_string(second).unwrap(), "second");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
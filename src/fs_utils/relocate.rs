@@ -0,0 +1,365 @@
+//! Moving files and directories to other directories.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use time::OffsetDateTime;
+
+use crate::fs_utils::error::{self, ActionError};
+use crate::fs_utils::{remove, remove_empty_parents, write_date};
+
+
+/// Where [`Action::MoveTo`](super::Action::MoveTo) moves entries.
+///
+/// Entries are moved into the `directory`. If `keep_structure` is set,
+/// entries found in subdirectories of the directory being cleaned are moved
+/// into the same subdirectories of the `directory`, see
+/// [`move_keeping_structure`].
+///
+/// In configuration files, targets are written as the directory, as in
+/// `move_to = "~/Staging"`, or as a table if the structure is kept, as in
+/// `move_to = { directory = "~/Staging", keep_structure = true }`.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::fs_utils::MoveTarget;
+/// use std::path::Path;
+///
+/// let target: MoveTarget = serde_json::from_str(r#""D:/Staging""#).unwrap();
+/// assert_eq!(target.directory, Path::new("D:/Staging"));
+/// assert!(!target.keep_structure);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveTarget {
+    pub directory: PathBuf,
+    pub keep_structure: bool
+}
+
+impl<P: Into<PathBuf>> From<P> for MoveTarget {
+    /// A target that moves entries straight into `directory`.
+    fn from(directory: P) -> Self {
+        MoveTarget { directory: directory.into(), keep_structure: false }
+    }
+}
+
+/// How targets are written in configuration files.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum MoveTargetRepr {
+    Directory(PathBuf),
+    Table {
+        directory: PathBuf,
+        #[serde(default)]
+        keep_structure: bool
+    }
+}
+
+impl Serialize for MoveTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self.keep_structure {
+            true => MoveTargetRepr::Table { directory: self.directory.clone(), keep_structure: true },
+            false => MoveTargetRepr::Directory(self.directory.clone())
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MoveTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match MoveTargetRepr::deserialize(deserializer)? {
+            MoveTargetRepr::Directory(directory) => MoveTarget::from(directory),
+            MoveTargetRepr::Table { directory, keep_structure } => MoveTarget { directory, keep_structure }
+        })
+    }
+}
+
+
+/// Moves a file or directory into `directory`, returning where it ended up.
+///
+/// The directory is created if it doesn't exist. If it already contains an
+/// entry with the same name, a number is added to the name, so that
+/// `report.pdf` becomes `report (2).pdf`, and nothing is ever overwritten.
+/// An entry that can't simply be renamed into the directory because it's on
+/// another drive is copied there and then removed.
+///
+/// # Errors
+///
+/// This function returns an error if the directory can't be created or the
+/// entry can't be moved, copied or removed. If copying fails, whatever was
+/// copied is removed again and the original is left alone. Unlike
+/// [`remove`], it's an error if `path` doesn't exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::move_into;
+///
+/// let moved_to = move_into(r"C:\Users\user\Desktop\notes.txt", r"D:\Staging").unwrap();
+/// ```
+pub fn move_into<P: AsRef<Path>, D: AsRef<Path>>(
+    path: P,
+    directory: D
+) -> Result<PathBuf, ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("move", path, e);
+
+    fs::create_dir_all(directory.as_ref()).map_err(error)?;
+    let target = free_path(directory.as_ref(), path).map_err(error)?;
+    match fs::rename(path, &target) {
+        Ok(()) => Ok(target),
+
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_all(path, &target) {
+                let _ = remove(&target);
+                return Err(error(e));
+            }
+            remove(path)?;
+            Ok(target)
+        }
+
+        Err(e) => Err(error(e))
+    }
+}
+
+/// Moves a file or directory found in `root` or one of its subdirectories
+/// into the same subdirectory of `directory`, returning where it ended up.
+///
+/// An entry at `root/photos/2024/beach.jpg` is moved to
+/// `directory/photos/2024/beach.jpg`, creating the subdirectories it needs.
+/// Apart from that, entries are moved like [`move_into`] moves them, so a
+/// number is added to names that are taken. If moving fails, the
+/// subdirectories that were created for it are removed again if they're
+/// still empty.
+///
+/// # Errors
+///
+/// This function returns an error if `path` isn't inside `root` or if the
+/// entry can't be moved, see [`move_into`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::move_keeping_structure;
+///
+/// let moved_to = move_keeping_structure(
+///     r"C:\Users\user\Pictures\2024\beach.jpg",
+///     r"C:\Users\user\Pictures",
+///     r"D:\Pictures"
+/// ).unwrap();
+/// ```
+pub fn move_keeping_structure<P: AsRef<Path>, R: AsRef<Path>, D: AsRef<Path>>(
+    path: P,
+    root: R,
+    directory: D
+) -> Result<PathBuf, ActionError> {
+    let (path, directory) = (path.as_ref(), directory.as_ref());
+    let relative = path
+        .parent()
+        .and_then(|parent| parent.strip_prefix(root.as_ref()).ok())
+        .ok_or_else(|| ActionError::new("move", path, io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("the entry isn't in \"{}\"", root.as_ref().display())
+        )))?;
+
+    let target = directory.join(relative);
+    move_into(path, &target).inspect_err(|_| {
+        // the name only stands in for the entry, so that every directory
+        // that was created is looked at
+        let _ = remove_empty_parents(target.join("entry"), directory);
+    })
+}
+
+/// Moves a file or directory into a subdirectory of `directory` named after
+/// the date it was last modified, returning where it ended up.
+///
+/// The `layout` says how the subdirectories are named, using the
+/// placeholders of [`FilePattern::NameDate`](super::FilePattern::NameDate)
+/// with `/` between the levels, so that with `%Y/%m`, a file last modified
+/// in May 2024 is moved into `2024/05`. Dates are in UTC. Apart from that,
+/// entries are moved like [`move_into`] moves them.
+///
+/// # Errors
+///
+/// This function returns an error if the layout isn't valid, if the date
+/// the entry was last modified can't be read or if it can't be moved, see
+/// [`move_into`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::move_by_date;
+///
+/// let moved_to = move_by_date(r"C:\Users\user\Documents\taxes.pdf", r"D:\Archive", "%Y/%m").unwrap();
+/// ```
+pub fn move_by_date<P: AsRef<Path>, D: AsRef<Path>>(
+    path: P,
+    directory: D,
+    layout: &str
+) -> Result<PathBuf, ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("move", path, e);
+
+    let modified = path.symlink_metadata().and_then(|m| m.modified()).map_err(error)?;
+    let subdirectory = layout_path(layout, modified.into())
+        .map_err(|reason| error(io::Error::new(io::ErrorKind::InvalidInput, reason)))?;
+    move_into(path, directory.as_ref().join(subdirectory))
+}
+
+/// Checks that a layout for [`move_by_date`] is valid, returning the reason
+/// if it isn't.
+pub(crate) fn check_layout(layout: &str) -> Result<(), String> {
+    layout_path(layout, OffsetDateTime::now_utc()).map(|_| ())
+}
+
+/// The relative path `layout` says entries from `date` go into.
+fn layout_path(layout: &str, date: OffsetDateTime) -> Result<PathBuf, String> {
+    let mut path = PathBuf::new();
+    for level in write_date(layout, date)?.split('/') {
+        // the entries must stay inside the directory they're moved into
+        match Path::new(level).components().collect::<Vec<_>>()[..] {
+            [Component::Normal(_)] => path.push(level),
+            _ => return Err(format!("\"{level}\" in \"{layout}\" isn't the name of a directory"))
+        }
+    }
+    Ok(path)
+}
+
+/// Finds a path in `directory` for `path` that isn't taken, adding a number
+/// to the name if it has to.
+pub(crate) fn free_path(directory: &Path, path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "the path doesn't have a name")
+    })?;
+
+    let mut candidate = directory.join(name);
+    let mut number = 2;
+    loop {
+        match candidate.symlink_metadata() {
+            Err(e) if error::not_found(&e) => return Ok(candidate),
+            Err(e) => return Err(e),
+            Ok(_) => {
+                candidate = directory.join(numbered(name, number));
+                number += 1;
+            }
+        }
+    }
+}
+
+/// `name` with `number` added to it, as in `"notes (2).txt"`, for when the
+/// name itself is taken.
+pub(crate) fn numbered(name: &OsStr, number: u32) -> String {
+    let stem = Path::new(name).file_stem().unwrap_or(name).to_string_lossy();
+    let extension = match Path::new(name).extension() {
+        // a name like ".bashrc" is all stem
+        Some(ext) if !stem.is_empty() => format!(".{}", ext.to_string_lossy()),
+        _ => String::new()
+    };
+    format!("{stem} ({number}){extension}")
+}
+
+/// Copies a file, a link or a whole directory to `target`.
+pub(crate) fn copy_all(path: &Path, target: &Path) -> io::Result<()> {
+    let metadata = path.symlink_metadata()?;
+    if metadata.is_dir() {
+        fs::create_dir(target)?;
+        for entry in path.read_dir()? {
+            let entry = entry?;
+            copy_all(&entry.path(), &target.join(entry.file_name()))?;
+        }
+        Ok(())
+    }
+    else if metadata.is_symlink() {
+        copy_link(path, target)
+    }
+    else {
+        fs::copy(path, target).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_link(path: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(path)?, target)
+}
+
+#[cfg(windows)]
+fn copy_link(path: &Path, target: &Path) -> io::Result<()> {
+    use std::os::windows::fs::{symlink_dir, symlink_file};
+
+    let link = fs::read_link(path)?;
+    match path.metadata().is_ok_and(|m| m.is_dir()) {
+        true => symlink_dir(link, target),
+        false => symlink_file(link, target)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn copy_link(_path: &Path, _target: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "links can't be copied on this platform"))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_utils::test_dir;
+
+    #[test]
+    fn moved_entries_never_replace_others() {
+        let directory = test_dir("move-names");
+        let target = directory.join("target");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("report.pdf"), "old").unwrap();
+        fs::write(target.join("report (2).pdf"), "older").unwrap();
+        fs::write(directory.join("report.pdf"), "new").unwrap();
+
+        let moved_to = move_into(directory.join("report.pdf"), &target).unwrap();
+        assert_eq!(moved_to, target.join("report (3).pdf"));
+        assert_eq!(fs::read_to_string(moved_to).unwrap(), "new");
+        assert_eq!(fs::read_to_string(target.join("report.pdf")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(target.join("report (2).pdf")).unwrap(), "older");
+        assert!(!directory.join("report.pdf").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn numbers_go_before_the_extension() {
+        assert_eq!(numbered(OsStr::new("notes.txt"), 2), "notes (2).txt");
+        assert_eq!(numbered(OsStr::new("archive.tar.gz"), 2), "archive.tar (2).gz");
+        assert_eq!(numbered(OsStr::new(".bashrc"), 3), ".bashrc (3)");
+        assert_eq!(numbered(OsStr::new("README"), 4), "README (4)");
+    }
+
+    #[test]
+    fn structure_is_kept_below_the_root() {
+        let directory = test_dir("move-structure");
+        let (root, target) = (directory.join("root"), directory.join("target"));
+        let path = root.join("photos/2024/beach.jpg");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+
+        let moved_to = move_keeping_structure(&path, &root, &target).unwrap();
+        assert_eq!(moved_to, target.join("photos/2024/beach.jpg"));
+        assert!(moved_to.exists());
+
+        // nothing is left behind for an entry that can't be moved
+        let missing = root.join("music/song.mp3");
+        assert!(move_keeping_structure(&missing, &root, &target).is_err());
+        assert!(!target.join("music").exists());
+        assert!(move_keeping_structure(&missing, root.join("photos"), &target).is_err());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn layouts_stay_inside_the_directory() {
+        let date = OffsetDateTime::from_unix_timestamp(1_714_521_600).unwrap();
+        assert_eq!(layout_path("%Y/%m", date).unwrap(), Path::new("2024").join("05"));
+        assert!(layout_path("%Y/../%m", date).is_err());
+        assert!(layout_path("/%Y", date).is_err());
+        assert!(layout_path("%Y//%m", date).is_err());
+    }
+}
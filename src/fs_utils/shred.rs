@@ -0,0 +1,182 @@
+//! Overwriting files before removing them.
+
+use std::fs::OpenOptions;
+use std::io::{self, Seek, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::fs_utils::error::{self, ActionError};
+use crate::fs_utils::remove;
+
+
+/// How many bytes are written at a time.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+
+/// Overwrites the contents of a file `passes` times and then removes it.
+///
+/// Every pass but the last overwrites the file with pseudo-random bytes,
+/// and the last one with zeros. Each pass is flushed to the disk before the
+/// next one starts. Directories are shredded along with every file in them,
+/// while links are only removed, since what they point to isn't theirs to
+/// shred.
+///
+/// This makes the old contents much harder to recover, but it's no
+/// guarantee: solid-state drives and file systems that write changes to new
+/// places, such as ZFS and Btrfs, can keep copies of the old contents
+/// elsewhere.
+///
+/// # Errors
+///
+/// This function returns an error if a file can't be opened for writing or
+/// written to, or if an entry can't be removed afterwards. Like
+/// [`remove`], no error is returned if `path` doesn't exist. An error is
+/// also returned without touching the entry if `passes` is zero, since
+/// nothing would be overwritten then.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::shred;
+///
+/// let result = shred(r"C:\Users\user\Downloads\bank-export.csv", 3);
+/// ```
+pub fn shred<P: AsRef<Path>>(path: P, passes: u32) -> Result<(), ActionError> {
+    let path = path.as_ref();
+    if passes == 0 {
+        return Err(ActionError::new("shred", path, io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "files have to be overwritten at least once"
+        )));
+    }
+    match path.symlink_metadata() {
+        Err(e) if error::not_found(&e) => return Ok(()),
+        Err(e) => return Err(ActionError::new("shred", path, e)),
+        Ok(_) => {}
+    }
+    overwrite_all(path, passes)?;
+    remove(path).map_err(ActionError::from)
+}
+
+/// Overwrites every file at or under `path`.
+fn overwrite_all(path: &Path, passes: u32) -> Result<(), ActionError> {
+    let error = |e| ActionError::new("shred", path, e);
+
+    let metadata = path.symlink_metadata().map_err(error)?;
+    if metadata.is_dir() {
+        for entry in path.read_dir().map_err(error)? {
+            overwrite_all(&entry.map_err(error)?.path(), passes)?;
+        }
+        Ok(())
+    }
+    else if metadata.is_file() {
+        overwrite(path, metadata.len(), passes).map_err(error)
+    }
+    else {
+        Ok(())
+    }
+}
+
+/// Overwrites the first `len` bytes of a file.
+fn overwrite(path: &Path, len: u64, passes: u32) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let mut random = Random::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    for pass in 1..=passes {
+        file.rewind()?;
+        let mut left = len;
+        while left > 0 {
+            let chunk = usize::try_from(left).map_or(BUFFER_SIZE, |left| left.min(BUFFER_SIZE));
+            match pass == passes {
+                true => buffer[..chunk].fill(0),
+                false => random.fill(&mut buffer[..chunk])
+            }
+            file.write_all(&buffer[..chunk])?;
+            left -= chunk as u64;
+        }
+        file.sync_all()?;
+    }
+    Ok(())
+}
+
+
+/// A fast source of bytes that look random, which is all that overwriting
+/// needs.
+struct Random(u64);
+
+impl Random {
+    fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        // xorshift gets stuck at zero
+        Random(seed | 1)
+    }
+
+    fn fill(&mut self, buffer: &mut [u8]) {
+        for chunk in buffer.chunks_mut(8) {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            chunk.copy_from_slice(&self.0.to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::fs_utils::test_dir;
+
+    #[test]
+    fn shredding_without_passes_keeps_the_file() {
+        let directory = test_dir("shred-no-passes");
+        let path = directory.join("secret.txt");
+        fs::write(&path, "secret").unwrap();
+
+        let e = shred(&path, 0).unwrap_err();
+        assert_eq!(e.io_source().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "secret");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn every_file_is_overwritten_with_zeros_in_the_end() {
+        let directory = test_dir("shred-overwrite");
+        let secrets = directory.join("secrets");
+        fs::create_dir_all(secrets.join("more")).unwrap();
+        fs::write(secrets.join("a.txt"), "secret").unwrap();
+        // more than fits in the buffer at once
+        fs::write(secrets.join("more/b.bin"), vec![1; BUFFER_SIZE + 10]).unwrap();
+
+        overwrite_all(&secrets, 3).unwrap();
+        assert_eq!(fs::read(secrets.join("a.txt")).unwrap(), [0; 6]);
+        assert_eq!(fs::read(secrets.join("more/b.bin")).unwrap(), vec![0; BUFFER_SIZE + 10]);
+
+        shred(&secrets, 1).unwrap();
+        assert!(!secrets.exists());
+        // like removing, shredding what isn't there is fine
+        shred(&secrets, 1).unwrap();
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_removed_without_shredding_their_targets() {
+        let directory = test_dir("shred-link");
+        let (target, link) = (directory.join("target.txt"), directory.join("link"));
+        fs::write(&target, "not mine").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        shred(&link, 1).unwrap();
+        assert!(link.symlink_metadata().is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "not mine");
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+//! Flagging files and directories for review by renaming them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::relocate::free_path;
+
+
+/// Renames a file or directory by adding `prefix` to the start of its name
+/// and `suffix` to the end of its stem, returning the new path.
+///
+/// With a prefix of `STALE__`, `report.pdf` becomes `STALE__report.pdf`, and
+/// with a suffix of `.old`, it becomes `report.old.pdf`. An entry that's
+/// already tagged is left as it is, so tagging it again on a later run
+/// doesn't keep making its name longer. If the new name is taken, a number
+/// is added to it like [`move_into`](super::move_into) does.
+///
+/// # Errors
+///
+/// This function returns an error if the entry can't be renamed, for
+/// example because it doesn't exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::tag;
+///
+/// let tagged = tag(r"C:\Users\user\Desktop\notes.txt", "STALE__", "").unwrap();
+/// ```
+pub fn tag<P: AsRef<Path>>(path: P, prefix: &str, suffix: &str) -> Result<PathBuf, ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("tag", path, e);
+
+    path.symlink_metadata().map_err(error)?;
+    if is_tagged(path, prefix, suffix) {
+        return Ok(path.to_path_buf());
+    }
+
+    let (stem, extension) = split_name(path);
+    let name = format!("{prefix}{stem}{suffix}{extension}");
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let target = free_path(directory, Path::new(&name)).map_err(error)?;
+    fs::rename(path, &target).map_err(error)?;
+    Ok(target)
+}
+
+/// Checks whether the name of `path` already has the prefix and suffix.
+fn is_tagged(path: &Path, prefix: &str, suffix: &str) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let Some(rest) = name.strip_prefix(prefix) else {
+        return false;
+    };
+    // a suffix with a dot in it can look like the extension of a name that
+    // didn't have one
+    let (stem, _) = split_name(Path::new(rest));
+    stem.ends_with(suffix) || rest.ends_with(suffix)
+}
+
+/// Splits the name of `path` into its stem and its extension, including the
+/// dot.
+fn split_name(path: &Path) -> (String, String) {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    match name.rsplit_once('.') {
+        // a name like ".bashrc" is all stem
+        Some((stem, extension)) if !stem.is_empty() => (stem.to_string(), format!(".{extension}")),
+        _ => (name.to_string(), String::new())
+    }
+}
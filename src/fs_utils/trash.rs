@@ -0,0 +1,315 @@
+//! Moving files and directories to the trash rather than removing them.
+//!
+//! On Windows, entries are sent to the Recycle Bin. On macOS, they're moved
+//! into the `.Trash` directory of the user's home directory, and on other
+//! Unix systems into the trash described by the FreeDesktop.org trash
+//! specification, which is what Linux desktops use.
+
+use std::path::{Path, PathBuf};
+
+use crate::fs_utils::error::{self, ActionError};
+
+
+/// Moves a file or directory to the trash, from where it can be restored.
+///
+/// # Errors
+///
+/// This function returns an error if the entry can't be moved to the trash,
+/// for example because the trash can't be found or because the entry is on
+/// a different drive than the trash on a platform that would require
+/// copying it. Like [`remove`](super::remove), no error is returned if
+/// `path` doesn't exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::trash;
+///
+/// let result = trash(r"C:\path\to\dir\or\file");
+/// ```
+pub fn trash<P: AsRef<Path>>(path: P) -> Result<(), ActionError> {
+    trash_to(path.as_ref()).map(|_| ())
+}
+
+/// Moves a file or directory to the trash like [`trash`], returning where it
+/// ended up if it's somewhere it can simply be moved back from.
+pub(crate) fn trash_to(path: &Path) -> Result<Option<PathBuf>, ActionError> {
+    if path.symlink_metadata().is_err_and(|e| error::not_found(&e)) {
+        return Ok(None);
+    }
+    platform::trash(path).map_err(|e| ActionError::new("move to the trash", path, e))
+}
+
+/// Forgets about an entry that has been moved back out of the trash, if the
+/// trash keeps records of its entries.
+pub(crate) fn forget(moved_to: &Path) {
+    platform::forget(moved_to);
+}
+
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use windows_sys::Win32::UI::Shell::{
+        SHFileOperationW,
+        FOF_ALLOWUNDO,
+        FOF_NOCONFIRMATION,
+        FOF_NOERRORUI,
+        FOF_SILENT,
+        FO_DELETE,
+        SHFILEOPSTRUCTW
+    };
+
+    pub(super) fn trash(path: &Path) -> io::Result<Option<PathBuf>> {
+        // the shell can only undo the deletion of absolute paths
+        let path = std::path::absolute(path)?;
+        // the list of paths must end with two nulls
+        let from: Vec<u16> = path.as_os_str().encode_wide().chain([0, 0]).collect();
+        let mut operation = SHFILEOPSTRUCTW {
+            wFunc: FO_DELETE,
+            pFrom: from.as_ptr(),
+            fFlags: (FOF_ALLOWUNDO | FOF_NOCONFIRMATION | FOF_NOERRORUI | FOF_SILENT) as u16,
+            ..Default::default()
+        };
+
+        // SAFETY: the operation is fully initialized and the list of paths
+        // outlives the call
+        let status = unsafe { SHFileOperationW(&mut operation) };
+        match (status, operation.fAnyOperationsAborted) {
+            // where the entry ends up in the Recycle Bin isn't known
+            (0, 0) => Ok(None),
+            (0, _) => Err(io::Error::new(io::ErrorKind::Interrupted, "the operation was aborted")),
+            (code, _) => Err(io::Error::other(format!("the shell returned error code {code:#x}")))
+        }
+    }
+
+    pub(super) fn forget(_moved_to: &Path) {}
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    pub(super) fn trash(path: &Path) -> io::Result<Option<PathBuf>> {
+        let home = std::env::var_os("HOME")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME isn't set"))?;
+        let trash = PathBuf::from(home).join(".Trash");
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        // like Finder, add a number to the name if it's taken
+        let target = (1..)
+            .map(|n| match n {
+                1 => trash.join(&*name),
+                n => trash.join(format!("{name} {n}"))
+            })
+            .find(|target| target.symlink_metadata().is_err())
+            .unwrap_or_default();
+        super::rename(path, &target)?;
+        Ok(Some(target))
+    }
+
+    pub(super) fn forget(_moved_to: &Path) {}
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use std::fs::{self, OpenOptions};
+    use std::io::{self, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use time::OffsetDateTime;
+
+    pub(super) fn trash(path: &Path) -> io::Result<Option<PathBuf>> {
+        trash_into(path, &trash_dir()?)
+    }
+
+    /// Moves an entry into the trash at `trash`.
+    fn trash_into(path: &Path, trash: &Path) -> io::Result<Option<PathBuf>> {
+        let path = std::path::absolute(path)?;
+        let (files, info) = (trash.join("files"), trash.join("info"));
+        fs::create_dir_all(&files)?;
+        fs::create_dir_all(&info)?;
+
+        // the info file is created first to claim the name
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let (target, info_path, mut info_file) = (1..)
+            .map(|n| match n {
+                1 => name.to_string(),
+                n => format!("{name}.{n}")
+            })
+            // entries without an info file are still never replaced
+            .filter(|name| files.join(name).symlink_metadata().is_err())
+            .find_map(|name| {
+                let info_path = info.join(format!("{name}.trashinfo"));
+                let file = OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&info_path);
+                match file {
+                    Ok(file) => Some(Ok((files.join(&name), info_path, file))),
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => None,
+                    Err(e) => Some(Err(e))
+                }
+            })
+            .unwrap_or_else(|| Err(io::Error::other("no free name in the trash")))?;
+
+        // the specification asks for local time, but UTC is all that can be
+        // read reliably from a program with several threads
+        let now = OffsetDateTime::now_utc();
+        let written = writeln!(
+            info_file,
+            "[Trash Info]\nPath={}\nDeletionDate={:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            encode(&path),
+            now.year(),
+            u8::from(now.month()),
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second()
+        );
+
+        match written.and_then(|_| super::rename(&path, &target)) {
+            Ok(()) => Ok(Some(target)),
+            Err(e) => {
+                let _ = fs::remove_file(info_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Removes the info file of an entry that was in the trash.
+    pub(super) fn forget(moved_to: &Path) {
+        let (Some(files), Some(name)) = (moved_to.parent(), moved_to.file_name()) else {
+            return;
+        };
+        let in_trash = files.parent()
+            .and_then(Path::file_name)
+            .is_some_and(|trash| trash.to_string_lossy().contains("Trash"));
+        if in_trash && files.file_name().is_some_and(|dir| dir == "files") {
+            let mut info = name.to_os_string();
+            info.push(".trashinfo");
+            let _ = fs::remove_file(files.with_file_name("info").join(info));
+        }
+    }
+
+    /// The trash in the user's home directory.
+    fn trash_dir() -> io::Result<PathBuf> {
+        match (std::env::var_os("XDG_DATA_HOME"), std::env::var_os("HOME")) {
+            (Some(data), _) if !data.is_empty() => Ok(PathBuf::from(data).join("Trash")),
+            (_, Some(home)) => Ok(PathBuf::from(home).join(".local/share/Trash")),
+            _ => Err(io::Error::new(io::ErrorKind::NotFound, "neither XDG_DATA_HOME nor HOME is set"))
+        }
+    }
+
+    /// Percent-encodes a path for an info file.
+    fn encode(path: &Path) -> String {
+        path.as_os_str()
+            .as_bytes()
+            .iter()
+            .map(|&byte| match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~'
+                => char::from(byte).to_string(),
+
+                byte
+                => format!("%{byte:02X}")
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::fs_utils::test_dir;
+
+        #[test]
+        fn trashed_entries_never_replace_others() {
+            let directory = test_dir("trash-names");
+            let trash = directory.join("Trash");
+            let path = directory.join("notes.txt");
+
+            fs::write(&path, "first").unwrap();
+            let first = trash_into(&path, &trash).unwrap().unwrap();
+            fs::write(&path, "second").unwrap();
+            let second = trash_into(&path, &trash).unwrap().unwrap();
+            // left behind by something that didn't write an info file
+            fs::write(trash.join("files/notes.txt.3"), "third").unwrap();
+            fs::write(&path, "fourth").unwrap();
+            let fourth = trash_into(&path, &trash).unwrap().unwrap();
+
+            assert!(!path.exists());
+            assert_eq!(first, trash.join("files/notes.txt"));
+            assert_eq!(second, trash.join("files/notes.txt.2"));
+            assert_eq!(fourth, trash.join("files/notes.txt.4"));
+            assert_eq!(fs::read_to_string(first).unwrap(), "first");
+            assert_eq!(fs::read_to_string(second).unwrap(), "second");
+            assert_eq!(fs::read_to_string(trash.join("files/notes.txt.3")).unwrap(), "third");
+            assert_eq!(fs::read_to_string(fourth).unwrap(), "fourth");
+
+            fs::remove_dir_all(directory).unwrap();
+        }
+
+        #[test]
+        fn trashed_entries_can_be_restored() {
+            let directory = test_dir("trash-info");
+            let trash = directory.join("Trash");
+            let path = directory.join("a b.txt");
+            fs::write(&path, "").unwrap();
+
+            let moved_to = trash_into(&path, &trash).unwrap().unwrap();
+            let info = fs::read_to_string(trash.join("info/a b.txt.trashinfo")).unwrap();
+            assert!(info.contains(&format!("\nPath={}\n", encode(&path))), "{info}");
+            assert!(info.contains("%20b.txt"), "{info}");
+
+            fs::rename(&moved_to, &path).unwrap();
+            forget(&moved_to);
+            assert!(!trash.join("info/a b.txt.trashinfo").exists());
+
+            fs::remove_dir_all(directory).unwrap();
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    pub(super) fn trash(_path: &Path) -> io::Result<Option<PathBuf>> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "there's no trash on this platform"))
+    }
+
+    pub(super) fn forget(_moved_to: &Path) {}
+}
+
+
+/// Moves an entry into the trash, explaining the error if it's on another
+/// drive.
+#[cfg(unix)]
+fn rename(from: &Path, to: &Path) -> std::io::Result<()> {
+    use std::io;
+
+    std::fs::rename(from, to).map_err(|e| match e.kind() {
+        io::ErrorKind::CrossesDevices => io::Error::new(
+            e.kind(),
+            "the trash is on a different drive, so the entry would have to be copied"
+        ),
+        _ => e
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_utils::test_dir;
+
+    #[test]
+    fn missing_entries_are_already_gone() {
+        let directory = test_dir("trash-missing");
+        assert_eq!(trash_to(&directory.join("missing")).unwrap(), None);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
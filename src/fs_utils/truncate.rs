@@ -0,0 +1,85 @@
+//! Emptying files without removing them.
+
+use std::fs::OpenOptions;
+use std::path::Path;
+
+use crate::fs_utils::error::{self, ActionError};
+
+
+/// Empties a file, or every file in a directory, leaving the files
+/// themselves in place.
+///
+/// This is meant for log files that programs keep open or expect to exist,
+/// which they might not recreate if they were removed. Files a directory
+/// contains are emptied along with those in its subdirectories, and links
+/// are left alone, since what they point to isn't theirs to empty.
+///
+/// # Errors
+///
+/// This function returns an error if a file can't be opened for writing or
+/// emptied. Like [`remove`](super::remove), no error is returned if `path`
+/// doesn't exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::truncate;
+///
+/// let result = truncate("/var/log/myapp/debug.log");
+/// ```
+pub fn truncate<P: AsRef<Path>>(path: P) -> Result<(), ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("truncate", path, e);
+
+    let metadata = match path.symlink_metadata() {
+        Err(e) if error::not_found(&e) => return Ok(()),
+        other => other.map_err(error)?
+    };
+    if metadata.is_dir() {
+        for entry in path.read_dir().map_err(error)? {
+            truncate(entry.map_err(error)?.path())?;
+        }
+    }
+    else if metadata.is_file() && metadata.len() > 0 {
+        OpenOptions::new().write(true).open(path).and_then(|file| file.set_len(0)).map_err(error)?;
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::fs_utils::test_dir;
+
+    #[test]
+    fn files_are_emptied_but_kept() {
+        let directory = test_dir("truncate");
+        fs::create_dir_all(directory.join("logs/old")).unwrap();
+        fs::write(directory.join("logs/app.log"), "lines").unwrap();
+        fs::write(directory.join("logs/old/app.log"), "more lines").unwrap();
+
+        truncate(directory.join("logs")).unwrap();
+        assert_eq!(fs::read(directory.join("logs/app.log")).unwrap(), b"");
+        assert_eq!(fs::read(directory.join("logs/old/app.log")).unwrap(), b"");
+        truncate(directory.join("missing.log")).unwrap();
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_left_alone() {
+        let directory = test_dir("truncate-link");
+        let (target, link) = (directory.join("target.log"), directory.join("link.log"));
+        fs::write(&target, "not mine").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        truncate(&link).unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "not mine");
+        assert!(link.symlink_metadata().unwrap().is_symlink());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+//! Uploading files and directories to a server before removing them.
+//!
+//! Uploads are done with [curl](https://curl.se), which comes with Windows
+//! 10 and later, macOS and most Linux distributions, and which has to be
+//! installed elsewhere. Entries can be uploaded to WebDAV servers, which
+//! includes most cloud storage that can be mounted as a drive, and to
+//! storage that's compatible with Amazon S3.
+
+use std::env;
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::path::{Component, Path};
+use std::process::{self, Stdio};
+use serde::{Deserialize, Serialize};
+
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::{numbered, remove};
+
+
+/// Where curl writes the responses of the server, which aren't needed.
+const NULL_DEVICE: &str = match cfg!(windows) {
+    true => "NUL",
+    false => "/dev/null"
+};
+
+
+/// Where entries are uploaded to.
+///
+/// Entries are uploaded into the directory at `url`, such as
+/// `https://dav.example.com/backup/`. If that's on a server that needs a
+/// password, `user` is the user name, and the password is read from the
+/// environment variable named by `password_env`, so that it doesn't have to
+/// be written in the configuration.
+///
+/// If `s3_region` is set, the server is taken to be compatible with Amazon
+/// S3 and requests are signed for that region, with the access key as the
+/// `user` and the secret key as the password. The `url` is then the bucket
+/// and the prefix to upload to, such as
+/// `https://my-bucket.s3.eu-north-1.amazonaws.com/downloads/`.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::fs_utils::UploadTarget;
+///
+/// let target: UploadTarget = toml::from_str(r#"
+///     url = "https://dav.example.com/backup/"
+///     user = "me"
+///     password_env = "DAV_PASSWORD"
+/// "#).unwrap();
+/// assert_eq!(target.s3_region, None);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct UploadTarget {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_env: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3_region: Option<String>
+}
+
+
+/// Uploads a file or directory to `target` and then removes it.
+///
+/// The entry is uploaded to the same place under the target as it is under
+/// `root`, so a file in a subdirectory of the cleaned directory ends up in a
+/// directory of the same name on the server. Directories are uploaded along
+/// with everything in them, into a directory on the server that's created
+/// if it doesn't exist. Files never replace files that are already on the
+/// server, and get a number added to their names instead, like
+/// `notes (2).txt`, as long as the server supports the `If-None-Match`
+/// header, as WebDAV servers and S3 do. The entry is only removed once the
+/// server has accepted all of it. Links are uploaded as whatever they point
+/// to.
+///
+/// # Errors
+///
+/// This function returns an error if curl can't be run, if the password
+/// isn't set, if the server refuses or fails an upload, or if the entry
+/// can't be removed afterwards. Some of a directory can be left on the
+/// server if uploading the rest of it fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::{upload, UploadTarget};
+///
+/// let target = UploadTarget {
+///     url: "https://dav.example.com/backup/".into(),
+///     user: Some("me".into()),
+///     password_env: Some("DAV_PASSWORD".into()),
+///     s3_region: None
+/// };
+/// let result = upload(r"C:\Users\user\Desktop\notes.txt", r"C:\Users\user\Desktop", &target);
+/// ```
+pub fn upload<P: AsRef<Path>, R: AsRef<Path>>(path: P, root: R, target: &UploadTarget) -> Result<(), ActionError> {
+    let path = path.as_ref();
+    let error = |e| ActionError::new("upload", path, e);
+
+    path.metadata().map_err(error)?;
+    let password = match &target.password_env {
+        Some(name) => Some(env::var(name).map_err(|_| {
+            error(io::Error::new(io::ErrorKind::NotFound, format!("{name} isn't set")))
+        })?),
+        None => None
+    };
+    let mut directory = match target.url.ends_with('/') {
+        true => target.url.clone(),
+        false => format!("{}/", target.url)
+    };
+
+    let uploader = Uploader { target, password: password.as_deref() };
+    let relative = path
+        .parent()
+        .and_then(|parent| parent.strip_prefix(root.as_ref()).ok())
+        .unwrap_or(Path::new(""));
+    for component in relative.components() {
+        if let Component::Normal(name) = component {
+            directory = format!("{directory}{}/", encode(&name.to_string_lossy()));
+            uploader.make_directory(&directory).map_err(error)?;
+        }
+    }
+    uploader.upload(path, &directory, path.file_name().unwrap_or_default()).map_err(error)?;
+    remove(path).map_err(ActionError::from)
+}
+
+
+/// Uploads entries to a target with curl.
+struct Uploader<'a> {
+    target: &'a UploadTarget,
+    password: Option<&'a str>
+}
+
+impl Uploader<'_> {
+    /// Uploads an entry as `name` into the directory at `directory`, which
+    /// ends with a slash.
+    fn upload(&self, path: &Path, directory: &str, name: &OsStr) -> io::Result<()> {
+        if path.is_dir() {
+            let url = format!("{directory}{}/", encode(&name.to_string_lossy()));
+            self.make_directory(&url)?;
+            for entry in path.read_dir()? {
+                let entry = entry?;
+                self.upload(&entry.path(), &url, &entry.file_name())?;
+            }
+            return Ok(());
+        }
+
+        let mut candidate = name.to_string_lossy().into_owned();
+        let mut number = 2;
+        // the server refuses to replace a file that's already there
+        while !self.put(path, &format!("{directory}{}", encode(&candidate)))? {
+            candidate = numbered(name, number);
+            number += 1;
+        }
+        Ok(())
+    }
+
+    /// Uploads a file to `url` unless there's one there already, returning
+    /// whether it did.
+    fn put(&self, path: &Path, url: &str) -> io::Result<bool> {
+        let args = ["--upload-file".as_ref(), path.as_os_str(), "--header".as_ref(), "If-None-Match: *".as_ref()];
+        match self.curl(&args, url)? {
+            200..=299 => Ok(true),
+            412 => Ok(false),
+            status => Err(refused(status))
+        }
+    }
+
+    /// Creates the directory at `url` unless it exists already.
+    fn make_directory(&self, url: &str) -> io::Result<()> {
+        // S3 has no directories, only names with slashes in them
+        if self.target.s3_region.is_some() {
+            return Ok(());
+        }
+        // the directory can be left from an upload that failed halfway
+        match self.curl(&["--request".as_ref(), "MKCOL".as_ref()], url)? {
+            200..=299 | 405 => Ok(()),
+            status => Err(refused(status))
+        }
+    }
+
+    /// Runs curl with `args` for `url`, returning the status of the server's
+    /// response.
+    fn curl(&self, args: &[&OsStr], url: &str) -> io::Result<u16> {
+        let mut command = process::Command::new("curl");
+        command
+            .args(["--silent", "--show-error", "--output", NULL_DEVICE, "--write-out", "%{http_code}"])
+            .args(["--config", "-"])
+            .args(args)
+            .arg(url);
+        if let Some(region) = &self.target.s3_region {
+            command.arg("--aws-sigv4").arg(format!("aws:amz:{region}:s3"));
+            // S3 wants to know whether the contents are signed too
+            command.args(["--header", "x-amz-content-sha256: UNSIGNED-PAYLOAD"]);
+        }
+
+        // the credentials are passed through stdin to keep them out of the
+        // list of processes
+        let mut child = command.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        if let (Some(mut stdin), Some(user)) = (child.stdin.take(), &self.target.user) {
+            let credentials = format!("{user}:{}", self.password.unwrap_or_default());
+            writeln!(stdin, "user = \"{}\"", credentials.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!("curl exited with {}", output.status)));
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .map_err(|_| io::Error::other("curl didn't report the status of the response"))
+    }
+}
+
+/// The error for a server responding with `status`.
+fn refused(status: u16) -> io::Error {
+    io::Error::other(format!("the server responded with status {status}"))
+}
+
+/// Percent-encodes a name for a URL.
+fn encode(name: &str) -> String {
+    name.bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~'
+            => char::from(byte).to_string(),
+
+            byte
+            => format!("%{byte:02X}")
+        })
+        .collect()
+}
@@ -1,13 +1,305 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use folder_cleaner::config;
-use folder_cleaner::routine;
+use std::env;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use folder_cleaner::config::{self, Config, Format, LoadOptions};
+use folder_cleaner::config::watch::ConfigWatcher;
+use folder_cleaner::routine::Scheduler;
+
+use args::Args;
+
+mod args;
+mod autostart;
+
+/// How often the configuration file is checked for changes, and for
+/// routines that have been asked to run right away.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The directory in the state directory that requests to run routines right
+/// away are left in, in a directory for each configuration file.
+const REQUESTS_DIR: &str = "run-now";
 
 fn main() {
-    let routines = config::routines();
-    
-    for r in routines {
-        let t = routine::spawn_routine(r);
-        t.join().unwrap();
+    attach_console();
+    let args = match Args::from_env() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n\n{}", args::USAGE);
+            process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", args::USAGE);
+        return;
+    }
+    if args.install {
+        install(&args);
+        return;
+    }
+    if args.uninstall {
+        uninstall();
+        return;
+    }
+
+    let path = match args.config.map_or_else(config::default_path, Ok) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("failed to find the configuration: {e}");
+            process::exit(1);
+        }
+    };
+    let options = LoadOptions {
+        strict: args.strict,
+        profile: args.profile,
+        lenient: args.lenient
+    };
+    let mut config = match Config::load_with(&path, &options) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("failed to load configuration: {e}");
+            process::exit(1);
+        }
+    };
+
+    if args.print_config {
+        // problems are reported but don't stop the configuration from being
+        // printed, since seeing it helps with fixing them
+        check(&mut config, options.lenient);
+        if let Err(e) = config.to_writer(std::io::stdout(), Format::Toml) {
+            eprintln!("failed to print configuration: {e}");
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(name) = &args.undo {
+        undo(&config, name);
+        return;
+    }
+
+    if let Some(name) = &args.run_now {
+        request_run(&path, &config, name);
+        return;
+    }
+
+    let scheduler = Scheduler::new();
+    if check(&mut config, options.lenient) {
+        scheduler.update(config.into_routines());
+    }
+
+    let lenient = options.lenient;
+    let mut watcher = ConfigWatcher::with_options(&path, options);
+    loop {
+        thread::sleep(RELOAD_INTERVAL);
+        run_requested(&path, &scheduler);
+
+        let mut config = match watcher.poll() {
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                eprintln!("failed to reload configuration: {e}");
+                continue;
+            }
+            None => continue
+        };
+
+        // keep the old routines running rather than stopping everything
+        if check(&mut config, lenient) {
+            scheduler.update(config.into_routines());
+        }
+    }
+}
+
+/// Reports any problems with a configuration, returning whether it can be
+/// run.
+///
+/// Warnings are reported too, but they don't stop a configuration from
+/// running. In lenient mode, routines with problems are removed so that the
+/// rest of them can still run.
+fn check(config: &mut Config, lenient: bool) -> bool {
+    for w in config.warnings() {
+        eprintln!("warning: {w}");
+    }
+
+    if lenient {
+        for e in config.retain_valid() {
+            eprintln!("skipping invalid routine: {e}");
+        }
+        return true;
+    }
+
+    let errors = config.validate();
+    for e in &errors {
+        eprintln!("invalid configuration: {e}");
+    }
+    errors.is_empty()
+}
+
+/// Undoes the last run of the routine called `name`, exiting with an error
+/// if anything couldn't be restored.
+fn undo(config: &Config, name: &str) {
+    let Some(routine) = config.routines().iter().find(|r| r.name == name) else {
+        eprintln!("there's no routine called \"{name}\"");
+        process::exit(1);
+    };
+
+    match routine.undo_last_run() {
+        Ok(errors) if errors.is_empty() => {}
+        Ok(errors) => {
+            for e in errors {
+                eprintln!("{e}");
+            }
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("failed to undo the last run of {name}: {e}");
+            process::exit(1);
+        }
     }
 }
+
+/// Sets the program up to start with the same options when the user logs
+/// in, exiting with an error if it can't be.
+fn install(args: &Args) {
+    if let Err(e) = start_command(args).and_then(|command| Ok(autostart::install(&command)?)) {
+        eprintln!("failed to set the program up to start when you log in: {e}");
+        process::exit(1);
+    }
+    println!("the program will start whenever you log in");
+}
+
+/// The program and the options to start it with when the user logs in,
+/// which are those it was started with.
+fn start_command(args: &Args) -> Result<Vec<OsString>, Box<dyn Error>> {
+    let mut command = vec![env::current_exe()?.into_os_string()];
+    if let Some(path) = &args.config {
+        // the program isn't started in the same directory
+        command.extend(["--config".into(), std::path::absolute(path)?.into_os_string()]);
+    }
+    if let Some(profile) = &args.profile {
+        command.extend(["--profile".into(), profile.into()]);
+    }
+    if args.strict {
+        command.push("--strict".into());
+    }
+    if args.lenient {
+        command.push("--lenient".into());
+    }
+    Ok(command)
+}
+
+/// Stops the program from starting when the user logs in, exiting with an
+/// error if it can't be stopped.
+fn uninstall() {
+    match autostart::uninstall() {
+        Ok(true) => println!("the program won't start when you log in anymore"),
+        Ok(false) => println!("the program wasn't set up to start when you log in"),
+        Err(e) => {
+            eprintln!("failed to stop the program from starting when you log in: {e}");
+            process::exit(1);
+        }
+    }
+}
+
+/// Asks the running instance of the program with the configuration at
+/// `path` to run the routine called `name` right away, exiting with an error
+/// if the request can't be left for it.
+///
+/// The running instance only checks for requests every [`RELOAD_INTERVAL`],
+/// so the routine can take that long to start.
+fn request_run(path: &Path, config: &Config, name: &str) {
+    match config.routines().iter().find(|r| r.name == name) {
+        Some(routine) if !routine.enabled => {
+            eprintln!("{name} isn't enabled, so it can't be run");
+            process::exit(1);
+        }
+        Some(_) => {}
+        None => {
+            eprintln!("there's no routine called \"{name}\"");
+            process::exit(1);
+        }
+    }
+    if let Err(e) = write_request(path, name) {
+        eprintln!("failed to ask for {name} to run: {e}");
+        process::exit(1);
+    }
+}
+
+/// Leaves a request to run the routine called `name` in the requests
+/// directory of the configuration at `config`.
+fn write_request(config: &Path, name: &str) -> Result<(), Box<dyn Error>> {
+    let directory = requests_dir(config)?;
+    fs::create_dir_all(&directory)?;
+    let nanos = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let path = directory.join(format!("{}-{nanos}", process::id()));
+
+    // the request is only moved into place once it's complete, so that it
+    // can't be read half written
+    let partial = path.with_extension("tmp");
+    fs::write(&partial, name)?;
+    fs::rename(&partial, &path)?;
+    Ok(())
+}
+
+/// Runs the routines of the configuration at `config` that have been asked
+/// to run right away since the last check, and removes the requests.
+fn run_requested(config: &Path, scheduler: &Scheduler) {
+    let Ok(entries) = requests_dir(config).and_then(|directory| Ok(directory.read_dir()?)) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().is_some_and(|extension| extension == "tmp") {
+            continue;
+        }
+        let Ok(name) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if let Err(e) = fs::remove_file(&path) {
+            eprintln!("failed to remove the request to run {name}: {e}");
+            continue;
+        }
+        if !scheduler.run_now(&name) {
+            eprintln!("can't run {name} right away, since there's no routine called that");
+        }
+    }
+}
+
+/// The directory that requests to run the routines of the configuration at
+/// `config` right away are left in, which is named after the configuration
+/// so that instances of the program with different ones don't take each
+/// other's requests.
+fn requests_dir(config: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let path = fs::canonicalize(config).unwrap_or_else(|_| config.to_path_buf());
+    // FNV-1a, which unlike the hashers of the standard library is the same
+    // for every version of the program
+    let hash = path
+        .as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3));
+    Ok(config::state_dir()?.join(REQUESTS_DIR).join(format!("{hash:016x}")))
+}
+
+
+/// Lets the program write to the console of the program that started it,
+/// such as a command prompt, since release builds on Windows don't get a
+/// console of their own and their messages would be lost otherwise.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // SAFETY: the call has no preconditions, and fails harmlessly if there's
+    // no console to attach to or the program already has one
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
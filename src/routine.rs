@@ -7,39 +7,111 @@
 //! # Examples
 //! 
 //! Creates a routine to delete all contents of a user's Downloads folder
-//! hourly and creates a [thread] that runs the routine for as long as the
-//! program is allowed to run.
+//! hourly and runs it with a [`Scheduler`] for as long as the program is
+//! allowed to run.
 //! 
 //! ```no_run
 //! use folder_cleaner::fs_utils::FilePattern;
-//! use folder_cleaner::routine::{Routine, spawn_routine};
-//! use std::path::PathBuf;
-//! use time::Duration;
+//! use folder_cleaner::routine::{Routine, Scheduler};
+//! use std::time::Duration;
 //! 
-//! let downloads_routine = Routine {
-//!     directory: PathBuf::from(r"C:\Users\user\Downloads"),
-//!     interval: Duration::HOUR,
-//!     pattern: FilePattern::Any
-//! };
+//! let mut downloads_routine = Routine::new("downloads", r"C:\Users\user\Downloads", FilePattern::Any);
+//! downloads_routine.interval = Duration::from_secs(60 * 60).into();
 //! 
-//! let downloads_thread = spawn_routine(downloads_routine);
-//! downloads_thread.join().unwrap();
+//! let scheduler = Scheduler::new();
+//! scheduler.add(downloads_routine);
+//! scheduler.join();
 //! ```
 
-use std::path::PathBuf;
-use std::thread;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use serde::{Serialize, Deserialize};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::fs_utils::{self, FilePattern};
+use crate::fs_utils::error::ActionError;
+use crate::fs_utils::{self, Action, FilePattern, Journal, MatchContext, MatchOptions};
 
+mod decider;
+mod hook;
+mod idle;
+mod interval;
+mod report;
+mod retry;
+mod schedule;
+mod scheduler;
+mod trigger;
 
-#[derive(Serialize, Deserialize)]
+#[doc(inline)]
+pub use decider::{Decider, Decision};
+#[doc(inline)]
+pub use hook::{Hook, HookEvent, HookFunction, Hooks};
+#[doc(inline)]
+pub use interval::Interval;
+#[doc(inline)]
+pub use report::{Report, ReportFormat};
+#[doc(inline)]
+pub use retry::Retry;
+#[doc(inline)]
+pub use schedule::{ParseScheduleError, QuietHours, Schedule, TimeOfDay, TimeZone, Weekday};
+#[doc(inline)]
+pub use scheduler::{MissedRuns, Overlap, Scheduler, Timing};
+#[doc(inline)]
+pub use trigger::{Space, Trigger};
+
+
+/// How often the routines that purge quarantines run.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 /// A routine to clear a directory based on a pattern.
 /// 
-/// Can be [`run`](Self::run()) to clear the directory once.
-/// Roughly the amount of time represented by the `interval` of a routine
-/// should be allowed to pass between repeated, automated runs.
+/// Can be [`run`](Self::run()) to clear the directory once, and is set up
+/// by the following fields, which are left at their defaults by
+/// [`new`](Self::new()). More fields can be added in later versions, so
+/// routines can only be created with `new` outside of this crate.
+/// 
+/// - The `name` of a routine identifies it in messages about the routine,
+///   such as errors that occur while it's running.
+/// - Roughly the amount of time represented by the [`interval`](Interval),
+///   which can also be drawn at random from a range for each wait, should be
+///   allowed to pass between repeated, automated runs, give or take a random
+///   `jitter` of up to that percentage of it, measured as set by the
+///   [`timing`](Timing).
+/// - A [`schedule`](Schedule) of the times to run at, or running
+///   [`at`](TimeOfDay) the same times every day, replaces the interval. The
+///   automated runs can be limited to certain [`days`](Weekday) of the week,
+///   all in the local time zone unless a [`time_zone`](TimeZone) is set.
+/// - The first automated run can be put off by an `initial_delay`, such as
+///   to let a computer finish starting up, and routines that run `once` stop
+///   after it.
+/// - A routine that's due while it's still running handles the
+///   [`overlap`](Overlap) as set, and likewise for the runs it misses while
+///   the computer is asleep with [`missed_runs`](MissedRuns).
+/// - A [`trigger`](Trigger) can hold automated runs back until they're
+///   needed, such as until the disk is getting full.
+/// - Automated runs can also wait until the user has been `idle` for a
+///   while, on Windows, and until the [`quiet_hours`](QuietHours) are over.
+/// - Routines that aren't `enabled` are kept in the configuration but never
+///   run automatically.
+/// - Whether the `pattern` is `case_sensitive` can be set per routine,
+///   defaulting to that of the [platform](MatchOptions).
+/// - The [`action`](Action) decides what happens to the matching entries,
+///   which are permanently removed by default, and [`hooks`](Hooks) can be
+///   run around each run.
+/// - Routines that are `recursive` clean subdirectories too, down to
+///   `max_depth` levels below the directory if it's set.
+/// - Setting `prune_empty_dirs` removes the directories that are left empty
+///   once the matching entries inside them have been removed.
+/// - A [`report`](Report) of each run can be written for the people using
+///   the directory.
+/// - If the action fails for an entry because of something that's likely to
+///   pass, it can be tried again with a [`retry`](Retry).
+/// - A run that takes longer than the `max_run_duration` stops once it's
+///   done with the entry it's on.
+/// - Applications can also let a [`decider`](Decider) have the final say
+///   about each entry.
 /// 
 /// More details about using this type can be found in the
 /// [`module documentation`](crate::routine).
@@ -52,29 +124,136 @@ use crate::fs_utils::{self, FilePattern};
 /// ```no_run
 /// use folder_cleaner::fs_utils::FilePattern;
 /// use folder_cleaner::routine::Routine;
-/// use std::path::PathBuf;
 /// use std::time::Duration;
 /// 
-/// let desktop_routine = Routine {
-///     directory: PathBuf::from(r"C:\Users\user\Desktop"),
-///     interval: Duration::HOUR,
-///     pattern: FilePattern::Extension("lnk".into())
-/// };
+/// let mut desktop_routine = Routine::new(
+///     "desktop-shortcuts",
+///     r"C:\Users\user\Desktop",
+///     FilePattern::Extension("lnk".into())
+/// );
+/// desktop_routine.interval = Duration::from_secs(60 * 60).into();
 /// 
 /// desktop_routine.run();
 /// ```
+#[non_exhaustive]
 pub struct Routine {
+    pub name: String,
     pub directory: PathBuf,
-    pub interval: Duration,
-    pub pattern: FilePattern
+    #[serde(default, skip_serializing_if = "Interval::is_zero")]
+    pub interval: Interval,
+    pub pattern: FilePattern,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_sensitive: Option<bool>,
+    #[serde(default, skip_serializing_if = "Action::is_default")]
+    pub action: Action,
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub recursive: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<u32>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub prune_empty_dirs: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<Report>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<Retry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub at: Vec<TimeOfDay>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    #[serde(default, alias = "timezone", skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<TimeZone>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter: Option<u32>,
+    #[serde(default, with = "crate::config::duration", skip_serializing_if = "Duration::is_zero")]
+    pub initial_delay: Duration,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub once: bool,
+    #[serde(default, skip_serializing_if = "Timing::is_default")]
+    pub timing: Timing,
+    #[serde(default, skip_serializing_if = "Overlap::is_default")]
+    pub overlap: Overlap,
+    #[serde(default, skip_serializing_if = "MissedRuns::is_default")]
+    pub missed_runs: MissedRuns,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<Trigger>,
+    #[serde(default, with = "crate::config::duration", skip_serializing_if = "Duration::is_zero")]
+    pub idle: Duration,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quiet_hours: Vec<QuietHours>,
+    #[serde(default, with = "crate::config::duration", skip_serializing_if = "Duration::is_zero")]
+    pub max_run_duration: Duration,
+    #[serde(skip)]
+    pub decider: Option<Decider>
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
 impl Routine {
+    /// Creates a routine called `name` that cleans the entries of
+    /// `directory` matching `pattern`.
+    /// 
+    /// The routine is `enabled`, removes the entries for good and has every
+    /// other setting at its default, like a routine in a configuration file
+    /// that only sets those three. In particular, its `interval` is zero, so
+    /// it's never run automatically until one is set.
+    pub fn new<N: Into<String>, D: Into<PathBuf>>(name: N, directory: D, pattern: FilePattern) -> Self {
+        Routine {
+            name: name.into(),
+            directory: directory.into(),
+            interval: Interval::default(),
+            pattern,
+            enabled: true,
+            case_sensitive: None,
+            action: Action::default(),
+            hooks: Hooks::default(),
+            recursive: false,
+            max_depth: None,
+            prune_empty_dirs: false,
+            report: None,
+            retry: None,
+            schedule: None,
+            at: Vec::new(),
+            days: Vec::new(),
+            time_zone: None,
+            jitter: None,
+            initial_delay: Duration::ZERO,
+            once: false,
+            timing: Timing::default(),
+            overlap: Overlap::default(),
+            missed_runs: MissedRuns::default(),
+            trigger: None,
+            idle: Duration::ZERO,
+            quiet_hours: Vec::new(),
+            max_run_duration: Duration::ZERO,
+            decider: None
+        }
+    }
+
     /// Executes a routine once.
     /// 
-    /// Any files and directories in the routine's `directory` matching
-    /// the routine's `pattern` are removed. See [`FilePattern`] and
-    /// [`remove`](fs_utils::remove).
+    /// The routine's `action` is applied to any files and directories in
+    /// the routine's `directory` matching the routine's `pattern`, apart
+    /// from any protected by a [`.cleanerignore`](crate::fs_utils::ignore)
+    /// file. See [`FilePattern`] and [`Action`].
+    /// 
+    /// If the routine is `recursive`, the pattern is also matched against
+    /// the entries of each subdirectory that doesn't match it, with the
+    /// subdirectory as the [directory](MatchContext::directory()) of the
+    /// match. Directories that match are handled along with everything in
+    /// them, so nothing inside them is matched on its own. Links to
+    /// directories aren't followed.
     /// 
     /// # Errors
     /// 
@@ -86,24 +265,479 @@ impl Routine {
     /// 
     /// See the [`module documentation`](crate::routine).
     pub fn run(&self) -> std::io::Result<()> {
-        for item in self.directory.read_dir()? {
-            if let Ok(entry) = item {
-                if self.pattern.matches(&entry.path()) {
-                    fs_utils::remove(&entry.path());
+        self.run_until(&AtomicBool::new(false))
+    }
+
+    /// Executes a routine once, stopping early if `cancelled` is set or once
+    /// it has taken longer than its `max_run_duration`.
+    /// 
+    /// A cancelled run stops looking for matching entries and applying its
+    /// action to them, but finishes what it's in the middle of and what has
+    /// to follow that, such as saving its journal and writing its report.
+    pub(crate) fn run_until(&self, cancelled: &AtomicBool) -> std::io::Result<()> {
+        let deadline = match self.max_run_duration.is_zero() {
+            true => None,
+            false => Instant::now().checked_add(self.max_run_duration)
+        };
+        let timed_out = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        let stopped = || cancelled.load(Ordering::Relaxed) || timed_out();
+
+        self.hooks.run(self, &HookEvent::Start);
+        let entries = match self.directory.read_dir() {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.hooks.run(self, &HookEvent::Error(&e.to_string()));
+                return Err(e);
+            }
+        };
+
+        // a single stubborn entry shouldn't stop the rest of the run
+        let mut matches = Vec::new();
+        let mut errors = Vec::new();
+        self.find_matches(&self.directory, entries, 0, &mut matches, &mut errors, &stopped);
+
+        let mut journal = Journal::new();
+        if let Some(decider) = &self.decider {
+            matches = self.decide(decider, matches, &mut journal, &mut errors);
+        }
+        // a command run once for all the entries can't be stopped part way
+        let batch = match self.action {
+            Action::Command { once: true, .. } => matches.len().max(1),
+            _ => 1
+        };
+        let mut applied = 0;
+        for batch in matches.chunks(batch) {
+            if stopped() {
+                break;
+            }
+            errors.extend(self.action.apply_all_in(&self.directory, batch, &mut journal));
+            applied += batch.len();
+        }
+        matches.truncate(applied);
+        if timed_out() {
+            eprintln!(
+                "{}: stopped the run after {}, the most it's allowed to take",
+                self.name,
+                crate::config::duration::format(self.max_run_duration)
+            );
+        }
+        if let (false, Some(retry)) = (stopped(), &self.retry) {
+            errors = retry.retry(self, &matches, errors, &mut journal, &stopped);
+        }
+        if self.prune_empty_dirs {
+            for path in &matches {
+                if let Err(e) = fs_utils::remove_empty_parents(path, &self.directory) {
+                    errors.push(e.into());
                 }
             }
         }
+        // a run that didn't move anything leaves the last one to be undone
+        if let (false, Some(path)) = (journal.is_empty(), self.journal_path()) {
+            if let Err(e) = journal.save(&path) {
+                eprintln!("{}: failed to save the journal \"{}\": {e}", self.name, path.display());
+            }
+        }
+        if let (false, Some(report)) = (matches.is_empty(), &self.report) {
+            if let Err(e) = report.write(self, &matches, &errors) {
+                eprintln!("{}: failed to write the report: {e}", self.name);
+            }
+        }
+        for e in &errors {
+            eprintln!("{}: {e}", self.name);
+            self.hooks.run(self, &HookEvent::Error(&e.to_string()));
+        }
+        self.hooks.run(self, &HookEvent::Complete { matched: matches.len(), failed: errors.len() });
         Ok(())
     } // fn run()
+
+    /// Adds the entries of `directory` that match the routine's `pattern` to
+    /// `matches`, followed by those in its subdirectories if the routine is
+    /// `recursive` and `directory` is less than `max_depth` levels below
+    /// the routine's own, as it is by `depth`. Stops early once the run is
+    /// `stopped`.
+    fn find_matches(
+        &self,
+        directory: &Path,
+        entries: fs::ReadDir,
+        depth: u32,
+        matches: &mut Vec<PathBuf>,
+        errors: &mut Vec<ActionError>,
+        stopped: &dyn Fn() -> bool
+    ) {
+        let context = MatchContext::new(directory, self.match_options()).with_root(&self.directory);
+        let mut subdirectories = Vec::new();
+        for entry in entries.flatten() {
+            if stopped() {
+                return;
+            }
+            let path = entry.path();
+            if context.is_ignored(&path) || self.action.is_destination(&self.directory, &path) || self.is_report(&path) {
+                continue;
+            }
+            if self.pattern.matches_in(&path, &context) {
+                matches.push(path);
+            }
+            else if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                subdirectories.push(path);
+            }
+        }
+
+        if !self.recursive || self.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            return;
+        }
+        for subdirectory in subdirectories {
+            match subdirectory.read_dir() {
+                Ok(entries) => self.find_matches(&subdirectory, entries, depth + 1, matches, errors, stopped),
+                Err(e) => errors.push(ActionError::new("read", &subdirectory, e))
+            }
+        }
+    }
+
+    /// Checks whether `path` is one of the routine's reports, which it
+    /// mustn't clean up itself.
+    fn is_report(&self, path: &Path) -> bool {
+        self.report.as_ref().is_some_and(|report| report.is_report(self, path))
+    }
+
+    /// Sets the [`Decider`] that decides what happens to each entry the
+    /// routine's `pattern` matches.
+    pub fn with_decider<F>(mut self, decide: F) -> Self
+    where
+        F: Fn(&Path, &Metadata) -> Decision + Send + Sync + 'static
+    {
+        self.decider = Some(Decider::new(decide));
+        self
+    }
+
+    /// Asks `decider` about each entry in `matches`, applying other actions
+    /// straight away and returning the entries the routine's own action
+    /// should be applied to.
+    fn decide(
+        &self,
+        decider: &Decider,
+        matches: Vec<PathBuf>,
+        journal: &mut Journal,
+        errors: &mut Vec<ActionError>
+    ) -> Vec<PathBuf> {
+        let mut apply = Vec::with_capacity(matches.len());
+        for path in matches {
+            // an entry that's gone already doesn't need deciding about
+            let Ok(metadata) = path.symlink_metadata() else {
+                continue;
+            };
+            match decider.decide(&path, &metadata) {
+                Decision::Apply => apply.push(path),
+                Decision::Skip => {}
+                Decision::Use(action) => errors.extend(action.apply_in(&self.directory, &path, journal).err())
+            }
+        }
+        apply
+    }
+
+    /// Moves everything the last run that moved anything moved back, as
+    /// recorded in its [`Journal`].
+    /// 
+    /// Only actions that move entries can be undone, such as moving them to
+    /// the trash on platforms other than Windows, where they have to be
+    /// restored from the Recycle Bin instead. The journal is kept until the
+    /// next run that moves anything, and entries are removed from it once
+    /// they've been restored.
+    /// 
+    /// Returns the errors for the entries that couldn't be restored, which
+    /// can be tried again.
+    /// 
+    /// # Errors
+    /// 
+    /// This function returns an error if the journal can't be read, for
+    /// example because the routine hasn't moved anything yet.
+    pub fn undo_last_run(&self) -> std::io::Result<Vec<ActionError>> {
+        let path = self.journal_path().ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "there's nowhere to keep journals"
+        ))?;
+        let mut journal = Journal::load(&path)?;
+        let errors = journal.restore();
+        // the entries that couldn't be restored are kept for another try
+        match journal.is_empty() {
+            true => fs::remove_file(&path)?,
+            false => journal.save(&path)?
+        }
+        Ok(errors)
+    }
+
+    /// Where the journal of the routine's last run is kept, which is in the
+    /// [`state_dir`](crate::config::state_dir()), if there is one.
+    pub fn journal_path(&self) -> Option<PathBuf> {
+        let name: String = self.name
+            .chars()
+            .map(|c| match c.is_alphanumeric() || c == '-' || c == '_' {
+                true => c,
+                false => '_'
+            })
+            .collect();
+        let directory = crate::config::state_dir().ok()?.join("journals");
+        Some(directory.join(format!("{name}.json")))
+    }
+
+    /// The routine that purges the quarantine of this routine, if its
+    /// `action` is [`Action::Quarantine`] or a [`Action::Fallback`] chain
+    /// with one in it.
+    /// 
+    /// The purging routine runs every hour and permanently removes
+    /// everything that has been in the quarantine for longer than the grace
+    /// period. It doesn't keep to this routine's interval, which can be zero
+    /// for a routine that only watches its directory, or to its schedule,
+    /// its initial delay or running `once`, since the grace period of what a
+    /// single run quarantines has to pass before it can be purged. It's
+    /// named after this routine, with `-purge` at the end, and isn't held
+    /// back by this routine's `trigger`. A [`Scheduler`] runs it alongside
+    /// this one.
+    pub fn purge_routine(&self) -> Option<Routine> {
+        let (directory, grace) = self.action.actions().into_iter().find_map(|action| match action {
+            Action::Quarantine { directory, grace } => Some((directory, grace)),
+            _ => None
+        })?;
+        Some(Routine {
+            interval: PURGE_INTERVAL.into(),
+            enabled: self.enabled,
+            time_zone: self.time_zone,
+            idle: self.idle,
+            quiet_hours: self.quiet_hours.clone(),
+            max_run_duration: self.max_run_duration,
+            ..Routine::new(
+                format!("{}-purge", self.name),
+                self.directory.join(directory),
+                fs_utils::purge_pattern(*grace)
+            )
+        })
+    }
+
+    /// Whether the routine runs at the times of a `schedule` or `at` times
+    /// of day rather than every `interval`.
+    pub fn is_scheduled(&self) -> bool {
+        self.schedule.is_some() || !self.at.is_empty()
+    }
+
+    /// The first time after `time` that the routine is due to run at by its
+    /// `schedule` or `at` times of day, if it has any that come around on
+    /// its `days`.
+    pub fn next_scheduled_run(&self, time: SystemTime) -> Option<SystemTime> {
+        let zone = self.time_zone.as_ref();
+        let scheduled = self.schedule.as_ref().and_then(|schedule| schedule.next_on(time, &self.days, zone));
+        scheduled.into_iter().chain(schedule::next_at(&self.at, &self.days, time, zone)).min()
+    }
+
+    /// How long the routine waits for changes to its directory to settle
+    /// before running, if it watches the directory.
+    pub(crate) fn watch_delay(&self) -> Option<Duration> {
+        match self.trigger {
+            Some(Trigger::Watch(delay)) => Some(delay),
+            _ => None
+        }
+    }
+
+    /// Whether the routine's `trigger` holds, which it always does if it
+    /// doesn't have one.
+    /// 
+    /// # Errors
+    /// 
+    /// This function returns an error if the trigger can't be checked. See
+    /// [`Trigger::holds`].
+    pub fn is_triggered(&self) -> std::io::Result<bool> {
+        let max_depth = match self.recursive {
+            true => self.max_depth,
+            false => Some(0)
+        };
+        self.trigger.as_ref().map_or(Ok(true), |trigger| trigger.holds(&self.directory, max_depth))
+    }
+
+    /// When the [`quiet_hours`](QuietHours) that `time` is in end, or
+    /// `None` if it isn't in any of them.
+    /// 
+    /// Quiet hours that overlap or follow on from each other are treated as
+    /// one.
+    pub fn quiet_until(&self, time: SystemTime) -> Option<SystemTime> {
+        let zone = self.time_zone.as_ref();
+        let mut end = time;
+        // each of the quiet hours can only move the end on once
+        for _ in 0..self.quiet_hours.len() {
+            match self.quiet_hours.iter().filter_map(|hours| hours.end_with(end, zone)).max() {
+                Some(later) => end = later,
+                None => break
+            }
+        }
+        Some(end).filter(|end| *end != time)
+    }
+
+    /// Whether the routine runs automatically on the day of `time` in its
+    /// `time_zone`, which is any day unless its `days` are set.
+    pub fn runs_on(&self, time: SystemTime) -> bool {
+        self.days.is_empty() || self.days.contains(&Weekday::of_in(time, self.time_zone.as_ref()))
+    }
+
+    /// The options the routine's `pattern` is matched with.
+    pub fn match_options(&self) -> MatchOptions {
+        let mut options = MatchOptions::default();
+        if let Some(case_sensitive) = self.case_sensitive {
+            options.case_sensitive = case_sensitive;
+        }
+        options
+    }
 } // impl Routine
 
-/// Spawns a thread that runs a routine repeatedly.
-pub fn spawn_routine(routine: Routine) -> thread::JoinHandle<()> {
-    thread::spawn(move || {
-        loop {
-            routine.run();
 
-            thread::sleep(routine.interval);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    /// An empty directory for a test to clean, called `name`.
+    fn test_dir(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("folder_cleaner-{}-{name}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// Creates an empty file at each of `paths` in `directory`, along with
+    /// the directories they're in.
+    fn create(directory: &Path, paths: &[&str]) {
+        for path in paths {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
         }
-    })
+    }
+
+    /// A routine that cleans `directory` with the settings in `toml`.
+    fn routine(directory: &Path, toml: &str) -> Routine {
+        toml::from_str(&format!("name = \"test\"\ndirectory = '{}'\n{toml}", directory.display())).unwrap()
+    }
+
+    #[test]
+    fn new_routines_are_like_ones_with_only_the_required_keys() {
+        let directory = Path::new("/home/user/Downloads");
+        assert_eq!(Routine::new("test", directory, FilePattern::Any), routine(directory, "pattern = \"any\""));
+    }
+
+    #[test]
+    fn finds_nested_matches_down_to_max_depth() {
+        let directory = test_dir("max-depth");
+        create(&directory, &["0.log", "a/1.log", "a/b/2.log", "a/b/c/3.log", "a/b/keep.txt"]);
+
+        routine(&directory, "pattern = \"ext(log)\"\nrecursive = true\nmax_depth = 2").run().unwrap();
+        assert!(!directory.join("0.log").exists());
+        assert!(!directory.join("a/1.log").exists());
+        assert!(!directory.join("a/b/2.log").exists());
+        assert!(directory.join("a/b/c/3.log").exists());
+        assert!(directory.join("a/b/keep.txt").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn only_finds_top_level_matches_unless_recursive() {
+        let directory = test_dir("not-recursive");
+        create(&directory, &["0.log", "a/1.log"]);
+
+        routine(&directory, "pattern = \"ext(log)\"").run().unwrap();
+        assert!(!directory.join("0.log").exists());
+        assert!(directory.join("a/1.log").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn removes_matching_directories_with_everything_in_them() {
+        let directory = test_dir("matching-directory");
+        create(&directory, &["a/cache/1.log", "a/cache/b/2.txt", "a/keep.txt"]);
+
+        routine(&directory, "pattern = \"glob(cache)\"\nrecursive = true").run().unwrap();
+        assert!(!directory.join("a/cache").exists());
+        assert!(directory.join("a/keep.txt").exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn removes_emptied_directories_after_their_contents() {
+        let directory = test_dir("prune-empty-dirs");
+        create(&directory, &["a/b/c/1.log", "a/b/2.log", "d/3.log", "d/keep.txt"]);
+
+        let toml = "pattern = \"ext(log)\"\nrecursive = true\nprune_empty_dirs = true";
+        routine(&directory, toml).run().unwrap();
+        // the directories are only empty once the entries in them are gone
+        assert!(!directory.join("a").exists());
+        assert!(directory.join("d/keep.txt").exists());
+        assert!(!directory.join("d/3.log").exists());
+        assert!(directory.exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn quarantines_are_purged_hourly() {
+        let watching = routine(Path::new("."), "pattern = \"any\"\ninterval = \"0s\"
+trigger = { watch = \"5s\" }
+action = { quarantine = { grace = \"7d\" } }");
+        let purge = watching.purge_routine().unwrap();
+        assert_eq!(purge.interval, Duration::from_secs(60 * 60).into());
+        assert_eq!(purge.trigger, None);
+
+        // what a routine that runs once quarantines has to be purged later
+        let once = routine(Path::new("."), "pattern = \"any\"\nonce = true\nat = [\"03:00\"]
+action = { quarantine = { grace = \"7d\" } }");
+        let purge = once.purge_routine().unwrap();
+        assert!(!purge.once);
+        assert!(!purge.is_scheduled());
+    }
+
+    #[test]
+    fn quarantines_are_in_the_cleaned_directory_by_default() {
+        let directory = test_dir("quarantine");
+        create(&directory, &["old.log", "keep.txt"]);
+
+        // not loaded from a file, so the quarantine is still relative
+        let routine = routine(&directory, "pattern = \"not glob(keep.txt)\"\naction = { quarantine = { grace = \"7d\" } }");
+        routine.run().unwrap();
+        routine.run().unwrap();
+        assert!(!directory.join("old.log").exists());
+        assert!(directory.join("keep.txt").exists());
+        let batches: Vec<_> = directory.join(".quarantine").read_dir().unwrap().flatten().collect();
+        assert_eq!(batches.len(), 1);
+        assert!(batches[0].path().join("old.log").exists());
+        assert_eq!(routine.purge_routine().unwrap().directory, directory.join(".quarantine"));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn quiet_hours_that_overlap_or_follow_on_are_one() {
+        let routine = routine(Path::new("."), "pattern = \"any\"\ntime_zone = \"UTC\"\nquiet_hours = [
+            { from = \"09:00\", to = \"12:00\" },
+            { from = \"14:00\", to = \"15:00\" },
+            { from = \"11:00\", to = \"14:00\" }
+        ]");
+        // June 1st, 2024 at 10:00 UTC
+        let morning = SystemTime::UNIX_EPOCH + Duration::from_secs(1_717_236_000);
+        let hour = Duration::from_secs(60 * 60);
+        assert_eq!(routine.quiet_until(morning), Some(morning + 5 * hour));
+        assert_eq!(routine.quiet_until(morning + 6 * hour), None);
+    }
+
+    #[test]
+    fn size_triggers_only_count_the_directories_that_are_cleaned() {
+        let directory = test_dir("size-above");
+        create(&directory, &["a/b/c.log"]);
+        fs::write(directory.join("top.log"), [0; 10]).unwrap();
+        fs::write(directory.join("a/1.log"), [0; 10]).unwrap();
+        fs::write(directory.join("a/b/2.log"), [0; 10]).unwrap();
+
+        let trigger = "pattern = \"ext(log)\"\ntrigger = { size_above = 25 }";
+        assert!(!routine(&directory, trigger).is_triggered().unwrap());
+        assert!(!routine(&directory, &format!("{trigger}\nrecursive = true\nmax_depth = 1")).is_triggered().unwrap());
+        assert!(routine(&directory, &format!("{trigger}\nrecursive = true")).is_triggered().unwrap());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
 }
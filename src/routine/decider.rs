@@ -0,0 +1,80 @@
+//! Letting applications decide what happens to each matching entry.
+
+use std::fmt;
+use std::fs::Metadata;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::fs_utils::Action;
+
+
+/// What a [`Decider`] wants to happen to an entry.
+#[non_exhaustive]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Decision {
+    /// Applies the routine's action.
+    Apply,
+    /// Leaves the entry alone.
+    Skip,
+    /// Applies another action instead.
+    Use(Action)
+}
+
+
+/// A function a routine asks what to do with each entry its pattern
+/// matches, such as one that asks the user.
+///
+/// Deciders only exist in code, so they aren't written to or read from
+/// configuration files. Clones share the same function, and deciders are
+/// only equal to their own clones.
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::fs_utils::{Action, FilePattern};
+/// use folder_cleaner::routine::{Decision, Routine};
+///
+/// let routine = Routine::new("downloads", r"C:\Users\user\Downloads", FilePattern::Any);
+///
+/// // keep anything big in the trash rather than removing it for good
+/// let routine = routine.with_decider(|_path, metadata| match metadata.len() > 1_000_000_000 {
+///     true => Decision::Use(Action::Trash),
+///     false => Decision::Apply
+/// });
+/// routine.run().unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Decider {
+    decide: Arc<DecideFn>
+}
+
+type DecideFn = dyn Fn(&Path, &Metadata) -> Decision + Send + Sync;
+
+impl Decider {
+    /// Creates a decider from a function.
+    pub fn new<F>(decide: F) -> Self
+    where
+        F: Fn(&Path, &Metadata) -> Decision + Send + Sync + 'static
+    {
+        Decider { decide: Arc::new(decide) }
+    }
+
+    /// Decides what happens to the entry at `path`.
+    pub fn decide(&self, path: &Path, metadata: &Metadata) -> Decision {
+        (self.decide)(path, metadata)
+    }
+}
+
+impl PartialEq for Decider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.decide, &other.decide)
+    }
+}
+
+impl Eq for Decider {}
+
+impl fmt::Debug for Decider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Decider").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,178 @@
+//! Running commands and functions around the runs of a routine.
+
+use std::fmt;
+use std::process;
+use std::sync::Arc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::routine::Routine;
+
+
+/// The hooks of a routine, which are run when it starts a run, when it
+/// completes one and when something goes wrong.
+///
+/// In configuration files, hooks are commands written as a list of the
+/// program and its arguments, as in
+/// `hooks = { on_error = ["notify-send", "{routine}: {error}"] }`. The
+/// arguments can contain placeholders:
+///
+/// - `{routine}` is replaced with the name of the routine
+/// - `{directory}` is replaced with the directory the routine cleans
+/// - `{matched}` and `{failed}` are replaced with the number of entries
+///   that matched and that the action failed for, in `on_complete`
+/// - `{error}` is replaced with a description of the error, in `on_error`
+///
+/// A run isn't affected by its hooks failing, which is only reported.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Hooks {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_start: Option<Hook>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_complete: Option<Hook>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<Hook>
+}
+
+impl Hooks {
+    /// Checks whether there are no hooks, in which case they don't need to
+    /// be written in configuration files.
+    pub fn is_empty(&self) -> bool {
+        self.on_start.is_none() && self.on_complete.is_none() && self.on_error.is_none()
+    }
+
+    /// Runs the hook for `event`, if there is one.
+    pub(crate) fn run(&self, routine: &Routine, event: &HookEvent<'_>) {
+        let hook = match event {
+            HookEvent::Start => &self.on_start,
+            HookEvent::Complete { .. } => &self.on_complete,
+            HookEvent::Error(_) => &self.on_error
+        };
+        if let Some(Err(e)) = hook.as_ref().map(|hook| hook.call(routine, event)) {
+            eprintln!("{}: hook failed: {e}", routine.name);
+        }
+    }
+}
+
+
+/// What a hook is run for.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent<'a> {
+    /// A run is about to start.
+    Start,
+    /// A run has been completed. Some entries may still have failed.
+    Complete { matched: usize, failed: usize },
+    /// Something went wrong, as described.
+    Error(&'a str)
+}
+
+
+/// A command or a function run by a routine.
+///
+/// Commands can be written in configuration files, while functions only
+/// exist in code, so serializing a routine that has one fails. Clones of a
+/// function share it, and functions are only equal to their own clones.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::{Hook, HookEvent};
+///
+/// let hook = Hook::function(|routine, event| {
+///     if let HookEvent::Complete { matched, .. } = event {
+///         println!("{} handled {matched} entries", routine.name);
+///     }
+/// });
+/// ```
+#[derive(Clone)]
+pub enum Hook {
+    /// Runs a program, given first, with the rest as its arguments.
+    Command(Vec<String>),
+    /// Calls a function.
+    Function(Arc<HookFunction>)
+}
+
+/// The functions that can be used as hooks.
+pub type HookFunction = dyn Fn(&Routine, &HookEvent<'_>) + Send + Sync;
+
+impl Hook {
+    /// Creates a hook that calls `function`.
+    pub fn function<F>(function: F) -> Self
+    where
+        F: Fn(&Routine, &HookEvent<'_>) + Send + Sync + 'static
+    {
+        Hook::Function(Arc::new(function))
+    }
+
+    /// Runs the hook, waiting for a command to finish.
+    fn call(&self, routine: &Routine, event: &HookEvent<'_>) -> Result<(), String> {
+        let command = match self {
+            Self::Function(function) => {
+                function(routine, event);
+                return Ok(());
+            }
+            Self::Command(command) => command
+        };
+        let Some((program, args)) = command.split_first() else {
+            return Err("the command is empty".into());
+        };
+
+        let (matched, failed, error) = match event {
+            HookEvent::Complete { matched, failed } => (matched.to_string(), failed.to_string(), ""),
+            HookEvent::Error(error) => (String::new(), String::new(), *error),
+            HookEvent::Start => (String::new(), String::new(), "")
+        };
+        let args = args.iter().map(|arg| {
+            arg.replace("{routine}", &routine.name)
+                .replace("{directory}", &routine.directory.to_string_lossy())
+                .replace("{matched}", &matched)
+                .replace("{failed}", &failed)
+                .replace("{error}", error)
+        });
+
+        let status = process::Command::new(program)
+            .args(args)
+            .status()
+            .map_err(|e| format!("failed to run {program}: {e}"))?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(format!("{program} exited with {status}"))
+        }
+    }
+}
+
+impl PartialEq for Hook {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Command(a), Self::Command(b)) => a == b,
+            (Self::Function(a), Self::Function(b)) => Arc::ptr_eq(a, b),
+            _ => false
+        }
+    }
+}
+
+impl Eq for Hook {}
+
+impl fmt::Debug for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command(command) => f.debug_tuple("Command").field(command).finish(),
+            Self::Function(_) => f.debug_tuple("Function").finish_non_exhaustive()
+        }
+    }
+}
+
+impl Serialize for Hook {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Command(command) => command.serialize(serializer),
+            Self::Function(_) => Err(serde::ser::Error::custom("function hooks can't be serialized"))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Hook {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Hook::Command)
+    }
+}
@@ -0,0 +1,41 @@
+//! Finding out how long the user has been away from the computer.
+
+use std::time::Duration;
+
+
+/// How long it has been since the user last used the keyboard or the mouse,
+/// or `None` if that can't be found out, which it can only be on Windows.
+pub(crate) fn idle_time() -> Option<Duration> {
+    platform::idle_time()
+}
+
+
+#[cfg(windows)]
+mod platform {
+    use std::mem;
+    use std::time::Duration;
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    pub(super) fn idle_time() -> Option<Duration> {
+        let mut info = LASTINPUTINFO { cbSize: mem::size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+
+        // SAFETY: the size of the info is set as the call requires
+        if unsafe { GetLastInputInfo(&mut info) } == 0 {
+            return None;
+        }
+        // both are milliseconds since the computer started, which wrap
+        // around after about 49 days
+        let idle = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+        Some(Duration::from_millis(idle.into()))
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::time::Duration;
+
+    pub(super) fn idle_time() -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,116 @@
+//! How long routines wait between their runs.
+
+use std::fmt;
+use std::time::Duration;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::config::duration;
+
+
+/// How long a routine waits between its runs, which is either always the
+/// same, written like `"1h"`, or drawn at random from a range for each wait,
+/// written like `"45m..75m"`.
+///
+/// A range keeps runs from always lining up with other jobs that run
+/// periodically, such as backups of the same folder. Durations are written
+/// as described in the [`duration`] module.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::Interval;
+/// use std::time::Duration;
+///
+/// let interval: Interval = serde_json::from_str(r#""45m..75m""#).unwrap();
+/// assert_eq!(interval.min(), Duration::from_secs(45 * 60));
+/// assert_eq!(interval.max(), Duration::from_secs(75 * 60));
+/// assert_eq!(interval.to_string(), "45m..1h15m");
+///
+/// let hourly = Interval::from(Duration::from_secs(60 * 60));
+/// assert_eq!(hourly.to_string(), "1h");
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Interval {
+    min: Duration,
+    max: Duration
+}
+
+impl Interval {
+    /// An interval drawn at random from `min` to `max` for each wait, or
+    /// `None` if `min` is longer than `max`.
+    pub fn between(min: Duration, max: Duration) -> Option<Self> {
+        (min <= max).then_some(Interval { min, max })
+    }
+
+    /// The shortest the interval can be.
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    /// The longest the interval can be.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Whether the interval is always zero.
+    pub fn is_zero(&self) -> bool {
+        self.max.is_zero()
+    }
+
+    /// Halfway between the shortest and the longest the interval can be.
+    pub(crate) fn average(&self) -> Duration {
+        self.min + (self.max - self.min) / 2
+    }
+
+    /// Draws the length of a wait from the range of the interval.
+    pub(crate) fn sample(&self) -> Duration {
+        random_between(self.min, self.max)
+    }
+}
+
+impl From<Duration> for Interval {
+    fn from(duration: Duration) -> Self {
+        Interval { min: duration, max: duration }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.min == self.max {
+            true => f.write_str(&duration::format(self.min)),
+            false => write!(f, "{}..{}", duration::format(self.min), duration::format(self.max))
+        }
+    }
+}
+
+impl Serialize for Interval {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Interval {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let s = String::deserialize(deserializer)?;
+        let Some((min, max)) = s.split_once("..") else {
+            return duration::parse(&s).map(Interval::from).map_err(D::Error::custom);
+        };
+        let min = duration::parse(min).map_err(D::Error::custom)?;
+        let max = duration::parse(max).map_err(D::Error::custom)?;
+        Interval::between(min, max).ok_or_else(|| {
+            D::Error::custom(format!("invalid interval \"{s}\": the start of the range is after its end"))
+        })
+    }
+}
+
+
+/// A random duration from `min` to `max`, which is where every wait that's
+/// drawn at random comes from, so that they're spread out evenly.
+///
+/// The numbers come from a generator for each thread that's only seeded
+/// once, rather than being made up anew for each duration.
+pub(crate) fn random_between(min: Duration, max: Duration) -> Duration {
+    min + (max - min).mul_f64(fastrand::f64())
+}
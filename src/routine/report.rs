@@ -0,0 +1,171 @@
+//! Writing down what the runs of a routine did.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::fs_utils;
+use crate::fs_utils::error::ActionError;
+use crate::routine::Routine;
+
+
+/// How a routine reports what each of its runs did, so that the other
+/// users of a shared folder can see what happened to their files.
+///
+/// A report is written by each run that matches anything, naming the
+/// routine, its pattern and its action and listing the entries the action
+/// was applied to, along with the errors for those it failed for. Reports
+/// are named after the time of the run in UTC, as in
+/// `cleaned-2024-05-01_14-30-00.txt`, with a number added for runs in the
+/// same second like `cleaned-2024-05-01_14-30-00 (2).txt`, and are written
+/// into the `directory`, which is the directory being cleaned unless set.
+/// The routine leaves its own reports alone.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::{Report, ReportFormat};
+///
+/// let report: Report = toml::from_str(r#"format = "json""#).unwrap();
+/// assert_eq!(report.format, ReportFormat::Json);
+/// assert_eq!(report.directory, None);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Report {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "ReportFormat::is_default")]
+    pub format: ReportFormat
+}
+
+/// The formats reports can be written in.
+#[non_exhaustive]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    /// Plain text, for people to read.
+    #[default]
+    Text,
+    /// JSON, for programs to read.
+    Json
+}
+
+impl ReportFormat {
+    /// The extension of reports in this format.
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Json => "json"
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The start of the names of reports.
+const PREFIX: &str = "cleaned-";
+
+impl Report {
+    /// Writes a report of a run of `routine` that applied its action to
+    /// `matches`, returning where it was written.
+    pub(crate) fn write(
+        &self,
+        routine: &Routine,
+        matches: &[PathBuf],
+        errors: &[ActionError]
+    ) -> io::Result<PathBuf> {
+        let now = OffsetDateTime::now_utc();
+        let (date, time) = (
+            format!("{:04}-{:02}-{:02}", now.year(), u8::from(now.month()), now.day()),
+            format!("{:02}:{:02}:{:02}", now.hour(), now.minute(), now.second())
+        );
+        let entries: Vec<ReportEntry> = matches
+            .iter()
+            .map(|path| ReportEntry {
+                // the paths are for the people using the directory, who
+                // know where it is
+                path: path.strip_prefix(&routine.directory).unwrap_or(path).display().to_string(),
+                // actions that handle directories can fail for an entry
+                // inside one
+                error: errors.iter().find(|e| e.path().starts_with(path)).map(ToString::to_string)
+            })
+            .collect();
+
+        let text = match self.format {
+            ReportFormat::Text => {
+                let mut text = format!(
+                    "The routine \"{}\" cleaned \"{}\" on {date} at {time} UTC.\n\
+                     The {} action was applied to the entries matching {}:\n\n",
+                    routine.name,
+                    routine.directory.display(),
+                    routine.action.name(),
+                    routine.pattern
+                );
+                for entry in &entries {
+                    match &entry.error {
+                        Some(error) => text.push_str(&format!("failed  {} ({error})\n", entry.path)),
+                        None => text.push_str(&format!("done    {}\n", entry.path))
+                    }
+                }
+                text
+            }
+            ReportFormat::Json => serde_json::to_string_pretty(&ReportContents {
+                routine: &routine.name,
+                directory: &routine.directory,
+                time: format!("{date}T{time}Z"),
+                pattern: routine.pattern.to_string(),
+                action: routine.action.name(),
+                entries
+            })
+            .map_err(io::Error::other)?
+        };
+
+        let directory = self.directory(routine);
+        fs::create_dir_all(directory)?;
+        let name = format!("{PREFIX}{date}_{}.{}", time.replace(':', "-"), self.format.extension());
+        // runs within the same second get numbered reports of their own
+        let path = fs_utils::free_path(directory, Path::new(&name))?;
+        fs::write(&path, text)?;
+        Ok(path)
+    }
+
+    /// Checks whether `path` is one of the reports of `routine`.
+    pub(crate) fn is_report(&self, routine: &Routine, path: &Path) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.parent() == Some(self.directory(routine))
+            && name.starts_with(PREFIX)
+            // reports written before the format was changed are still its own
+            && path.extension().is_some_and(|extension| {
+                [ReportFormat::Text, ReportFormat::Json].iter().any(|format| extension == format.extension())
+            })
+    }
+
+    /// The directory reports are written into.
+    fn directory<'a>(&'a self, routine: &'a Routine) -> &'a Path {
+        self.directory.as_deref().unwrap_or(&routine.directory)
+    }
+}
+
+
+/// What a JSON report contains.
+#[derive(Serialize)]
+struct ReportContents<'a> {
+    routine: &'a str,
+    directory: &'a Path,
+    time: String,
+    pattern: String,
+    action: &'static str,
+    entries: Vec<ReportEntry>
+}
+
+/// An entry the action of a routine was applied to, with the error if it
+/// failed.
+#[derive(Serialize)]
+struct ReportEntry {
+    path: String,
+    error: Option<String>
+}
@@ -0,0 +1,132 @@
+//! Trying actions again when they fail for reasons that may pass.
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+use crate::fs_utils::error::{self, ActionError};
+use crate::fs_utils::Journal;
+use crate::routine::Routine;
+
+
+/// How long waiting for a retry goes at most without checking whether the
+/// run has been stopped.
+const WAIT_SLICE: Duration = Duration::from_millis(100);
+
+
+/// How a routine tries its action again for entries it failed for because
+/// of something that's likely to pass, such as a file being open in another
+/// program on Windows.
+///
+/// The action is tried up to `attempts` times in all, waiting for the
+/// `backoff` before the first retry, which is a second unless set, and
+/// twice as long before each one after that. The run waits for the retries,
+/// so a long backoff with many attempts keeps the routine busy for a while,
+/// unless it's cancelled or runs out of its `max_run_duration` first.
+/// Entries that failed for other reasons, such as not being allowed to
+/// remove them, aren't tried again. See [`is_transient`](error::is_transient).
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::Retry;
+/// use std::time::Duration;
+///
+/// let retry: Retry = toml::from_str(r#"
+///     attempts = 4
+///     backoff = "500ms"
+/// "#).unwrap();
+/// assert_eq!(retry.backoff, Duration::from_millis(500));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Retry {
+    pub attempts: u32,
+    #[serde(default = "default_backoff", with = "crate::config::duration")]
+    pub backoff: Duration
+}
+
+fn default_backoff() -> Duration {
+    Duration::from_secs(1)
+}
+
+impl Retry {
+    /// Applies the action of `routine` again to the entries in `matches`
+    /// that `errors` show it failed for transiently, returning the errors
+    /// that are left once it has succeeded, run out of attempts or been
+    /// `stopped`.
+    pub(crate) fn retry(
+        &self,
+        routine: &Routine,
+        matches: &[PathBuf],
+        mut errors: Vec<ActionError>,
+        journal: &mut Journal,
+        stopped: &dyn Fn() -> bool
+    ) -> Vec<ActionError> {
+        let mut backoff = self.backoff;
+        for _ in 1..self.attempts {
+            // errors for directories can be about something inside them
+            let failed: Vec<&PathBuf> = matches
+                .iter()
+                .filter(|path| errors.iter().any(|e| is_transient(e) && e.path().starts_with(path)))
+                .collect();
+            if failed.is_empty() {
+                break;
+            }
+
+            if !wait(backoff, stopped) {
+                break;
+            }
+            backoff = backoff.saturating_mul(2);
+            errors.retain(|e| !failed.iter().any(|path| e.path().starts_with(path)));
+            errors.extend(routine.action.apply_all_in(&routine.directory, &failed, journal));
+        }
+        errors
+    }
+}
+
+fn is_transient(e: &ActionError) -> bool {
+    error::is_transient(e.io_source())
+}
+
+/// Waits for `duration` a slice at a time, returning whether it did or
+/// stopped waiting because the run was `stopped`.
+fn wait(duration: Duration, stopped: &dyn Fn() -> bool) -> bool {
+    // backoffs long enough to overflow are waited for until the run stops
+    let end = Instant::now().checked_add(duration);
+    loop {
+        if stopped() {
+            return false;
+        }
+        let left = end.map_or(WAIT_SLICE, |end| end.saturating_duration_since(Instant::now()));
+        if left.is_zero() {
+            return true;
+        }
+        thread::sleep(left.min(WAIT_SLICE));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn retries_stop_waiting_once_the_run_is_stopped() {
+        let routine: Routine = toml::from_str("name = \"test\"\ndirectory = '.'\npattern = \"any\"").unwrap();
+        let path = PathBuf::from("busy.txt");
+        let busy = ActionError::new("remove", &path, io::Error::from(io::ErrorKind::ResourceBusy));
+        let retry = Retry { attempts: 10, backoff: Duration::from_secs(60) };
+
+        // the run is stopped while waiting for the first retry
+        let checks = AtomicU32::new(0);
+        let stopped = || checks.fetch_add(1, Ordering::Relaxed) >= 3;
+        let start = Instant::now();
+        let errors = retry.retry(&routine, &[path], vec![busy], &mut Journal::new(), &stopped);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].io_source().kind(), io::ErrorKind::ResourceBusy);
+    }
+}
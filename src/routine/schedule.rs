@@ -0,0 +1,729 @@
+//! Running routines at calendar times, as described by cron expressions.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::SystemTime;
+use chrono::{
+    DateTime, Datelike, Days, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Utc
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+
+/// The names that can be used for months, in order.
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+/// The names that can be used for days of the week, starting with Sunday.
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How many days ahead the next run is looked for, which is long enough for
+/// schedules that only run on February 29th.
+const SEARCH_DAYS: u64 = 8 * 366;
+
+
+/// Represents errors that occur when a string isn't a valid schedule, time
+/// of day or time zone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseScheduleError {
+    input: String,
+    reason: String,
+    kind: &'static str,
+    example: &'static str
+}
+
+impl ParseScheduleError {
+    fn new(input: &str, reason: String) -> Self {
+        ParseScheduleError { input: input.to_string(), reason, kind: "schedule", example: "0 3 * * sun" }
+    }
+
+    fn time(input: &str, reason: String) -> Self {
+        ParseScheduleError { input: input.to_string(), reason, kind: "time of day", example: "03:00" }
+    }
+
+    fn zone(input: &str, reason: String) -> Self {
+        ParseScheduleError { input: input.to_string(), reason, kind: "time zone", example: "Europe/Berlin" }
+    }
+}
+
+impl fmt::Display for ParseScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} \"{}\": {} (expected something like \"{}\")",
+            self.kind,
+            self.input,
+            self.reason,
+            self.example
+        )
+    }
+}
+
+impl std::error::Error for ParseScheduleError {}
+
+
+/// A schedule of calendar times, written like the schedules of cron.
+///
+/// A schedule has five fields separated by spaces: the minute, the hour, the
+/// day of the month, the month and the day of the week. Each field is `*`
+/// for any value, a value, a range like `1-5`, or a list of those separated
+/// by commas, and ranges and `*` can be followed by a step, as in `*/15`.
+/// Months and days of the week can also be written as the first three
+/// letters of their English names, and both `0` and `7` are Sunday. If both
+/// days are restricted, the schedule matches days that match either of them,
+/// as in cron. The shorthands `@hourly`, `@daily`, `@weekly`, `@monthly`
+/// and `@yearly` can be used as well.
+///
+/// Times are in the local time zone unless the routine has a
+/// [`time_zone`](TimeZone). A time that's skipped when the clocks go forward
+/// is taken to be the same time after the change, and a time that happens
+/// twice when they go back only counts the first time.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::Schedule;
+///
+/// // three in the morning every Sunday
+/// let schedule: Schedule = "0 3 * * SUN".parse().unwrap();
+/// assert_eq!(schedule.to_string(), "0 3 * * SUN");
+/// assert!("0 24 * * *".parse::<Schedule>().is_err());
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of the month is `*`, which makes only the day of the
+    /// week count, and the other way around.
+    any_day: bool,
+    any_weekday: bool
+}
+
+impl Schedule {
+    /// The first time after `time` that's in the schedule.
+    ///
+    /// Returns `None` if the schedule never matches, such as one for
+    /// February 30th.
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        self.next_on(time, &[], None)
+    }
+
+    /// The first time after `time` that's in the schedule and on one of the
+    /// `days` of the week, or any day if there are none, in `zone` or the
+    /// local time zone.
+    pub(crate) fn next_on(&self, time: SystemTime, days: &[Weekday], zone: Option<&TimeZone>) -> Option<SystemTime> {
+        match zone {
+            Some(zone) => self.next_in(&zone.at(time), days).map(SystemTime::from),
+            None => self.next_in(&DateTime::<Local>::from(time), days).map(SystemTime::from)
+        }
+    }
+
+    /// The first time after `time` that's in the schedule and on one of the
+    /// `days` of the week, in the time zone of `time`.
+    pub(crate) fn next_in<Tz: chrono::TimeZone>(&self, time: &DateTime<Tz>, days: &[Weekday]) -> Option<DateTime<Tz>> {
+        let times: Vec<NaiveTime> = (0..24)
+            .filter(|hour| has(self.hours, *hour))
+            .flat_map(|hour| {
+                (0..60)
+                    .filter(|minute| has(self.minutes, *minute))
+                    .filter_map(move |minute| NaiveTime::from_hms_opt(hour, minute, 0))
+            })
+            .collect();
+        next_time(time, &times, |date| self.matches_date(date) && is_on(days, date))
+    }
+
+    /// Checks whether the schedule runs at some point on `date`.
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !has(self.months, date.month()) {
+            return false;
+        }
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday
+        }
+    }
+}
+
+/// The first time after `time` that's one of the `times` of day, which are in
+/// order, on a date that `matches_date`, in the time zone of `time`.
+fn next_time<Tz: chrono::TimeZone>(
+    time: &DateTime<Tz>,
+    times: &[NaiveTime],
+    matches_date: impl Fn(NaiveDate) -> bool
+) -> Option<DateTime<Tz>> {
+    if times.is_empty() {
+        return None;
+    }
+    let zone = time.timezone();
+    let local = time.naive_local();
+    // runs are on whole minutes, so the next one is at the next minute at
+    // the earliest
+    let start = local.with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+
+    for offset in 0..SEARCH_DAYS {
+        let date = start.date().checked_add_days(Days::new(offset))?;
+        if !matches_date(date) {
+            continue;
+        }
+        for candidate in times.iter().map(|time| date.and_time(*time)) {
+            if candidate < start {
+                continue;
+            }
+            match resolve(&zone, candidate) {
+                Some(next) if next > *time => return Some(next),
+                _ => continue
+            }
+        }
+    }
+    None
+}
+
+/// Checks whether `date` is on one of the `days` of the week, which it always
+/// is if there are none.
+fn is_on(days: &[Weekday], date: NaiveDate) -> bool {
+    days.is_empty() || days.iter().any(|day| *day as u32 == date.weekday().num_days_from_monday())
+}
+
+/// The time at which a local time happens in `zone`.
+fn resolve<Tz: chrono::TimeZone>(zone: &Tz, time: NaiveDateTime) -> Option<DateTime<Tz>> {
+    match zone.from_local_datetime(&time) {
+        LocalResult::Single(time) => Some(time),
+        LocalResult::Ambiguous(first, _) => Some(first),
+        // skipped when the clocks went forward, which they do by an hour
+        // almost everywhere
+        LocalResult::None => zone.from_local_datetime(&(time + TimeDelta::hours(1))).earliest()
+    }
+}
+
+/// Checks whether `value` is in the set of values `bits`.
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+impl FromStr for Schedule {
+    type Err = ParseScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason| ParseScheduleError::new(s, reason);
+
+        let expression = match s.trim().to_lowercase().as_str() {
+            "@hourly" => "0 * * * *".to_string(),
+            "@daily" | "@midnight" => "0 0 * * *".to_string(),
+            "@weekly" => "0 0 * * 0".to_string(),
+            "@monthly" => "0 0 1 * *".to_string(),
+            "@yearly" | "@annually" => "0 0 1 1 *".to_string(),
+            other if other.starts_with('@') => return Err(error(format!("unknown shorthand \"{other}\""))),
+            _ => s.trim().to_string()
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(error(format!("expected 5 fields but found {}", fields.len())));
+        };
+
+        let weekdays_bits = parse_field(weekdays, 0, 7, &WEEKDAYS).map_err(error)?;
+        Ok(Schedule {
+            expression: s.trim().to_string(),
+            minutes: parse_field(minutes, 0, 59, &[]).map_err(error)?,
+            hours: parse_field(hours, 0, 23, &[]).map_err(error)?,
+            days: parse_field(days, 1, 31, &[]).map_err(error)?,
+            months: parse_field(months, 1, 12, &MONTHS).map_err(error)?,
+            // Sunday can be 7 as well as 0
+            weekdays: match has(weekdays_bits, 7) {
+                true => (weekdays_bits | 1) & !(1 << 7),
+                false => weekdays_bits
+            },
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*')
+        })
+    }
+}
+
+/// Parses a field whose values go from `min` to `max`, and which can be
+/// written as `names` starting from `min` if there are any.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |text: &str| -> Result<u32, String> {
+        let text = text.to_lowercase();
+        let value = match names.iter().position(|name| *name == text) {
+            Some(index) => index as u32 + min,
+            None => text.parse().map_err(|_| format!("\"{text}\" isn't a number"))?
+        };
+        match (min..=max).contains(&value) {
+            true => Ok(value),
+            false => Err(format!("{value} isn't between {min} and {max}"))
+        }
+    };
+
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse().map_err(|_| format!("\"{step}\" isn't a step"))?),
+            None => (part, 1)
+        };
+        if step == 0 {
+            return Err("a step must not be zero".into());
+        }
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            // a single value with a step goes on to the end of the field
+            None if part.contains('/') => (value(range)?, max),
+            None => {
+                let value = value(range)?;
+                (value, value)
+            }
+        };
+        if start > end {
+            return Err(format!("the range \"{range}\" is backwards"));
+        }
+        for value in (start..=end).step_by(step) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl Serialize for Schedule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.expression)
+    }
+}
+
+impl<'de> Deserialize<'de> for Schedule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+
+/// A time of day, written like `"15:30"`, for routines that run at the same
+/// times every day.
+///
+/// Times are in the local time zone unless the routine has a
+/// [`time_zone`](TimeZone), and handled like those of a [`Schedule`] when
+/// the clocks change.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::TimeOfDay;
+///
+/// let time: TimeOfDay = "3:00".parse().unwrap();
+/// assert_eq!((time.hour(), time.minute()), (3, 0));
+/// assert_eq!(time.to_string(), "03:00");
+/// assert!("24:00".parse::<TimeOfDay>().is_err());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TimeOfDay {
+    time: NaiveTime
+}
+
+impl TimeOfDay {
+    /// Creates a time of day, returning `None` if the hour or the minute is
+    /// out of range.
+    pub fn new(hour: u32, minute: u32) -> Option<Self> {
+        NaiveTime::from_hms_opt(hour, minute, 0).map(|time| TimeOfDay { time })
+    }
+
+    /// The hour, from 0 to 23.
+    pub fn hour(&self) -> u32 {
+        self.time.hour()
+    }
+
+    /// The minute, from 0 to 59.
+    pub fn minute(&self) -> u32 {
+        self.time.minute()
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = ParseScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| ParseScheduleError::time(s, reason.to_string());
+
+        let (hour, minute) = s.trim().split_once(':').ok_or_else(|| error("missing ':'"))?;
+        let number = |text: &str| match !text.is_empty() && text.len() <= 2 {
+            true => text.parse::<u32>().map_err(|_| error("the hour and minute must be numbers")),
+            false => Err(error("the hour and minute must have one or two digits"))
+        };
+        let (hour, minute) = (number(hour)?, number(minute)?);
+        TimeOfDay::new(hour, minute).ok_or_else(|| error("the time must be between 00:00 and 23:59"))
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour(), self.minute())
+    }
+}
+
+impl Serialize for TimeOfDay {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The first time after `time` that's one of the `times` of day, on one of
+/// the `days` of the week or any day if there are none, in `zone` or the
+/// local time zone.
+pub(crate) fn next_at(
+    times: &[TimeOfDay],
+    days: &[Weekday],
+    time: SystemTime,
+    zone: Option<&TimeZone>
+) -> Option<SystemTime> {
+    let mut times: Vec<NaiveTime> = times.iter().map(|time| time.time).collect();
+    times.sort();
+    let matches_date = |date| is_on(days, date);
+    match zone {
+        Some(zone) => next_time(&zone.at(time), &times, matches_date).map(SystemTime::from),
+        None => next_time(&DateTime::<Local>::from(time), &times, matches_date).map(SystemTime::from)
+    }
+}
+
+
+/// A time of day when a routine mustn't run, such as working hours for a
+/// folder that's used during them.
+///
+/// The quiet hours start at `from` on each of the `days`, or on every day if
+/// there are none, and last until the next time it's `to`, which can be on
+/// the next day as in `from = "22:00"` and `to = "06:00"`, or a whole day
+/// later if they're the same. Runs that are due during them are put off
+/// until they end. Times are local unless the routine has a
+/// [`time_zone`](TimeZone), and handled like those of a [`Schedule`] when
+/// the clocks change.
+///
+/// Like any other setting, the quiet hours of a routine replace those in the
+/// [defaults](crate::config::defaults) of its configuration rather than
+/// being added to them.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::{QuietHours, Weekday};
+///
+/// let hours: QuietHours = toml::from_str(r#"
+///     from = "09:00"
+///     to = "18:00"
+///     days = ["mon", "tue", "wed", "thu", "fri"]
+/// "#).unwrap();
+/// assert!(!hours.days.contains(&Weekday::Saturday));
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub from: TimeOfDay,
+    pub to: TimeOfDay,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>
+}
+
+impl QuietHours {
+    /// When the quiet hours that `time` is in end, or `None` if it isn't in
+    /// them.
+    pub fn end(&self, time: SystemTime) -> Option<SystemTime> {
+        self.end_with(time, None)
+    }
+
+    /// When the quiet hours that `time` is in end, in `zone` or the local
+    /// time zone.
+    pub(crate) fn end_with(&self, time: SystemTime, zone: Option<&TimeZone>) -> Option<SystemTime> {
+        match zone {
+            Some(zone) => self.end_in(&zone.at(time)).map(SystemTime::from),
+            None => self.end_in(&DateTime::<Local>::from(time)).map(SystemTime::from)
+        }
+    }
+
+    /// When the quiet hours that `time` is in end, in the time zone of
+    /// `time`.
+    pub(crate) fn end_in<Tz: chrono::TimeZone>(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let local = time.naive_local();
+        let (date, now) = (local.date(), local.time());
+        let (from, to) = (self.from.time, self.to.time);
+
+        // hours that go past midnight started the day before in the morning
+        let start = match from < to {
+            true => Some(date).filter(|_| from <= now && now < to),
+            false if now >= from => Some(date),
+            false if now < to => date.pred_opt(),
+            false => None
+        };
+        let start = start.filter(|start| is_on(&self.days, *start))?;
+        let end = match from < to {
+            true => start,
+            false => start.succ_opt()?
+        };
+        resolve(&time.timezone(), end.and_time(to)).filter(|end| end > time)
+    }
+}
+
+
+/// A day of the week, written as its English name or the first three letters
+/// of it, as in `"sat"`.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::Weekday;
+///
+/// let days: Vec<Weekday> = serde_json::from_str(r#"["sat", "sunday"]"#).unwrap();
+/// assert_eq!(days, [Weekday::Saturday, Weekday::Sunday]);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum Weekday {
+    #[serde(rename = "mon", alias = "monday")]
+    Monday,
+    #[serde(rename = "tue", alias = "tuesday")]
+    Tuesday,
+    #[serde(rename = "wed", alias = "wednesday")]
+    Wednesday,
+    #[serde(rename = "thu", alias = "thursday")]
+    Thursday,
+    #[serde(rename = "fri", alias = "friday")]
+    Friday,
+    #[serde(rename = "sat", alias = "saturday")]
+    Saturday,
+    #[serde(rename = "sun", alias = "sunday")]
+    Sunday
+}
+
+impl Weekday {
+    /// The day of the week `time` is on in the local time zone.
+    pub fn of(time: SystemTime) -> Self {
+        Self::of_in(time, None)
+    }
+
+    /// The day of the week `time` is on in `zone` or the local time zone.
+    pub(crate) fn of_in(time: SystemTime, zone: Option<&TimeZone>) -> Self {
+        const DAYS: [Weekday; 7] = [
+            Weekday::Monday,
+            Weekday::Tuesday,
+            Weekday::Wednesday,
+            Weekday::Thursday,
+            Weekday::Friday,
+            Weekday::Saturday,
+            Weekday::Sunday
+        ];
+        let weekday = match zone {
+            Some(zone) => zone.at(time).weekday(),
+            None => DateTime::<Local>::from(time).weekday()
+        };
+        DAYS[weekday.num_days_from_monday() as usize]
+    }
+}
+
+
+/// A time zone from the tz database, written like `"Europe/Berlin"` or
+/// `"UTC"`, for a routine whose times shouldn't depend on the time zone the
+/// computer is set to, such as on a laptop that travels or a server that's
+/// set to UTC.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::TimeZone;
+///
+/// let zone: TimeZone = "America/New_York".parse().unwrap();
+/// assert_eq!(zone.to_string(), "America/New_York");
+/// assert!("Mars/Olympus_Mons".parse::<TimeZone>().is_err());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TimeZone {
+    zone: chrono_tz::Tz
+}
+
+impl TimeZone {
+    /// `time` in the time zone.
+    fn at(&self, time: SystemTime) -> DateTime<chrono_tz::Tz> {
+        DateTime::<Utc>::from(time).with_timezone(&self.zone)
+    }
+}
+
+impl FromStr for TimeZone {
+    type Err = ParseScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().parse() {
+            Ok(zone) => Ok(TimeZone { zone }),
+            Err(_) => Err(ParseScheduleError::zone(s, "there's no time zone with that name".to_string()))
+        }
+    }
+}
+
+impl fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.zone.name())
+    }
+}
+
+impl Serialize for TimeZone {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeZone {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone as _;
+    use chrono_tz::Europe::Berlin;
+
+    /// The time it is in Berlin at that local time, which has to happen
+    /// exactly once.
+    fn berlin(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<chrono_tz::Tz> {
+        Berlin.with_ymd_and_hms(year, month, day, hour, minute, 0).single().unwrap()
+    }
+
+    fn at(times: &[&str]) -> Vec<NaiveTime> {
+        times.iter().map(|time| time.parse::<TimeOfDay>().unwrap().time).collect()
+    }
+
+    #[test]
+    fn times_of_day_are_local() {
+        let next = next_time(&berlin(2024, 6, 1, 12, 0), &at(&["03:00", "15:00"]), |_| true);
+        assert_eq!(next, Some(berlin(2024, 6, 1, 15, 0)));
+        let next = next_time(&berlin(2024, 6, 1, 15, 0), &at(&["03:00", "15:00"]), |_| true);
+        assert_eq!(next, Some(berlin(2024, 6, 2, 3, 0)));
+    }
+
+    #[test]
+    fn skipped_times_move_forward() {
+        // the clocks went from 02:00 to 03:00 on March 31st, 2024
+        let next = next_time(&berlin(2024, 3, 30, 12, 0), &at(&["02:30"]), |_| true).unwrap();
+        assert_eq!(next, berlin(2024, 3, 31, 3, 30));
+        assert_eq!(next.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap());
+
+        // and the day after, it's back to normal
+        let next = next_time(&next, &at(&["02:30"]), |_| true);
+        assert_eq!(next, Some(berlin(2024, 4, 1, 2, 30)));
+    }
+
+    #[test]
+    fn ambiguous_times_count_the_first_time() {
+        // the clocks went from 03:00 back to 02:00 on October 27th, 2024
+        let next = next_time(&berlin(2024, 10, 26, 12, 0), &at(&["02:30"]), |_| true).unwrap();
+        assert_eq!(next.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 10, 27, 0, 30, 0).unwrap());
+
+        // the second time it's 02:30 doesn't count
+        let next = next_time(&next, &at(&["02:30"]), |_| true).unwrap();
+        assert_eq!(next.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 10, 28, 1, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn schedules_handle_the_clocks_changing() {
+        let hourly: Schedule = "0 * * * *".parse().unwrap();
+        let next = hourly.next_in(&berlin(2024, 3, 31, 1, 30), &[]).unwrap();
+        assert_eq!(next, berlin(2024, 3, 31, 3, 0));
+
+        // of the two hours from 02:00 to 03:00, only the first has a run at
+        // 02:00, at midnight in UTC, and 03:00 comes two hours after it
+        let start = Utc.with_ymd_and_hms(2024, 10, 26, 23, 30, 0).unwrap().with_timezone(&Berlin);
+        let runs: Vec<_> = std::iter::successors(Some(start), |time| hourly.next_in(time, &[]))
+            .skip(1)
+            .take(3)
+            .map(|time| time.with_timezone(&Utc).hour())
+            .collect();
+        assert_eq!(runs, [0, 2, 3]);
+    }
+
+    #[test]
+    fn times_can_be_limited_to_days() {
+        // June 1st, 2024 was a Saturday
+        let next = next_time(&berlin(2024, 6, 1, 12, 0), &at(&["03:00"]), |date| is_on(&[Weekday::Tuesday], date));
+        assert_eq!(next, Some(berlin(2024, 6, 4, 3, 0)));
+    }
+
+    fn quiet(from: &str, to: &str, days: &[Weekday]) -> QuietHours {
+        QuietHours { from: from.parse().unwrap(), to: to.parse().unwrap(), days: days.to_vec() }
+    }
+
+    #[test]
+    fn quiet_hours_can_go_past_midnight() {
+        let night = quiet("22:00", "06:00", &[]);
+        assert_eq!(night.end_in(&berlin(2024, 6, 1, 23, 0)), Some(berlin(2024, 6, 2, 6, 0)));
+        assert_eq!(night.end_in(&berlin(2024, 6, 2, 5, 0)), Some(berlin(2024, 6, 2, 6, 0)));
+        assert_eq!(night.end_in(&berlin(2024, 6, 2, 6, 0)), None);
+        assert_eq!(night.end_in(&berlin(2024, 6, 2, 12, 0)), None);
+    }
+
+    #[test]
+    fn quiet_hours_belong_to_the_day_they_start_on() {
+        // June 1st, 2024 was a Saturday
+        let saturday_night = quiet("22:00", "06:00", &[Weekday::Saturday]);
+        assert_eq!(saturday_night.end_in(&berlin(2024, 6, 2, 5, 0)), Some(berlin(2024, 6, 2, 6, 0)));
+        assert_eq!(saturday_night.end_in(&berlin(2024, 6, 2, 23, 0)), None);
+    }
+
+    #[test]
+    fn quiet_hours_from_a_time_to_itself_last_a_day() {
+        let day = quiet("09:00", "09:00", &[]);
+        assert_eq!(day.end_in(&berlin(2024, 6, 1, 10, 0)), Some(berlin(2024, 6, 2, 9, 0)));
+        assert_eq!(day.end_in(&berlin(2024, 6, 1, 8, 0)), Some(berlin(2024, 6, 1, 9, 0)));
+    }
+
+    #[test]
+    fn quiet_hours_handle_the_clocks_changing() {
+        // the clocks went from 02:00 to 03:00 on March 31st, 2024, so the
+        // hours end at 03:30 instead, an hour after they started
+        let hours = quiet("01:00", "02:30", &[]);
+        let end = hours.end_in(&berlin(2024, 3, 31, 1, 30)).unwrap();
+        assert_eq!(end, berlin(2024, 3, 31, 3, 30));
+        assert_eq!(end.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap());
+    }
+
+    fn zone(name: &str) -> TimeZone {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn time_zones_decide_the_times_of_day() {
+        let times = ["03:00".parse().unwrap()];
+        let noon = SystemTime::from(berlin(2024, 6, 1, 12, 0));
+        let next = next_at(&times, &[], noon, Some(&zone("Europe/Berlin")));
+        assert_eq!(next, Some(berlin(2024, 6, 2, 3, 0).into()));
+        let next = next_at(&times, &[], noon, Some(&zone("UTC")));
+        assert_eq!(next, Some(Utc.with_ymd_and_hms(2024, 6, 2, 3, 0, 0).unwrap().into()));
+    }
+
+    #[test]
+    fn time_zones_decide_the_day_of_the_week() {
+        // late on Saturday in UTC is already Sunday in Berlin
+        let time = SystemTime::from(Utc.with_ymd_and_hms(2024, 6, 1, 23, 30, 0).unwrap());
+        assert_eq!(Weekday::of_in(time, Some(&zone("UTC"))), Weekday::Saturday);
+        assert_eq!(Weekday::of_in(time, Some(&zone("Europe/Berlin"))), Weekday::Sunday);
+    }
+
+    #[test]
+    fn time_zones_are_written_by_name() {
+        assert_eq!(zone(" Europe/Berlin "), zone("Europe/Berlin"));
+        let zone: TimeZone = serde_json::from_str("\"Europe/Berlin\"").unwrap();
+        assert_eq!(serde_json::to_string(&zone).unwrap(), "\"Europe/Berlin\"");
+        assert!(serde_json::from_str::<TimeZone>("\"Europe/Atlantis\"").is_err());
+    }
+}
@@ -0,0 +1,914 @@
+//! Running many routines repeatedly on a few threads.
+
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use crate::routine::{idle, interval::random_between, Routine};
+
+
+/// How many routines a scheduler runs at the same time unless told otherwise.
+const DEFAULT_WORKERS: usize = 4;
+
+/// How long the scheduler waits at most before checking the clock again,
+/// since waiting doesn't count time spent asleep.
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How far the system clock has to get ahead of the time the scheduler has
+/// waited for to count as the computer having been asleep.
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// How late a run has to be to count as missed.
+const MISSED_THRESHOLD: Duration = Duration::from_secs(60);
+
+
+/// Runs routines repeatedly, all from a single thread that waits for the
+/// next one that's due and a small pool of threads that run them.
+///
+/// A routine is run once as soon as it's added, or once its `initial_delay`
+/// has passed if it has one, and then again every time its `interval`, with
+/// its `jitter`, has passed since the last run finished, or since it started
+/// if its [`timing`](Timing) is fixed. Routines that are
+/// [scheduled](Routine::is_scheduled()) instead run at each of their times,
+/// including the first, going by the system clock so that changes to it and
+/// time spent asleep are caught up with. Runs only happen on a routine's
+/// `days`, if they're set, while it isn't [`pause`](Self::pause())d and
+/// when its [`trigger`](crate::routine::Trigger) holds. Routines that watch
+/// their directory also run once changes to it have settled. A routine that
+/// waits for the user to be `idle` puts its runs off until they have been,
+/// on Windows, and runs due in a routine's
+/// [`quiet_hours`](crate::routine::QuietHours) are put off until they end.
+/// Routines that run `once` stop after their first run. A routine is never
+/// run twice at the same time, and what happens when it's due while it's
+/// still running is up to its [`overlap`](Overlap), and what happens to
+/// runs it missed while the computer was asleep is up to its
+/// [`missed_runs`](MissedRuns).
+///
+/// Up to as many routines as there are workers run at the same time, which is
+/// four unless set [`with_workers`](Self::with_workers()). Routines that
+/// become due while every worker is busy wait for one to be free.
+///
+/// The routines can be replaced with [`update`](Self::update()), for example
+/// after the configuration has been reloaded. Routines that haven't changed
+/// keep running undisturbed, while removed or changed routines are stopped
+/// and new ones are started. Dropping the scheduler stops it once the runs
+/// in progress have finished.
+///
+/// # Panics
+///
+/// Creating a scheduler panics if the operating system fails to create a
+/// thread, just like [`thread::spawn`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use folder_cleaner::config::Config;
+/// use folder_cleaner::routine::Scheduler;
+///
+/// let config = Config::load(r"C:\Users\user\folder_cleaner.toml").unwrap();
+/// let scheduler = Scheduler::new();
+/// scheduler.update(config.into_routines());
+///
+/// // take a break from cleaning the downloads while working in them
+/// scheduler.pause("downloads");
+/// ```
+pub struct Scheduler {
+    shared: Arc<Shared>,
+    threads: Vec<thread::JoinHandle<()>>
+}
+
+/// What the interval of a routine is measured from.
+#[non_exhaustive]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Timing {
+    /// The end of the last run, so that long runs push the ones after them
+    /// back.
+    #[default]
+    AfterRun,
+    /// The start of the last run, so that runs keep to fixed ticks. What
+    /// happens when a run is still going at the next tick is up to the
+    /// [`Overlap`] of the routine, and ticks that pass in the meantime
+    /// after that are skipped.
+    Fixed
+}
+
+impl Timing {
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What happens when a routine is due while it's still running, so that two
+/// of its runs never clean its directory at the same time.
+#[non_exhaustive]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overlap {
+    /// The routine runs again as soon as the run in progress has finished,
+    /// however many times it was due in the meantime.
+    #[default]
+    Queue,
+    /// The run that was due is skipped.
+    Skip,
+    /// The run in progress is cancelled, and the routine runs again once it
+    /// has stopped. Cancelled runs stop looking for entries and applying
+    /// their action, but finish what they're in the middle of.
+    Cancel
+}
+
+impl Overlap {
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What happens to the runs a routine missed, such as while the computer was
+/// asleep or after the system clock was put forward.
+///
+/// A run counts as missed once it's more than a minute late, and intervals
+/// count the time the computer was asleep as having passed. Routines that
+/// run once always make up for their run.
+#[non_exhaustive]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRuns {
+    /// The routine runs right away, once for all the runs it missed.
+    #[default]
+    Once,
+    /// The missed runs are skipped, and the routine waits for its next one.
+    Skip,
+    /// The routine runs once for each run it missed, one after another, which
+    /// can be many for a short interval.
+    Each
+}
+
+impl MissedRuns {
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+
+/// What the threads of a scheduler share.
+struct Shared {
+    state: Mutex<State>,
+    /// Notified whenever the routines change or a run finishes.
+    changed: Condvar,
+    /// The watchers of the entries whose routines watch their directories,
+    /// which are kept apart from the state since they lock it themselves
+    /// and can't be dropped while it's locked.
+    watchers: Mutex<Vec<(u64, RecommendedWatcher)>>
+}
+
+#[derive(Default)]
+struct State {
+    entries: Vec<Entry>,
+    /// The ids of the entries whose routines are running, including those
+    /// that have been removed since they started.
+    running: Vec<u64>,
+    next_id: u64,
+    stopping: bool
+}
+
+/// A routine of a scheduler and when it's due.
+struct Entry {
+    id: u64,
+    routine: Arc<Routine>,
+    /// `None` if it isn't known yet, such as while a routine whose interval
+    /// is measured from the end of its runs is running, or if it's never due
+    /// again, such as once it has run if it runs `once`.
+    due: Option<Due>,
+    /// The tick of the current or last run, for routines with fixed timing.
+    tick: Instant,
+    paused: bool,
+    /// Cancels the run in progress, if there is one.
+    run: Option<Arc<AtomicBool>>,
+    /// Whether the routine runs again as soon as it isn't running.
+    queued: bool,
+    /// How many missed runs are left to make up for.
+    backlog: u32,
+    /// Whether the routine has been asked to run right away.
+    forced: bool
+}
+
+impl Entry {
+    /// Starts a run of the routine, which is `on_time` unless it was queued,
+    /// returning what cancels it.
+    fn start(&mut self, on_time: bool) -> Arc<AtomicBool> {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.run = Some(Arc::clone(&cancelled));
+        self.queued = false;
+
+        let is_timed = self.routine.is_scheduled() || self.routine.timing == Timing::Fixed;
+        match (self.routine.once, is_timed) {
+            (true, _) => self.due = None,
+            // the next run is due whether or not this one has finished
+            (false, true) if on_time => self.reschedule(),
+            (false, true) => {}
+            (false, false) => self.due = None
+        }
+        cancelled
+    }
+
+    /// Updates the routine once its run has finished.
+    fn finish(&mut self) {
+        self.run = None;
+        // finished routines are kept, so that updates don't start them again
+        match self.routine.once {
+            true => {
+                self.due = None;
+                self.queued = false;
+            }
+            false if self.backlog > 0 => {
+                self.backlog -= 1;
+                self.queued = true;
+            }
+            false if self.due.is_none() && !self.queued => self.reschedule(),
+            false => {}
+        }
+    }
+
+    /// Handles the routine being late for the run it's due for according to
+    /// its [`MissedRuns`], returning whether it should run now.
+    fn catch_up(&mut self) -> bool {
+        let Some(due) = self.due else {
+            return true;
+        };
+        // a routine that runs once has nothing to make up for and nothing
+        // that could be skipped without losing its only run
+        if self.routine.once || due.late() < MISSED_THRESHOLD {
+            return true;
+        }
+        match self.routine.missed_runs {
+            MissedRuns::Once => true,
+            MissedRuns::Skip => {
+                self.reschedule();
+                false
+            }
+            MissedRuns::Each => {
+                self.backlog = self.missed(due);
+                true
+            }
+        }
+    }
+
+    /// Puts the run the routine is due for off until its quiet hours are
+    /// over or the user could have been idle for long enough, returning
+    /// whether it did.
+    fn put_off(&mut self) -> bool {
+        let due = match self.routine.quiet_until(SystemTime::now()) {
+            Some(end) => Due::At(end),
+            None => match idle_left(&self.routine) {
+                Some(left) => Due::After(Instant::now() + left),
+                None => return false
+            }
+        };
+        // the run is put off rather than skipped
+        self.queued = false;
+        self.due = Some(due);
+        true
+    }
+
+    /// How many more runs the routine has missed since the one that was
+    /// `due`.
+    fn missed(&self, due: Due) -> u32 {
+        match due {
+            Due::After(_) if self.routine.interval.average().is_zero() => 0,
+            Due::After(_) => {
+                let missed = due.late().as_nanos() / self.routine.interval.average().as_nanos();
+                u32::try_from(missed).unwrap_or(u32::MAX)
+            }
+            Due::At(mut time) => {
+                let now = SystemTime::now();
+                let mut missed = 0;
+                while let Some(next) = self.routine.next_scheduled_run(time).filter(|next| *next <= now) {
+                    missed += 1;
+                    time = next;
+                }
+                missed
+            }
+        }
+    }
+
+    /// Moves the times the routine is due at that don't count time spent
+    /// asleep forward by the time `slept`.
+    fn wake(&mut self, slept: Duration) {
+        if let Some(Due::After(instant)) = self.due {
+            self.due = Some(Due::After(instant.checked_sub(slept).unwrap_or(instant)));
+        }
+        self.tick = self.tick.checked_sub(slept).unwrap_or(self.tick);
+    }
+
+    /// Handles the routine being due while it's still running, according to
+    /// its [`Overlap`].
+    fn overlap(&mut self) {
+        match self.routine.overlap {
+            Overlap::Queue => self.queued = true,
+            Overlap::Skip => {}
+            Overlap::Cancel => {
+                if let Some(run) = &self.run {
+                    run.store(true, Ordering::Relaxed);
+                }
+                self.queued = true;
+            }
+        }
+        self.reschedule();
+    }
+
+    /// Works out when the routine is due next, after it has started a run
+    /// or its run has been skipped.
+    fn reschedule(&mut self) {
+        let routine = &self.routine;
+        if routine.is_scheduled() {
+            self.due = routine.next_scheduled_run(SystemTime::now()).map(Due::At);
+            return;
+        }
+        if routine.interval.is_zero() && routine.watch_delay().is_some() {
+            // the routine only runs when its directory changes
+            self.due = None;
+            return;
+        }
+
+        let now = Instant::now();
+        let interval = routine.interval.sample();
+        let start = match routine.timing {
+            Timing::AfterRun => now,
+            Timing::Fixed => {
+                let mut tick = self.tick + interval;
+                if tick <= now && !interval.is_zero() {
+                    // the ticks that have passed were taken up by the run
+                    let missed = (now - tick).as_nanos() / interval.as_nanos() + 1;
+                    tick += interval.saturating_mul(u32::try_from(missed).unwrap_or(u32::MAX));
+                }
+                self.tick = tick;
+                tick - interval
+            }
+        };
+        let interval = jittered(interval, routine.jitter.unwrap_or(0));
+        self.due = Some(Due::After(start + interval));
+    }
+}
+
+/// When a routine is due to run.
+#[derive(Clone, Copy)]
+enum Due {
+    /// Once an instant has passed, for routines that run every interval.
+    After(Instant),
+    /// At a time of the system clock, for scheduled routines.
+    At(SystemTime)
+}
+
+impl Due {
+    /// The first time `routine` is due, right after it's added.
+    fn first(routine: &Routine) -> Option<Due> {
+        match routine.is_scheduled() {
+            true => routine.next_scheduled_run(SystemTime::now() + routine.initial_delay).map(Due::At),
+            false => Some(Due::After(Instant::now() + routine.initial_delay))
+        }
+    }
+
+    /// How long is left until the time is due.
+    fn left(self) -> Duration {
+        match self {
+            Due::After(instant) => instant.saturating_duration_since(Instant::now()),
+            Due::At(time) => time.duration_since(SystemTime::now()).unwrap_or_default()
+        }
+    }
+
+    /// How long ago the time was due.
+    fn late(self) -> Duration {
+        match self {
+            Due::After(instant) => Instant::now().saturating_duration_since(instant),
+            Due::At(time) => SystemTime::now().duration_since(time).unwrap_or_default()
+        }
+    }
+}
+
+/// A run of the routine of an entry, handed to a worker along with what
+/// cancels it and whether it was asked for rather than due.
+type Job = (u64, Arc<Routine>, Arc<AtomicBool>, bool);
+
+impl Scheduler {
+    /// Creates a scheduler with no routines, which runs up to four of them
+    /// at the same time.
+    pub fn new() -> Self {
+        Self::with_workers(DEFAULT_WORKERS)
+    }
+
+    /// Creates a scheduler with no routines, which runs up to `workers` of
+    /// them at the same time, or one if `workers` is zero.
+    pub fn with_workers(workers: usize) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::default(),
+            changed: Condvar::new(),
+            watchers: Mutex::default()
+        });
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let mut threads = Vec::with_capacity(workers.max(1) + 1);
+        for i in 0..workers.max(1) {
+            let (shared, receiver) = (Arc::clone(&shared), Arc::clone(&receiver));
+            let builder = thread::Builder::new().name(format!("folder_cleaner-worker-{i}"));
+            threads.push(builder.spawn(move || work(&shared, &receiver)).expect("failed to spawn thread"));
+        }
+        let dispatcher = Arc::clone(&shared);
+        let builder = thread::Builder::new().name("folder_cleaner-scheduler".into());
+        threads.push(builder.spawn(move || dispatch(&dispatcher, jobs)).expect("failed to spawn thread"));
+
+        Scheduler { shared, threads }
+    }
+
+    /// Adds a routine to the scheduler, which starts it unless it isn't
+    /// `enabled`.
+    pub fn add(&self, routine: Routine) {
+        if routine.enabled {
+            let added = self.shared.lock().add(routine, false);
+            self.shared.changed.notify_all();
+            self.watch(vec![added]);
+        }
+    }
+
+    /// Replaces the routines of the scheduler with `routines`.
+    ///
+    /// Only routines that differ from the current ones are stopped or
+    /// started, and stopping a routine waits for its run to finish if it's
+    /// in the middle of one. Routines that aren't `enabled` are left out,
+    /// and routines that quarantine entries are joined by their
+    /// [`purge_routine`](Routine::purge_routine()). Routines that run `once`
+    /// and have finished aren't run again unless they change, and paused
+    /// routines stay paused even if they change.
+    pub fn update(&self, routines: Vec<Routine>) {
+        let mut new: Vec<_> = routines.into_iter().filter(|r| r.enabled).collect();
+        let purges: Vec<_> = new.iter().filter_map(Routine::purge_routine).collect();
+        for purge in &purges {
+            // nothing may have been quarantined yet, which isn't an error
+            let _ = fs::create_dir_all(&purge.directory);
+        }
+        new.extend(purges);
+
+        let mut state = self.shared.lock();
+        let mut stopped = Vec::new();
+        let mut paused = Vec::new();
+        state.entries.retain(|entry| match new.iter().position(|r| *r == *entry.routine) {
+            Some(i) => {
+                new.swap_remove(i);
+                true
+            }
+            None => {
+                stopped.push(entry.id);
+                if entry.paused {
+                    paused.push(entry.routine.name.clone());
+                }
+                false
+            }
+        });
+        // even once gone, a routine that's running could clash with the
+        // routine replacing it
+        while state.running.iter().any(|id| stopped.contains(id)) {
+            state = self.shared.wait(state);
+        }
+
+        let mut added = Vec::with_capacity(new.len());
+        for routine in new {
+            let is_paused = paused.contains(&routine.name);
+            added.push(state.add(routine, is_paused));
+        }
+        drop(state);
+        self.shared.changed.notify_all();
+        self.unwatch(&stopped);
+        self.watch(added);
+    }
+
+    /// Removes the routine called `name`, returning whether there was one.
+    ///
+    /// Like [`update`](Self::update()), this waits for the routine's run to
+    /// finish if it's in the middle of one.
+    pub fn remove(&self, name: &str) -> bool {
+        let mut state = self.shared.lock();
+        let Some(index) = state.entries.iter().position(|entry| entry.routine.name == name) else {
+            return false;
+        };
+        let id = state.entries.remove(index).id;
+        while state.running.contains(&id) {
+            state = self.shared.wait(state);
+        }
+        drop(state);
+        self.unwatch(&[id]);
+        true
+    }
+
+    /// Pauses the routine called `name`, so that it skips its runs until
+    /// it's [`resume`](Self::resume())d, returning whether there's one.
+    ///
+    /// The routine keeps to its interval or schedule while paused, so
+    /// resuming it doesn't make it run right away. A run that has already
+    /// started is allowed to finish.
+    pub fn pause(&self, name: &str) -> bool {
+        self.set_paused(name, true)
+    }
+
+    /// Lets the paused routine called `name` run again from its next run on,
+    /// returning whether there's one.
+    pub fn resume(&self, name: &str) -> bool {
+        self.set_paused(name, false)
+    }
+
+    /// Runs the routine called `name` right away, returning whether there's
+    /// one.
+    ///
+    /// The routine runs even if it isn't due, if it's paused or if it would
+    /// be held back, such as by its `days`, its quiet hours or its trigger.
+    /// If it's in the middle of a run, it runs again once that's done. A
+    /// routine whose interval is measured from the end of its runs waits for
+    /// it again from the end of this one.
+    pub fn run_now(&self, name: &str) -> bool {
+        let mut state = self.shared.lock();
+        let mut found = false;
+        for entry in state.entries.iter_mut().filter(|entry| entry.routine.name == name) {
+            entry.forced = true;
+            found = true;
+        }
+        drop(state);
+        self.shared.changed.notify_all();
+        found
+    }
+
+    /// Whether the routine called `name` is paused, or `None` if there's no
+    /// routine with that name.
+    pub fn is_paused(&self, name: &str) -> Option<bool> {
+        let state = self.shared.lock();
+        state.entries.iter().find(|entry| entry.routine.name == name).map(|entry| entry.paused)
+    }
+
+    /// The names of the routines of the scheduler.
+    pub fn names(&self) -> Vec<String> {
+        self.shared.lock().entries.iter().map(|entry| entry.routine.name.clone()).collect()
+    }
+
+    /// Starts watching the directories of the routines of the `added`
+    /// entries that watch them.
+    fn watch(&self, added: Vec<(u64, Arc<Routine>)>) {
+        let mut watchers = self.shared.lock_watchers();
+        for (id, routine) in added {
+            // a routine that runs once has nothing to wait for after its run
+            let Some(delay) = routine.watch_delay().filter(|_| !routine.once) else {
+                continue;
+            };
+            match watch(Arc::downgrade(&self.shared), id, delay, &routine) {
+                Ok(watcher) => watchers.push((id, watcher)),
+                Err(e) => eprintln!(
+                    "{} failed to watch \"{}\": {}",
+                    routine.name,
+                    routine.directory.display(),
+                    e
+                )
+            }
+        }
+    }
+
+    /// Stops watching the directories of the `removed` entries.
+    fn unwatch(&self, removed: &[u64]) {
+        self.shared.lock_watchers().retain(|(id, _)| !removed.contains(id));
+    }
+
+    fn set_paused(&self, name: &str, paused: bool) -> bool {
+        let mut state = self.shared.lock();
+        let mut found = false;
+        for entry in state.entries.iter_mut().filter(|entry| entry.routine.name == name) {
+            entry.paused = paused;
+            found = true;
+        }
+        found
+    }
+
+    /// Stops the scheduler and waits for the runs in progress to finish.
+    pub fn stop(mut self) {
+        self.shared.stop();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+
+    /// Waits until none of the routines of the scheduler are going to run
+    /// again and then stops it.
+    ///
+    /// Routines run until removed unless they run `once`, so this normally
+    /// blocks forever.
+    pub fn join(self) {
+        let mut state = self.shared.lock();
+        let may_run = |entry: &Entry| {
+            entry.due.is_some() || (entry.routine.watch_delay().is_some() && !entry.routine.once)
+        };
+        while !state.running.is_empty() || state.entries.iter().any(may_run) {
+            state = self.shared.wait(state);
+        }
+        drop(state);
+        self.stop();
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.shared.stop();
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // a panicking routine is caught before it can poison the state
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        self.changed.wait(state).unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_watchers(&self) -> MutexGuard<'_, Vec<(u64, RecommendedWatcher)>> {
+        self.watchers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn stop(&self) {
+        self.lock().stopping = true;
+        self.changed.notify_all();
+        let watchers = mem::take(&mut *self.lock_watchers());
+        drop(watchers);
+    }
+}
+
+impl State {
+    /// Adds an entry for `routine`, returning its id and routine.
+    fn add(&mut self, routine: Routine, paused: bool) -> (u64, Arc<Routine>) {
+        let due = Due::first(&routine);
+        let tick = Instant::now() + routine.initial_delay;
+        let routine = Arc::new(routine);
+        self.entries.push(Entry {
+            id: self.next_id,
+            routine: Arc::clone(&routine),
+            due,
+            tick,
+            paused,
+            run: None,
+            queued: false,
+            backlog: 0,
+            forced: false
+        });
+        self.next_id += 1;
+        (self.next_id - 1, routine)
+    }
+}
+
+
+/// Watches the directory of `routine` for changes, making the entry `id`
+/// due once they've settled for `delay`.
+fn watch(shared: Weak<Shared>, id: u64, delay: Duration, routine: &Routine) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        // removals are usually the routine's own doing, and reading entries
+        // doesn't change them
+        let Ok(event) = event else {
+            return;
+        };
+        if event.kind.is_access() || event.kind.is_remove() {
+            return;
+        }
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+
+        let mut state = shared.lock();
+        if let Some(entry) = state.entries.iter_mut().find(|entry| entry.id == id && entry.run.is_none()) {
+            entry.due = Some(Due::After(Instant::now() + delay));
+        }
+        drop(state);
+        shared.changed.notify_all();
+    })?;
+    let mode = match routine.recursive {
+        true => RecursiveMode::Recursive,
+        false => RecursiveMode::NonRecursive
+    };
+    watcher.watch(&routine.directory, mode)?;
+    Ok(watcher)
+}
+
+
+/// Hands the routines of a scheduler to its workers as they become due,
+/// until the scheduler stops.
+fn dispatch(shared: &Shared, jobs: mpsc::Sender<Job>) {
+    let mut state = shared.lock();
+    let mut last_check = (Instant::now(), SystemTime::now());
+    while !state.stopping {
+        // the monotonic clock can stand still while the computer is asleep,
+        // which the system clock doesn't
+        let now = (Instant::now(), SystemTime::now());
+        let waited = now.1.duration_since(last_check.1).unwrap_or_default();
+        let slept = Some(waited.saturating_sub(now.0 - last_check.0))
+            .filter(|slept| *slept >= SLEEP_THRESHOLD);
+        last_check = now;
+
+        let mut wait = CLOCK_CHECK_INTERVAL;
+        let mut ready = Vec::new();
+        for entry in &mut state.entries {
+            if let Some(slept) = slept {
+                entry.wake(slept);
+            }
+            let on_time = entry.due.is_some_and(|due| due.left().is_zero());
+            let is_running = entry.run.is_some();
+            if entry.forced && !is_running {
+                entry.forced = false;
+                let cancelled = entry.start(false);
+                ready.push((entry.id, Arc::clone(&entry.routine), cancelled, true));
+            }
+            else if on_time && is_running {
+                entry.overlap();
+            }
+            else if on_time || (entry.queued && !is_running) {
+                if entry.paused || !entry.routine.runs_on(SystemTime::now()) {
+                    // skipped runs still count, so that the routine keeps to
+                    // its interval
+                    entry.queued = false;
+                    entry.backlog = 0;
+                    if on_time || entry.due.is_none() {
+                        entry.reschedule();
+                    }
+                }
+                else {
+                    // queued runs aren't late for anything
+                    let runs = !on_time || entry.catch_up();
+                    if runs && !entry.put_off() {
+                        let cancelled = entry.start(on_time);
+                        ready.push((entry.id, Arc::clone(&entry.routine), cancelled, false));
+                    }
+                }
+            }
+
+            if let Some(due) = entry.due {
+                wait = wait.min(due.left());
+            }
+        }
+
+        for job in ready {
+            state.running.push(job.0);
+            // the workers only stop once this thread has
+            let _ = jobs.send(job);
+        }
+        state = shared.changed.wait_timeout(state, wait).unwrap_or_else(PoisonError::into_inner).0;
+    }
+}
+
+/// Runs the routines handed to a worker of a scheduler, until the scheduler
+/// stops.
+fn work(shared: &Shared, jobs: &Mutex<mpsc::Receiver<Job>>) {
+    loop {
+        let job = jobs.lock().unwrap_or_else(PoisonError::into_inner).recv();
+        let Ok((id, routine, cancelled, forced)) = job else {
+            break;
+        };
+        // runs that were waiting for a worker are dropped along with the
+        // scheduler
+        if shared.lock().stopping {
+            break;
+        }
+
+        // runs that aren't triggered still count, like those of paused
+        // routines
+        let triggered = match forced {
+            true => Ok(true),
+            false => routine.is_triggered()
+        };
+        match triggered {
+            Ok(true) => run(&routine, &cancelled),
+            Ok(false) => {}
+            Err(e) => eprintln!(
+                "{} failed to check its trigger for \"{}\": {}",
+                routine.name,
+                routine.directory.display(),
+                e
+            )
+        }
+
+        let mut state = shared.lock();
+        state.running.retain(|running| *running != id);
+        if let Some(entry) = state.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.finish();
+        }
+        drop(state);
+        shared.changed.notify_all();
+    }
+}
+
+/// How much longer the user has to be idle for before `routine` runs, if
+/// it's waiting for them to be.
+fn idle_left(routine: &Routine) -> Option<Duration> {
+    if routine.idle.is_zero() {
+        return None;
+    }
+    let idle = idle::idle_time()?;
+    Some(routine.idle.saturating_sub(idle)).filter(|left| !left.is_zero())
+}
+
+/// Runs a routine for a worker, reporting what went wrong.
+fn run(routine: &Routine, cancelled: &AtomicBool) {
+    // a routine whose hooks or decider panic shouldn't take the worker down
+    // with it
+    match panic::catch_unwind(AssertUnwindSafe(|| routine.run_until(cancelled))) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => eprintln!(
+            "{} failed to clean \"{}\": {}",
+            routine.name,
+            routine.directory.display(),
+            e
+        ),
+        Err(_) => eprintln!("{} panicked while cleaning \"{}\"", routine.name, routine.directory.display())
+    }
+    if cancelled.load(Ordering::Relaxed) {
+        eprintln!("{}: cancelled a run that was still going when the next one was due", routine.name);
+    }
+}
+
+/// Makes `interval` randomly up to `percent` percent longer or shorter, so
+/// that routines started together don't all run at the same moment.
+fn jittered(interval: Duration, percent: u32) -> Duration {
+    let spread = interval.mul_f64(f64::from(percent.min(100)) / 100.0);
+    random_between(interval - spread, interval.saturating_add(spread))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An entry for a routine with the settings in `toml`, which is `due`
+    /// when that works out for the routine.
+    fn entry_for(toml: &str, due: impl FnOnce(&Routine) -> Due) -> Entry {
+        let routine: Routine = toml::from_str(&format!("name = \"test\"\ndirectory = '.'\npattern = \"ext(log)\"\n{toml}")).unwrap();
+        let mut state = State::default();
+        state.add(routine, false);
+        let mut entry = state.entries.remove(0);
+        entry.due = Some(due(&entry.routine));
+        entry
+    }
+
+    /// A time that was `secs` seconds ago.
+    fn ago(secs: u64) -> Instant {
+        Instant::now() - Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn missed_runs_of_intervals_go_by_the_average_interval() {
+        let entry = entry_for("interval = \"10s..30s\"", |_| Due::After(ago(75)));
+        assert_eq!(entry.missed(entry.due.unwrap()), 3);
+
+        let entry = entry_for("interval = \"0s\"", |_| Due::After(ago(75)));
+        assert_eq!(entry.missed(entry.due.unwrap()), 0);
+    }
+
+    #[test]
+    fn missed_runs_of_schedules_go_by_the_times_that_have_passed() {
+        // the first time after four days ago is at most three days ago, so
+        // it's followed by the three times since
+        let four_days_ago = SystemTime::now() - Duration::from_secs(4 * 24 * 60 * 60);
+        let entry = entry_for("at = [\"03:00\"]\ntime_zone = \"UTC\"", |routine| {
+            Due::At(routine.next_scheduled_run(four_days_ago).unwrap())
+        });
+        assert_eq!(entry.missed(entry.due.unwrap()), 3);
+    }
+
+    #[test]
+    fn late_runs_are_caught_up_with_as_set() {
+        let mut entry = entry_for("interval = \"20s\"\nmissed_runs = \"each\"", |_| Due::After(ago(70)));
+        assert!(entry.catch_up());
+        assert_eq!(entry.backlog, 3);
+
+        let mut entry = entry_for("interval = \"20s\"\nmissed_runs = \"skip\"", |_| Due::After(ago(70)));
+        assert!(!entry.catch_up());
+        assert!(entry.due.is_some_and(|due| !due.left().is_zero()));
+
+        // runs that are only a little late haven't missed anything
+        let mut entry = entry_for("interval = \"20s\"\nmissed_runs = \"skip\"", |_| Due::After(ago(10)));
+        assert!(entry.catch_up());
+    }
+
+    #[test]
+    fn jitter_stays_within_its_percentage() {
+        let interval = Duration::from_secs(100);
+        let waits: Vec<_> = (0..100).map(|_| jittered(interval, 10)).collect();
+        assert!(waits.iter().all(|wait| (90..=110).contains(&wait.as_secs())));
+        // the waits are actually drawn at random
+        assert!(waits.iter().any(|wait| *wait != waits[0]));
+        assert_eq!(jittered(interval, 0), interval);
+    }
+}
@@ -0,0 +1,259 @@
+//! Conditions that have to hold for routines to run.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::config::size;
+
+
+/// A condition that has to hold for a routine to run automatically, so that
+/// it cleans its directory when that's needed rather than whenever it's due.
+///
+/// The trigger is checked each time the routine is due by its `interval` or
+/// its schedule, and the run is skipped if the trigger doesn't hold, so a
+/// routine with a short interval runs soon after it's needed. A routine that
+/// runs `once` only checks its trigger once, and running a routine by hand
+/// doesn't check it at all. Watching the directory instead makes a routine
+/// run whenever something changes in it, without having to check again and
+/// again.
+///
+/// # Examples
+///
+/// ```
+/// use folder_cleaner::routine::{Space, Trigger};
+/// use std::time::Duration;
+///
+/// let trigger: Trigger = toml::from_str(r#"free_space_below = "10%""#).unwrap();
+/// assert_eq!(trigger, Trigger::FreeSpaceBelow(Space::Percent(10)));
+///
+/// let trigger: Trigger = toml::from_str(r#"free_space_below = "5GB""#).unwrap();
+/// assert_eq!(trigger, Trigger::FreeSpaceBelow(Space::Bytes(5_000_000_000)));
+///
+/// let trigger: Trigger = toml::from_str(r#"size_above = "20GB""#).unwrap();
+/// assert_eq!(trigger, Trigger::SizeAbove(20_000_000_000));
+///
+/// let trigger: Trigger = toml::from_str("count_above = 500").unwrap();
+/// assert_eq!(trigger, Trigger::CountAbove(500));
+///
+/// let trigger: Trigger = toml::from_str(r#"watch = "5s""#).unwrap();
+/// assert_eq!(trigger, Trigger::Watch(Duration::from_secs(5)));
+/// ```
+#[non_exhaustive]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    /// Less space than this is free on the volume the directory is on, not
+    /// counting space that's only for administrators.
+    FreeSpaceBelow(Space),
+    /// The files in the directory take up more bytes than this, written like
+    /// `"20GB"`, along with those in its subdirectories if the routine is
+    /// `recursive`, down to its `max_depth`, not counting what symbolic
+    /// links point to.
+    #[serde(with = "crate::config::size")]
+    SizeAbove(u64),
+    /// There are more entries than this directly in the directory, such as
+    /// screenshots piling up.
+    CountAbove(usize),
+    /// Entries are added to or changed in the directory, or in its
+    /// subdirectories if the routine is `recursive`, and then nothing changes
+    /// for this long, so that a routine doesn't run in the middle of a
+    /// download or a batch of screenshots.
+    ///
+    /// Removing entries doesn't count as a change, and neither do changes
+    /// while the routine is running, which are usually its own. The routine
+    /// still runs when it's first started, but only every `interval` as well
+    /// if it has one.
+    #[serde(with = "crate::config::duration")]
+    Watch(Duration)
+}
+
+impl Trigger {
+    /// Whether the trigger holds for a routine that cleans `directory` and
+    /// the subdirectories down to `max_depth` levels below it, or all of
+    /// them if it's `None`.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the directory can't be checked, for
+    /// example because it doesn't exist.
+    pub fn holds(&self, directory: &Path, max_depth: Option<u32>) -> io::Result<bool> {
+        match self {
+            Trigger::FreeSpaceBelow(space) => {
+                let (free, total) = platform::space(directory)?;
+                Ok(match space {
+                    Space::Bytes(bytes) => free < *bytes,
+                    Space::Percent(percent) => u128::from(free) * 100 < u128::from(total) * u128::from(*percent)
+                })
+            }
+            Trigger::SizeAbove(limit) => size_above(directory, max_depth, *limit),
+            Trigger::CountAbove(limit) => Ok(directory.read_dir()?.nth(*limit).is_some()),
+            // the routine only runs when something has changed
+            Trigger::Watch(_) => Ok(true)
+        }
+    }
+}
+
+/// Whether the files in `directory` and its subdirectories down to
+/// `max_depth` levels below it add up to more than `limit` bytes, which
+/// stops adding them up as soon as they do.
+fn size_above(directory: &Path, max_depth: Option<u32>, limit: u64) -> io::Result<bool> {
+    // the sizes aren't kept between checks, since the directory has usually
+    // changed by the next one
+    let mut total: u64 = 0;
+    let mut directories = vec![(directory.to_path_buf(), 0)];
+    while let Some((next, depth)) = directories.pop() {
+        let is_deepest = max_depth.is_some_and(|max_depth| depth >= max_depth);
+        // entries that can't be read, such as those removed since their
+        // directory was, don't count
+        let entries = match next.read_dir() {
+            Ok(entries) => entries,
+            Err(e) if next == directory => return Err(e),
+            Err(_) => continue
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            match metadata.is_dir() {
+                true if is_deepest => {}
+                true => directories.push((entry.path(), depth + 1)),
+                false => total = total.saturating_add(metadata.len())
+            }
+            if total > limit {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+
+/// An amount of space, either a size in bytes written like `"10GB"` or a
+/// percentage of the size of a volume written like `"10%"`.
+///
+/// Sizes are written and read as described in the [`size`] module.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Space {
+    Bytes(u64),
+    /// A percentage from 0 to 100.
+    Percent(u8)
+}
+
+impl fmt::Display for Space {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Space::Bytes(bytes) => f.write_str(&size::format(*bytes)),
+            Space::Percent(percent) => write!(f, "{percent}%")
+        }
+    }
+}
+
+impl Serialize for Space {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Space {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SpaceVisitor)
+    }
+}
+
+struct SpaceVisitor;
+
+impl Visitor<'_> for SpaceVisitor {
+    type Value = Space;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a size like \"500MB\", a percentage like \"10%\" or a number of bytes")
+    }
+
+    fn visit_u64<E: de::Error>(self, bytes: u64) -> Result<Self::Value, E> {
+        Ok(Space::Bytes(bytes))
+    }
+
+    fn visit_i64<E: de::Error>(self, bytes: i64) -> Result<Self::Value, E> {
+        u64::try_from(bytes).map(Space::Bytes).map_err(|_| E::custom("size must not be negative"))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        let Some(percent) = s.trim().strip_suffix('%') else {
+            return size::parse(s).map(Space::Bytes).map_err(E::custom);
+        };
+        match percent.trim_end().parse() {
+            Ok(percent) if percent <= 100 => Ok(Space::Percent(percent)),
+            _ => Err(E::custom(format!(
+                "invalid percentage \"{s}\": expected a whole number from 0 to 100"
+            )))
+        }
+    }
+}
+
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use std::ptr;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    /// The space that's free for the user on the volume `path` is on and the
+    /// size of the volume, in bytes.
+    pub(super) fn space(path: &Path) -> io::Result<(u64, u64)> {
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+        let (mut free, mut total) = (0, 0);
+
+        // SAFETY: the path ends with a null and outlives the call, and the
+        // sizes are written to valid locals
+        match unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut free, &mut total, ptr::null_mut()) } {
+            0 => Err(io::Error::last_os_error()),
+            _ => Ok((free, total))
+        }
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::ffi::CString;
+    use std::io;
+    use std::mem::MaybeUninit;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// The space that's free for unprivileged users on the volume `path` is
+    /// on and the size of the volume, in bytes.
+    // the types of the fields differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    pub(super) fn space(path: &Path) -> io::Result<(u64, u64)> {
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        let mut stats = MaybeUninit::uninit();
+
+        // SAFETY: the path ends with a null and outlives the call, and the
+        // stats are only read once the call has written them
+        let stats: libc::statvfs = match unsafe { libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) } {
+            0 => unsafe { stats.assume_init() },
+            _ => return Err(io::Error::last_os_error())
+        };
+        let block = stats.f_frsize as u64;
+        Ok((
+            (stats.f_bavail as u64).saturating_mul(block),
+            (stats.f_blocks as u64).saturating_mul(block)
+        ))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::io;
+    use std::path::Path;
+
+    pub(super) fn space(_path: &Path) -> io::Result<(u64, u64)> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "free space can't be checked on this platform"))
+    }
+}